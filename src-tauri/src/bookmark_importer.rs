@@ -0,0 +1,396 @@
+use anyhow::{Result, anyhow};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::credential_manager::unique_suffix;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BrowserKind {
+    Firefox,
+    Chromium,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookmarkFolder {
+    pub id: String,
+    pub name: String,
+    /// Names from the top-level folder down, e.g. "Bookmarks Toolbar/Research"
+    pub path: String,
+    pub parent_id: Option<String>,
+    /// Bookmarks directly in this folder
+    pub bookmark_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub title: Option<String>,
+    pub url: String,
+    pub folder_id: String,
+}
+
+/// The bookmarks of one browser profile, flattened into folders and links
+#[derive(Debug)]
+pub struct BookmarkLibrary {
+    pub browser: BrowserKind,
+    pub folders: Vec<BookmarkFolder>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl BookmarkLibrary {
+    /// Loads from a profile directory, a Firefox `places.sqlite` or a Chromium `Bookmarks` file
+    pub fn load(path: &Path) -> Result<Self> {
+        let (browser, file) = locate(path)?;
+        println!("=== BOOKMARKS: Reading {:?} bookmarks from {} ===", browser, file.display());
+
+        let (folders, bookmarks) = match browser {
+            BrowserKind::Firefox => read_firefox(&file)?,
+            BrowserKind::Chromium => read_chromium(&file)?,
+        };
+        Ok(Self::from_parts(browser, folders, bookmarks))
+    }
+
+    fn from_parts(browser: BrowserKind, mut folders: Vec<BookmarkFolder>, bookmarks: Vec<Bookmark>) -> Self {
+        let names: HashMap<String, (String, Option<String>)> = folders.iter()
+            .map(|folder| (folder.id.clone(), (folder.name.clone(), folder.parent_id.clone())))
+            .collect();
+
+        for folder in &mut folders {
+            let mut path = vec![folder.name.clone()];
+            let mut parent = folder.parent_id.clone();
+            // Bounded in case a corrupt database has a cycle
+            while let Some((name, next)) = parent.as_ref().and_then(|id| names.get(id)).filter(|_| path.len() < 64) {
+                path.push(name.clone());
+                parent = next.clone();
+            }
+            path.reverse();
+            folder.path = path.join("/");
+            folder.bookmark_count = bookmarks.iter().filter(|bookmark| bookmark.folder_id == folder.id).count();
+        }
+
+        Self { browser, folders, bookmarks }
+    }
+
+    /// (url, title) for every bookmark in the folder and its subfolders, or in the whole
+    /// library when no folder is given
+    pub fn links(&self, folder_id: Option<&str>) -> Result<Vec<(String, Option<String>)>> {
+        let included = match folder_id {
+            Some(folder_id) => {
+                if !self.folders.iter().any(|folder| folder.id == folder_id) {
+                    return Err(anyhow!("Bookmark folder '{}' not found", folder_id));
+                }
+                let mut included = vec![folder_id.to_string()];
+                let mut index = 0;
+                while index < included.len() {
+                    let children = self.folders.iter()
+                        .filter(|folder| folder.parent_id.as_deref() == Some(included[index].as_str()))
+                        .map(|folder| folder.id.clone())
+                        .collect::<Vec<_>>();
+                    included.extend(children);
+                    index += 1;
+                }
+                Some(included)
+            }
+            None => None,
+        };
+
+        Ok(self.bookmarks.iter()
+            .filter(|bookmark| included.as_ref().map_or(true, |included| included.contains(&bookmark.folder_id)))
+            .map(|bookmark| (bookmark.url.clone(), bookmark.title.clone()))
+            .collect())
+    }
+}
+
+fn locate(path: &Path) -> Result<(BrowserKind, PathBuf)> {
+    if path.is_dir() {
+        if path.join("places.sqlite").is_file() {
+            return Ok((BrowserKind::Firefox, path.join("places.sqlite")));
+        }
+        if path.join("Bookmarks").is_file() {
+            return Ok((BrowserKind::Chromium, path.join("Bookmarks")));
+        }
+        return Err(anyhow!("No Firefox or Chromium bookmarks found in {}", path.display()));
+    }
+    if !path.is_file() {
+        return Err(anyhow!("{} does not exist", path.display()));
+    }
+
+    let is_sqlite = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("sqlite"));
+    Ok((if is_sqlite { BrowserKind::Firefox } else { BrowserKind::Chromium }, path.to_path_buf()))
+}
+
+fn is_web_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+// The database is locked while Firefox runs, so it is read from a copy, together with
+// its write-ahead log holding recent changes
+struct DatabaseCopy(PathBuf);
+
+impl DatabaseCopy {
+    fn new(source: &Path) -> Result<Self> {
+        let copy = Self(std::env::temp_dir().join(format!("grabzilla-places-{}.sqlite", unique_suffix())));
+        std::fs::copy(source, &copy.0)
+            .map_err(|e| anyhow!("Cannot copy {}: {}", source.display(), e))?;
+
+        let wal = PathBuf::from(format!("{}-wal", source.display()));
+        if wal.is_file() {
+            std::fs::copy(&wal, copy.sidecar("-wal"))?;
+        }
+        Ok(copy)
+    }
+
+    fn sidecar(&self, suffix: &str) -> PathBuf {
+        PathBuf::from(format!("{}{}", self.0.display(), suffix))
+    }
+}
+
+impl Drop for DatabaseCopy {
+    fn drop(&mut self) {
+        for path in [self.0.clone(), self.sidecar("-wal"), self.sidecar("-shm")] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+// Built-in roots get their UI names; "tags" only holds copies of bookmarks grouped by tag
+fn firefox_root_name(guid: &str) -> Option<Option<&'static str>> {
+    match guid {
+        "root________" | "tags________" => Some(None),
+        "menu________" => Some(Some("Bookmarks Menu")),
+        "toolbar_____" => Some(Some("Bookmarks Toolbar")),
+        "unfiled_____" => Some(Some("Other Bookmarks")),
+        "mobile______" => Some(Some("Mobile Bookmarks")),
+        _ => None,
+    }
+}
+
+fn read_firefox(file: &Path) -> Result<(Vec<BookmarkFolder>, Vec<Bookmark>)> {
+    let copy = DatabaseCopy::new(file)?;
+    let connection = Connection::open(&copy.0)
+        .map_err(|e| anyhow!("Cannot open {}: {}", file.display(), e))?;
+
+    // type 2 rows are folders, type 1 bookmarks
+    let mut statement = connection.prepare(
+        "SELECT id, parent, title, guid FROM moz_bookmarks WHERE type = 2 ORDER BY parent, position",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, String>(3)?))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let hidden: Vec<i64> = rows.iter()
+        .filter(|(_, _, _, guid)| firefox_root_name(guid) == Some(None))
+        .map(|(id, ..)| *id)
+        .collect();
+    let root = rows.iter().find(|(_, _, _, guid)| guid == "root________").map(|(id, ..)| *id);
+
+    let mut folders: Vec<BookmarkFolder> = Vec::new();
+    for (id, parent, title, guid) in &rows {
+        if hidden.contains(id) {
+            continue;
+        }
+        // Skips everything under the tags root, however deep
+        let mut ancestor = Some(*parent);
+        let mut under_hidden = false;
+        while let Some(current) = ancestor.filter(|current| Some(*current) != root) {
+            if hidden.contains(&current) {
+                under_hidden = true;
+                break;
+            }
+            ancestor = rows.iter().find(|(id, ..)| *id == current).map(|(_, parent, ..)| *parent);
+        }
+        if under_hidden {
+            continue;
+        }
+
+        let name = firefox_root_name(guid).flatten().map(str::to_string)
+            .or_else(|| title.clone())
+            .unwrap_or_default();
+        folders.push(BookmarkFolder {
+            id: id.to_string(),
+            name,
+            path: String::new(),
+            parent_id: Some(*parent).filter(|parent| Some(*parent) != root).map(|parent| parent.to_string()),
+            bookmark_count: 0,
+        });
+    }
+
+    let mut statement = connection.prepare(
+        "SELECT b.parent, COALESCE(NULLIF(b.title, ''), p.title), p.url
+         FROM moz_bookmarks b JOIN moz_places p ON p.id = b.fk
+         WHERE b.type = 1 ORDER BY b.parent, b.position",
+    )?;
+    let bookmarks = statement.query_map([], |row| {
+        Ok(Bookmark {
+            folder_id: row.get::<_, i64>(0)?.to_string(),
+            title: row.get(1)?,
+            url: row.get(2)?,
+        })
+    })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|bookmark| is_web_url(&bookmark.url) && folders.iter().any(|folder| folder.id == bookmark.folder_id))
+        .collect();
+
+    Ok((folders, bookmarks))
+}
+
+#[derive(Deserialize)]
+struct ChromiumBookmarks {
+    roots: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ChromiumNode {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    url: Option<String>,
+    #[serde(default)]
+    children: Vec<ChromiumNode>,
+}
+
+fn read_chromium(file: &Path) -> Result<(Vec<BookmarkFolder>, Vec<Bookmark>)> {
+    let contents = std::fs::read_to_string(file)
+        .map_err(|e| anyhow!("Cannot read {}: {}", file.display(), e))?;
+    let bookmarks: ChromiumBookmarks = serde_json::from_str(&contents)
+        .map_err(|e| anyhow!("Invalid Chromium bookmarks file: {}", e))?;
+
+    let mut folders = Vec::new();
+    let mut links = Vec::new();
+    // "bookmark_bar", "other" and "synced" are the roots; "sync_transaction_version" and
+    // friends aren't folders
+    for key in ["bookmark_bar", "other", "synced"] {
+        let Some(root) = bookmarks.roots.get(key) else { continue };
+        let root: ChromiumNode = serde_json::from_value(root.clone())
+            .map_err(|e| anyhow!("Invalid Chromium bookmarks file: {}", e))?;
+        collect_chromium(root, None, &mut folders, &mut links);
+    }
+    Ok((folders, links))
+}
+
+fn collect_chromium(node: ChromiumNode, parent_id: Option<String>, folders: &mut Vec<BookmarkFolder>, links: &mut Vec<Bookmark>) {
+    if node.kind == "url" {
+        if let (Some(url), Some(folder_id)) = (node.url, parent_id) {
+            if is_web_url(&url) {
+                links.push(Bookmark {
+                    title: Some(node.name).filter(|name| !name.is_empty()),
+                    url,
+                    folder_id,
+                });
+            }
+        }
+        return;
+    }
+
+    folders.push(BookmarkFolder {
+        id: node.id.clone(),
+        name: node.name,
+        path: String::new(),
+        parent_id,
+        bookmark_count: 0,
+    });
+    for child in node.children {
+        collect_chromium(child, Some(node.id.clone()), folders, links);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("grabzilla-bookmarks-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_firefox_places() {
+        let dir = temp_dir("firefox");
+        let connection = Connection::open(dir.join("places.sqlite")).unwrap();
+        connection.execute_batch(r#"
+            CREATE TABLE moz_places (id INTEGER PRIMARY KEY, url TEXT, title TEXT);
+            CREATE TABLE moz_bookmarks (id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER, parent INTEGER, position INTEGER, title TEXT, guid TEXT);
+            INSERT INTO moz_places VALUES (1, 'https://www.youtube.com/watch?v=dQw4w9WgXcQ', 'Page title'), (2, 'https://vimeo.com/76979871', 'Vimeo'), (3, 'place:sort=8', NULL);
+            INSERT INTO moz_bookmarks VALUES
+                (1, 2, NULL, 0, 0, '', 'root________'),
+                (2, 2, NULL, 1, 0, 'menu', 'menu________'),
+                (3, 2, NULL, 1, 1, 'toolbar', 'toolbar_____'),
+                (4, 2, NULL, 1, 2, 'tags', 'tags________'),
+                (5, 2, NULL, 3, 0, 'Research', 'research____'),
+                (6, 2, NULL, 5, 0, 'Talks', 'talks_______'),
+                (7, 2, NULL, 4, 0, 'music', 'tagmusic____'),
+                (10, 1, 1, 5, 0, 'Bookmark title', 'bm1_________'),
+                (11, 1, 2, 6, 0, NULL, 'bm2_________'),
+                (12, 1, 3, 2, 0, 'Recent', 'bm3_________'),
+                (13, 1, 1, 7, 0, NULL, 'bm4_________');
+        "#).unwrap();
+        drop(connection);
+
+        let library = BookmarkLibrary::load(&dir).unwrap();
+        assert_eq!(library.browser, BrowserKind::Firefox);
+        let paths: Vec<&str> = library.folders.iter().map(|folder| folder.path.as_str()).collect();
+        assert_eq!(paths, vec!["Bookmarks Menu", "Bookmarks Toolbar", "Bookmarks Toolbar/Research", "Bookmarks Toolbar/Research/Talks"]);
+
+        // Subfolders are included; the place: query and the tag copy are not
+        let links = library.links(Some("5")).unwrap();
+        assert_eq!(links, vec![
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(), Some("Bookmark title".to_string())),
+            ("https://vimeo.com/76979871".to_string(), Some("Vimeo".to_string())),
+        ]);
+        assert_eq!(library.links(None).unwrap().len(), 2);
+        assert!(library.links(Some("7")).is_err());
+
+        // The copy is cleaned up
+        let leftovers = std::fs::read_dir(std::env::temp_dir()).unwrap()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_name().to_string_lossy().starts_with(&format!("grabzilla-places-{}-", std::process::id())));
+        assert!(!leftovers);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_chromium_bookmarks_keep_titles() {
+        let dir = temp_dir("chromium");
+        std::fs::write(dir.join("Bookmarks"), r#"{
+            "checksum": "0",
+            "roots": {
+                "bookmark_bar": { "id": "1", "name": "Bookmarks bar", "type": "folder", "children": [
+                    { "id": "4", "name": "Research", "type": "folder", "children": [
+                        { "id": "5", "name": "Keynote", "type": "url", "url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ&utm_source=x" },
+                        { "id": "6", "name": "Same video", "type": "url", "url": "https://youtu.be/dQw4w9WgXcQ" },
+                        { "id": "7", "name": "Bookmarklet", "type": "url", "url": "javascript:void(0)" }
+                    ]}
+                ]},
+                "other": { "id": "2", "name": "Other bookmarks", "type": "folder", "children": [
+                    { "id": "8", "name": "", "type": "url", "url": "https://vimeo.com/76979871" }
+                ]},
+                "synced": { "id": "3", "name": "Mobile bookmarks", "type": "folder", "children": [] }
+            },
+            "version": 1
+        }"#).unwrap();
+
+        let library = BookmarkLibrary::load(&dir).unwrap();
+        assert_eq!(library.browser, BrowserKind::Chromium);
+        let research = library.folders.iter().find(|folder| folder.id == "4").unwrap();
+        assert_eq!(research.path, "Bookmarks bar/Research");
+        assert_eq!(research.bookmark_count, 2);
+
+        let extractor = crate::url_parser::URLExtractor::new().unwrap();
+        let result = extractor.extract_titled_urls(&library.links(Some("1")).unwrap()).unwrap();
+        assert_eq!(result.urls.len(), 1);
+        assert_eq!(result.duplicates_removed, 1);
+        assert_eq!(result.urls[0].url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(result.urls[0].title.as_deref(), Some("Keynote"));
+
+        let result = extractor.extract_titled_urls(&library.links(None).unwrap()).unwrap();
+        assert_eq!(result.urls[1].title, None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,15 +1,29 @@
-use crate::url_parser::{URLExtractor, URLExtractionResult, Platform};
+use crate::url_parser::{classify_url, URLExtractor, URLExtractionResult, UrlKind};
 use crate::download_manager::{DownloadManager, DownloadRequest, VideoMetadata};
-use crate::ffmpeg_controller::ConversionFormat;
+use crate::ffmpeg_controller::{chapters_to_csv, chapters_to_edl, Chapter, ClipOptions, ConversionFormat, EncoderCapabilities, FFmpegController, VideoInfo};
 use crate::security_manager::SecurityManager;
 use crate::update_manager::{UpdateManager, UpdateChannel, UpdateInfo};
 use crate::dependency_manager::{DependencyManager, DependencyStatus};
+use crate::thumbnail_manager::ThumbnailManager;
+use crate::metadata_cache::MetadataCache;
+use crate::metadata_prefetcher::{MetadataPrefetcher, PrefetchResult};
+use crate::playlist_expander::{PlaylistEntry, PlaylistOptions};
+use crate::playlist_filter::PlaylistPreview;
+use crate::metadata_sidecar::{write_sidecars, Provenance, SidecarFormat, SidecarTarget};
+use crate::credential_manager::{CredentialManager, CredentialProfile, ResolvedCredentials};
+use crate::proxy_manager::{ProxyManager, ProxyServer, ProxySettings, ProxyTestResult, DEFAULT_TEST_URL};
+use crate::ytdlp_invocation::{YtDlpProfile, YtDlpProfileManager};
+use crate::short_url_resolver::ShortUrlResolver;
+use crate::page_video_finder::PageVideoFinder;
+use crate::bookmark_importer::{BookmarkFolder, BookmarkLibrary};
+use crate::platform_registry::{self, PlatformDescriptor};
+use crate::link_file_reader::{extract_link_text, read_link_file, MAX_FILE_SIZE};
 use anyhow::Result;
 use std::sync::{OnceLock, Arc};
 use tokio::sync::Mutex;
 use std::path::PathBuf;
 use std::fs;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 
 // Global instances
 static URL_EXTRACTOR: OnceLock<URLExtractor> = OnceLock::new();
@@ -17,6 +31,14 @@ static DOWNLOAD_MANAGER: OnceLock<Arc<Mutex<DownloadManager>>> = OnceLock::new()
 static SECURITY_MANAGER: OnceLock<SecurityManager> = OnceLock::new();
 static UPDATE_MANAGER: OnceLock<Arc<Mutex<UpdateManager>>> = OnceLock::new();
 static DEPENDENCY_MANAGER: OnceLock<Arc<Mutex<DependencyManager>>> = OnceLock::new();
+static THUMBNAIL_MANAGER: OnceLock<ThumbnailManager> = OnceLock::new();
+static METADATA_CACHE: OnceLock<MetadataCache> = OnceLock::new();
+static METADATA_PREFETCHER: OnceLock<MetadataPrefetcher> = OnceLock::new();
+static CREDENTIAL_MANAGER: OnceLock<CredentialManager> = OnceLock::new();
+static PROXY_MANAGER: OnceLock<ProxyManager> = OnceLock::new();
+static YTDLP_PROFILE_MANAGER: OnceLock<YtDlpProfileManager> = OnceLock::new();
+static SHORT_URL_RESOLVER: OnceLock<ShortUrlResolver> = OnceLock::new();
+static PAGE_VIDEO_FINDER: OnceLock<PageVideoFinder> = OnceLock::new();
 
 fn get_url_extractor() -> &'static URLExtractor {
     URL_EXTRACTOR.get_or_init(|| {
@@ -24,6 +46,18 @@ fn get_url_extractor() -> &'static URLExtractor {
     })
 }
 
+fn get_short_url_resolver() -> &'static ShortUrlResolver {
+    SHORT_URL_RESOLVER.get_or_init(|| {
+        ShortUrlResolver::new().expect("Failed to initialize short URL resolver")
+    })
+}
+
+fn get_page_video_finder() -> &'static PageVideoFinder {
+    PAGE_VIDEO_FINDER.get_or_init(|| {
+        PageVideoFinder::new().expect("Failed to initialize page video finder")
+    })
+}
+
 fn get_download_manager() -> Arc<Mutex<DownloadManager>> {
     DOWNLOAD_MANAGER.get_or_init(|| {
         let manager = DownloadManager::new().expect("Failed to initialize download manager");
@@ -51,20 +85,159 @@ fn get_dependency_manager(app_handle: &AppHandle) -> Arc<Mutex<DependencyManager
     }).clone()
 }
 
+pub fn get_thumbnail_manager(app_handle: &AppHandle) -> &'static ThumbnailManager {
+    THUMBNAIL_MANAGER.get_or_init(|| {
+        ThumbnailManager::new(app_handle).expect("Failed to initialize thumbnail manager")
+    })
+}
+
+fn get_metadata_cache(app_handle: &AppHandle) -> &'static MetadataCache {
+    METADATA_CACHE.get_or_init(|| {
+        MetadataCache::new(app_handle).expect("Failed to initialize metadata cache")
+    })
+}
+
+fn get_metadata_prefetcher() -> &'static MetadataPrefetcher {
+    METADATA_PREFETCHER.get_or_init(MetadataPrefetcher::default)
+}
+
+fn get_credential_manager(app_handle: &AppHandle) -> &'static CredentialManager {
+    CREDENTIAL_MANAGER.get_or_init(|| {
+        CredentialManager::new(app_handle).expect("Failed to initialize credential manager")
+    })
+}
+
+/// Loads the saved proxy settings, which also puts their routes into effect
+pub fn get_proxy_manager(app_handle: &AppHandle) -> &'static ProxyManager {
+    PROXY_MANAGER.get_or_init(|| {
+        ProxyManager::new(app_handle).expect("Failed to initialize proxy manager")
+    })
+}
+
+/// Applies the user's platforms.json from the config directory. Has to run before anything
+/// looks up a platform
+pub fn load_platform_registry(app_handle: &AppHandle) {
+    match app_handle.path().app_config_dir() {
+        Ok(config_dir) => platform_registry::init(&config_dir.join("platforms.json")),
+        Err(e) => eprintln!("Failed to get app config directory: {}", e),
+    }
+}
+
+/// Loads the saved yt-dlp profile and makes it the one every invocation uses
+pub fn get_ytdlp_profile_manager(app_handle: &AppHandle) -> &'static YtDlpProfileManager {
+    YTDLP_PROFILE_MANAGER.get_or_init(|| {
+        YtDlpProfileManager::new(app_handle).expect("Failed to initialize yt-dlp profile")
+    })
+}
+
+// Explicit profile if one was picked, otherwise whichever profile matches the URL's host
+fn resolve_credentials(app_handle: &AppHandle, profile_id: Option<&str>, url: &str) -> Result<Option<ResolvedCredentials>, String> {
+    get_credential_manager(app_handle)
+        .resolve(profile_id, url)
+        .map_err(|e| format!("Failed to load credentials: {}", e))
+}
+
 pub fn get_dependency_manager_if_initialized() -> Option<Arc<Mutex<DependencyManager>>> {
     DEPENDENCY_MANAGER.get().cloned()
 }
 
+// Local file operations only need FFmpeg, so don't require yt-dlp to be installed for them
+async fn get_ffmpeg_controller() -> Result<FFmpegController, String> {
+    let manager = get_download_manager();
+    {
+        let manager_guard = manager.lock().await;
+        if let Some(ffmpeg) = manager_guard.get_ffmpeg_controller() {
+            return Ok(ffmpeg.clone());
+        }
+    }
+    
+    let mut ffmpeg = FFmpegController::new()
+        .map_err(|e| format!("Failed to create FFmpeg controller: {}", e))?;
+    ffmpeg.initialize()
+        .await
+        .map_err(|e| format!("Failed to initialize FFmpeg: {}", e))?;
+    Ok(ffmpeg)
+}
+
 #[tauri::command]
 pub async fn extract_urls_from_text(text: String) -> Result<URLExtractionResult, String> {
     let extractor = get_url_extractor();
     
-    extractor.extract_urls(&text)
+    extractor.extract_urls_resolved(&text, get_short_url_resolver())
+        .await
         .map_err(|e| format!("Failed to extract URLs: {}", e))
 }
 
+/// Extracts URLs from a dropped file (.txt, .url, .webloc, .html, .csv, .docx or .pdf)
 #[tauri::command]
-pub async fn get_supported_platforms() -> Result<Vec<Platform>, String> {
+pub async fn extract_urls_from_file(path: String) -> Result<URLExtractionResult, String> {
+    let path = get_security_manager().sanitize_file_path(&path)
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+
+    let text = tokio::task::spawn_blocking(move || read_link_file(std::path::Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    extract_urls_from_text(text).await
+}
+
+/// Same as `extract_urls_from_file` for files picked in the webview, which only exposes contents
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn extract_urls_from_file_contents(fileName: String, contents: Vec<u8>) -> Result<URLExtractionResult, String> {
+    if contents.len() as u64 > MAX_FILE_SIZE {
+        return Err(format!("{} is too large to import", fileName));
+    }
+
+    let text = tokio::task::spawn_blocking(move || extract_link_text(&fileName, &contents))
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    extract_urls_from_text(text).await
+}
+
+/// Fetches an article or other web page and returns the videos embedded in it
+#[tauri::command]
+pub async fn find_videos_on_page(url: String) -> Result<URLExtractionResult, String> {
+    get_page_video_finder().find_videos(&url, get_url_extractor())
+        .await
+        .map_err(|e| format!("Failed to find videos on page: {}", e))
+}
+
+/// Folders of a Firefox or Chromium profile's bookmarks, for picking what to import
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn list_bookmark_folders(profilePath: String) -> Result<Vec<BookmarkFolder>, String> {
+    let library = load_bookmarks(profilePath).await?;
+    Ok(library.folders)
+}
+
+/// Imports the bookmarks in a folder (and its subfolders), or the whole profile without one
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn import_bookmarks(profilePath: String, folderId: Option<String>) -> Result<URLExtractionResult, String> {
+    let library = load_bookmarks(profilePath).await?;
+    let links = library.links(folderId.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    get_url_extractor().extract_titled_urls(&links)
+        .map_err(|e| format!("Failed to extract URLs: {}", e))
+}
+
+async fn load_bookmarks(profile_path: String) -> Result<BookmarkLibrary, String> {
+    let path = get_security_manager().sanitize_file_path(&profile_path)
+        .map_err(|e| format!("Invalid profile path: {}", e))?;
+
+    tokio::task::spawn_blocking(move || BookmarkLibrary::load(std::path::Path::new(&path)))
+        .await
+        .map_err(|e| format!("Failed to read bookmarks: {}", e))?
+        .map_err(|e| format!("Failed to read bookmarks: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_supported_platforms() -> Result<Vec<PlatformDescriptor>, String> {
     let extractor = get_url_extractor();
     Ok(extractor.get_supported_platforms())
 }
@@ -83,38 +256,16 @@ pub async fn validate_single_url(url: String) -> Result<bool, String> {
     }
 }
 
+/// Kind of each URL judged from its shape, as the extractor would report it
+#[tauri::command]
+pub async fn classify_urls(urls: Vec<String>) -> Result<Vec<UrlKind>, String> {
+    Ok(urls.iter().map(|url| classify_url(url)).collect())
+}
+
 #[tauri::command] 
 pub async fn clean_url(url: String) -> Result<String, String> {
-    use url::Url;
-    
-    let mut parsed_url = Url::parse(&url)
-        .map_err(|e| format!("Invalid URL: {}", e))?;
-    
-    // Remove tracking parameters
-    let tracking_params = [
-        "utm_source", "utm_medium", "utm_campaign", "utm_content", "utm_term",
-        "fbclid", "gclid", "ref", "referrer", "source", "campaign",
-    ];
-    
-    // Collect pairs to keep
-    let pairs_to_keep: Vec<_> = parsed_url
-        .query_pairs()
-        .filter(|pair| !tracking_params.contains(&pair.0.as_ref()))
-        .map(|(k, v)| (k.to_string(), v.to_string()))
-        .collect();
-    
-    // Clear and rebuild query
-    parsed_url.set_query(None);
-    if !pairs_to_keep.is_empty() {
-        let query_string = pairs_to_keep
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<_>>()
-            .join("&");
-        parsed_url.set_query(Some(&query_string));
-    }
-
-    Ok(parsed_url.to_string())
+    get_url_extractor().clean_url(&url)
+        .map_err(|e| format!("Invalid URL: {}", e))
 }
 
 #[tauri::command]
@@ -124,29 +275,39 @@ pub async fn test_connection() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn get_video_metadata(url: String) -> Result<VideoMetadata, String> {
+pub async fn get_video_metadata(
+    app_handle: AppHandle,
+    url: String,
+    #[allow(non_snake_case)] forceRefresh: Option<bool>,
+) -> Result<VideoMetadata, String> {
     println!("=== GET_VIDEO_METADATA CALLED ===");
     println!("URL: {}", url);
     
-    let manager = get_download_manager();
-    {
-        let mut manager_guard = manager.lock().await;
-        
-        // Initialize download manager if needed
-        println!("Initializing download manager...");
-        if let Err(e) = manager_guard.initialize().await {
-            let error_msg = format!("Failed to initialize download manager: {}", e);
-            println!("Initialization error: {}", error_msg);
-            return Err(error_msg);
+    // Single videos are cached by platform + id, so the same video pasted as a
+    // youtu.be link or a watch URL is only fetched once
+    let cache = get_metadata_cache(&app_handle);
+    let video_id = get_url_extractor().video_id(&url);
+    if let Some((ref platform, ref id)) = video_id {
+        if forceRefresh.unwrap_or(false) {
+            cache.remove(platform, id).await;
+        } else if let Some(metadata) = cache.get(platform, id).await {
+            println!("Using cached metadata for {}:{}", platform.as_str(), id);
+            return Ok(metadata);
         }
-        println!("Download manager initialized successfully");
     }
     
-    let manager_guard = manager.lock().await;
+    // The manager is only locked to find yt-dlp, so downloads carry on while it runs
+    let ytdlp_path = get_initialized_ytdlp_path().await?;
+    let credentials = resolve_credentials(&app_handle, None, &url)?;
     println!("Calling get_video_metadata...");
-    match manager_guard.get_video_metadata(&url).await {
+    match crate::download_manager::fetch_video_metadata(&ytdlp_path, &url, credentials.as_ref()).await {
         Ok(metadata) => {
             println!("Successfully got metadata: title={}", metadata.title);
+            if let Some((ref platform, ref id)) = video_id {
+                if let Err(e) = cache.insert(platform, id, &metadata).await {
+                    eprintln!("Failed to cache metadata: {}", e);
+                }
+            }
             Ok(metadata)
         }
         Err(e) => {
@@ -157,7 +318,74 @@ pub async fn get_video_metadata(url: String) -> Result<VideoMetadata, String> {
     }
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+struct MetadataPrefetchEvent {
+    batch_id: String,
+    #[serde(flatten)]
+    result: PrefetchResult,
+}
+
+#[tauri::command]
+pub async fn prefetch_metadata(
+    app_handle: AppHandle,
+    #[allow(non_snake_case)] batchId: String,
+    urls: Vec<String>,
+) -> Result<usize, String> {
+    println!("=== PREFETCH_METADATA CALLED ===");
+    println!("Batch {} with {} URLs", batchId, urls.len());
+    
+    let emit = |result: PrefetchResult| {
+        let event = MetadataPrefetchEvent { batch_id: batchId.clone(), result };
+        if let Err(e) = app_handle.emit("metadata-prefetched", &event) {
+            eprintln!("Failed to emit prefetched metadata: {}", e);
+        }
+    };
+    
+    // Cached entries are answered straight away and never count against the rate limit
+    let cache = get_metadata_cache(&app_handle);
+    let mut cached = 0;
+    let mut to_fetch = Vec::new();
+    for (index, url) in urls.into_iter().enumerate() {
+        let video_id = get_url_extractor().video_id(&url);
+        let hit = match video_id {
+            Some((ref platform, ref id)) => cache.get(platform, id).await,
+            None => None,
+        };
+        match hit {
+            Some(metadata) => {
+                cached += 1;
+                emit(PrefetchResult { index, url, metadata: Some(metadata), error: None });
+            }
+            None => to_fetch.push((index, url)),
+        }
+    }
+    println!("{} cached, {} to fetch", cached, to_fetch.len());
+    if to_fetch.is_empty() {
+        return Ok(cached);
+    }
+    
+    let ytdlp_path = get_initialized_ytdlp_path().await?;
+    let credential_manager = get_credential_manager(&app_handle);
+    let fetch = move |url: String| {
+        let ytdlp_path = ytdlp_path.clone();
+        async move {
+            let credentials = credential_manager.resolve(None, &url)?;
+            let metadata = crate::download_manager::fetch_video_metadata(&ytdlp_path, &url, credentials.as_ref()).await?;
+            if let Some((platform, id)) = get_url_extractor().video_id(&url) {
+                if let Err(e) = cache.insert(&platform, &id, &metadata).await {
+                    eprintln!("Failed to cache metadata: {}", e);
+                }
+            }
+            Ok(metadata)
+        }
+    };
+    
+    let fetched = get_metadata_prefetcher().prefetch(to_fetch, fetch, emit).await;
+    Ok(cached + fetched)
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn start_download(
     app_handle: tauri::AppHandle,
     id: String,
@@ -167,7 +395,19 @@ pub async fn start_download(
     #[allow(non_snake_case)] outputDir: String,
     convert_format: Option<String>,
     keep_original: Option<bool>,
+    clips: Option<ClipOptions>,
+    split_chapters: Option<bool>,
+    write_thumbnail: Option<bool>,
+    embed_thumbnail: Option<bool>,
+    sidecars: Option<Vec<SidecarFormat>>,
+    credential_profile: Option<String>,
+    keep_unsplit: Option<bool>,
 ) -> Result<(), String> {
+    // Clip times go to yt-dlp and FFmpeg as they are, so bad ranges are refused up front
+    if let Some(ref clips) = clips {
+        clips.validate().map_err(|e| format!("Invalid clip selection: {}", e))?;
+    }
+    let credentials = resolve_credentials(&app_handle, credential_profile.as_deref(), &url)?;
     let manager = get_download_manager();
     {
         let mut manager_guard = manager.lock().await;
@@ -198,6 +438,7 @@ pub async fn start_download(
     // Parse conversion format
     let parsed_convert_format = convert_format.and_then(|f| match f.as_str() {
         "h264" => Some(ConversionFormat::H264HighProfile),
+        "hevc" => Some(ConversionFormat::HEVC),
         "dnxhr" => Some(ConversionFormat::DNxHRSQ),
         "prores" => Some(ConversionFormat::ProResProxy),
         "mp3" => Some(ConversionFormat::MP3Audio),
@@ -212,6 +453,13 @@ pub async fn start_download(
         output_dir: PathBuf::from(outputDir),
         convert_format: parsed_convert_format,
         keep_original: keep_original.unwrap_or(true),
+        clips,
+        split_chapters: split_chapters.unwrap_or(false),
+        keep_unsplit: keep_unsplit.unwrap_or(false),
+        write_thumbnail: write_thumbnail.unwrap_or(false),
+        embed_thumbnail: embed_thumbnail.unwrap_or(false),
+        sidecars: sidecars.unwrap_or_default(),
+        credentials,
     };
     
     let mut manager_guard = manager.lock().await;
@@ -220,6 +468,56 @@ pub async fn start_download(
         .map_err(|e| format!("Failed to queue download: {}", e))
 }
 
+#[tauri::command]
+pub async fn regenerate_sidecars(
+    app_handle: AppHandle,
+    targets: Vec<SidecarTarget>,
+    formats: Vec<SidecarFormat>,
+) -> Result<Vec<String>, String> {
+    println!("=== REGENERATE_SIDECARS CALLED ===");
+    println!("Files: {}, formats: {:?}", targets.len(), formats);
+    
+    if formats.is_empty() {
+        return Err("No sidecar formats selected".to_string());
+    }
+    
+    // yt-dlp runs once per file, so the manager isn't held while it does
+    let ytdlp_path = {
+        let manager = get_download_manager();
+        let mut manager_guard = manager.lock().await;
+        if let Err(e) = manager_guard.initialize().await {
+            return Err(format!("Failed to initialize download manager: {}", e));
+        }
+        manager_guard.get_ytdlp_path().cloned()
+            .ok_or_else(|| "yt-dlp not initialized".to_string())?
+    };
+    
+    let security_manager = get_security_manager();
+    let mut written = Vec::new();
+    for target in targets {
+        let media_file = security_manager.validate_media_file(&target.file_path)
+            .map_err(|e| format!("Invalid file path: {}", e))?;
+        
+        let credentials = resolve_credentials(&app_handle, None, &target.url)?;
+        let metadata = crate::download_manager::fetch_video_metadata(&ytdlp_path, &target.url, credentials.as_ref())
+            .await
+            .map_err(|e| format!("Failed to get video metadata for {}: {}", target.url, e))?;
+        
+        // The file's modification time is the closest record of when it was downloaded
+        let downloaded_at = fs::metadata(&media_file)
+            .and_then(|m| m.modified())
+            .map(chrono::DateTime::<chrono::Utc>::from)
+            .unwrap_or_else(|_| chrono::Utc::now());
+        let provenance = Provenance::with_timestamp(&target.url, downloaded_at);
+        
+        let paths = write_sidecars(&media_file, &metadata, &provenance, &formats)
+            .map_err(|e| format!("Failed to write sidecars for {}: {}", target.file_path, e))?;
+        written.extend(paths.into_iter().map(|p| p.to_string_lossy().to_string()));
+    }
+    
+    Ok(written)
+}
+
 #[tauri::command]
 pub async fn set_max_concurrent_downloads(max: usize) -> Result<(), String> {
     let manager = get_download_manager();
@@ -327,6 +625,53 @@ pub async fn validate_network_url(url: String) -> Result<bool, String> {
     Ok(security_manager.validate_network_access(&url))
 }
 
+#[tauri::command]
+pub async fn get_proxy_settings(app_handle: AppHandle) -> Result<ProxySettings, String> {
+    Ok(get_proxy_manager(&app_handle).get_settings())
+}
+
+#[tauri::command]
+pub async fn save_proxy_settings(app_handle: AppHandle, settings: ProxySettings) -> Result<ProxySettings, String> {
+    get_proxy_manager(&app_handle).save_settings(settings)
+        .map_err(|e| format!("Failed to save proxy settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn test_proxy(
+    app_handle: AppHandle,
+    proxy: ProxyServer,
+    #[allow(non_snake_case)] testUrl: Option<String>,
+) -> Result<ProxyTestResult, String> {
+    let test_url = testUrl.unwrap_or_else(|| DEFAULT_TEST_URL.to_string());
+    if !get_security_manager().validate_network_access(&test_url) {
+        return Err(format!("Network access to '{}' is not allowed", test_url));
+    }
+    
+    let resolved = get_proxy_manager(&app_handle).resolve_server(&proxy)
+        .map_err(|e| format!("Invalid proxy: {}", e))?;
+    println!("=== TEST_PROXY: {:?} -> {} ===", resolved, test_url);
+    crate::proxy_manager::test_proxy(&resolved, &test_url)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_ytdlp_profile(app_handle: AppHandle) -> Result<YtDlpProfile, String> {
+    Ok(get_ytdlp_profile_manager(&app_handle).get_profile())
+}
+
+#[tauri::command]
+pub async fn save_ytdlp_profile(app_handle: AppHandle, profile: YtDlpProfile) -> Result<YtDlpProfile, String> {
+    get_ytdlp_profile_manager(&app_handle).save_profile(profile)
+        .map_err(|e| format!("Failed to save yt-dlp profile: {}", e))
+}
+
+#[tauri::command]
+pub async fn reset_ytdlp_profile(app_handle: AppHandle) -> Result<YtDlpProfile, String> {
+    get_ytdlp_profile_manager(&app_handle).reset_profile()
+        .map_err(|e| format!("Failed to reset yt-dlp profile: {}", e))
+}
+
 #[tauri::command]
 pub async fn check_for_updates(app_handle: tauri::AppHandle) -> Result<Vec<UpdateInfo>, String> {
     let update_manager = get_update_manager(&app_handle);
@@ -372,45 +717,95 @@ pub async fn cleanup_old_backups(app_handle: tauri::AppHandle) -> Result<(), Str
         .map_err(|e| format!("Failed to cleanup backups: {}", e))
 }
 
+// Only hold the download manager long enough to find yt-dlp; expanding a large
+// channel or fetching a batch of metadata takes a while and shouldn't block downloads
+async fn get_initialized_ytdlp_path() -> Result<std::path::PathBuf, String> {
+    let manager = get_download_manager();
+    let mut manager_guard = manager.lock().await;
+    if let Err(e) = manager_guard.initialize().await {
+        return Err(format!("Failed to initialize download manager: {}", e));
+    }
+    manager_guard.get_ytdlp_path()
+        .cloned()
+        .ok_or_else(|| "yt-dlp not initialized".to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct PlaylistEntriesEvent {
+    expansion_id: String,
+    entries: Vec<PlaylistEntry>,
+}
+
 #[tauri::command]
-pub async fn extract_playlist_videos(#[allow(non_snake_case)] playlistUrl: String) -> Result<Vec<String>, String> {
-    println!("=== EXTRACT_PLAYLIST_VIDEOS CALLED ===");
-    println!("Playlist URL: {}", playlistUrl);
+pub async fn expand_playlist(
+    app_handle: AppHandle,
+    #[allow(non_snake_case)] expansionId: String,
+    url: String,
+    options: Option<PlaylistOptions>,
+) -> Result<usize, String> {
+    println!("=== EXPAND_PLAYLIST CALLED ===");
+    println!("Playlist URL: {}", url);
     
-    let manager = get_download_manager();
-    {
-        let mut manager_guard = manager.lock().await;
-        
-        // Initialize download manager if needed
-        println!("Initializing download manager...");
-        if let Err(e) = manager_guard.initialize().await {
-            let error_msg = format!("Failed to initialize download manager: {}", e);
-            println!("Initialization error: {}", error_msg);
-            return Err(error_msg);
+    let credentials = resolve_credentials(&app_handle, None, &url)?;
+    let ytdlp_path = get_initialized_ytdlp_path().await?;
+    
+    let options = options.unwrap_or_default();
+    crate::playlist_expander::expand_playlist(&ytdlp_path, &url, &options, credentials.as_ref(), |entries| {
+        let event = PlaylistEntriesEvent {
+            expansion_id: expansionId.clone(),
+            entries,
+        };
+        if let Err(e) = app_handle.emit("playlist-entries", &event) {
+            eprintln!("Failed to emit playlist entries: {}", e);
         }
-        println!("Download manager initialized successfully");
-    }
+    })
+    .await
+    .map_err(|e| format!("Failed to expand playlist: {}", e))
+}
+
+#[tauri::command]
+pub async fn preview_playlist(
+    app_handle: AppHandle,
+    url: String,
+    options: Option<PlaylistOptions>,
+) -> Result<PlaylistPreview, String> {
+    println!("=== PREVIEW_PLAYLIST CALLED ===");
+    println!("Playlist URL: {}", url);
     
-    let manager_guard = manager.lock().await;
-    println!("Calling extract_playlist_videos...");
-    match manager_guard.extract_playlist_videos(&playlistUrl).await {
-        Ok(video_urls) => {
-            println!("Successfully extracted {} videos from playlist", video_urls.len());
-            Ok(video_urls)
+    let options = options.unwrap_or_default();
+    options.validate().map_err(|e| e.to_string())?;
+    // Entries are cached unfiltered, so changing a filter back and forth doesn't re-run
+    // yt-dlp over the whole playlist
+    let cache = get_metadata_cache(&app_handle);
+    let entries = match cache.get_playlist_entries(&url, &options).await {
+        Some(entries) => {
+            println!("Playlist preview cache hit");
+            entries
         }
-        Err(e) => {
-            let error_msg = format!("Failed to extract playlist videos: {}", e);
-            println!("Playlist extraction error: {}", error_msg);
-            Err(error_msg)
+        None => {
+            let credentials = resolve_credentials(&app_handle, None, &url)?;
+            let ytdlp_path = get_initialized_ytdlp_path().await?;
+            let entries = crate::playlist_expander::preview_entries(&ytdlp_path, &url, &options, credentials.as_ref())
+                .await
+                .map_err(|e| format!("Failed to preview playlist: {}", e))?;
+            if let Err(e) = cache.insert_playlist_entries(&url, &options, &entries).await {
+                eprintln!("Failed to cache playlist preview: {}", e);
+            }
+            entries
         }
-    }
+    };
+    let preview = PlaylistPreview::new(entries, options.filter.as_ref());
+    
+    println!("Playlist preview: {} of {} entries match ({} uncertain)", preview.entries.len(), preview.total, preview.uncertain);
+    Ok(preview)
 }
 
 #[tauri::command]
-pub async fn get_basic_video_metadata(url: String) -> Result<VideoMetadata, String> {
+pub async fn get_basic_video_metadata(app_handle: AppHandle, url: String) -> Result<VideoMetadata, String> {
     println!("=== GET_BASIC_VIDEO_METADATA CALLED ===");
     println!("URL: {}", url);
     
+    let credentials = resolve_credentials(&app_handle, None, &url)?;
     let manager = get_download_manager();
     {
         let mut manager_guard = manager.lock().await;
@@ -427,7 +822,8 @@ pub async fn get_basic_video_metadata(url: String) -> Result<VideoMetadata, Stri
     
     let manager_guard = manager.lock().await;
     println!("Calling get_basic_video_info...");
-    match manager_guard.get_basic_video_info(&url).await {
+    let platform = get_url_extractor().detect_platform(&url);
+    match manager_guard.get_basic_video_info(&url, &platform, credentials.as_ref()).await {
         Ok(metadata) => {
             println!("Successfully got basic metadata: title={}", metadata.title);
             Ok(metadata)
@@ -441,6 +837,7 @@ pub async fn get_basic_video_metadata(url: String) -> Result<VideoMetadata, Stri
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn convert_video_file(
     app_handle: tauri::AppHandle,
     id: String,
@@ -448,7 +845,9 @@ pub async fn convert_video_file(
     #[allow(non_snake_case)] outputFile: String,
     format: String,
     #[allow(non_snake_case)] _keepOriginal: Option<bool>,
-) -> Result<(), String> {
+    #[allow(non_snake_case)] hardwareAcceleration: Option<bool>,
+    clips: Option<ClipOptions>,
+) -> Result<Vec<String>, String> {
     println!("=== CONVERT_VIDEO_FILE CALLED ===");
     println!("Input file: {}", inputFile);
     println!("Output file: {}", outputFile);
@@ -457,6 +856,7 @@ pub async fn convert_video_file(
     // Parse conversion format
     let parsed_format = match format.as_str() {
         "h264" => ConversionFormat::H264HighProfile,
+        "hevc" => ConversionFormat::HEVC,
         "dnxhr" => ConversionFormat::DNxHRSQ,
         "prores" => ConversionFormat::ProResProxy,
         "mp3" => ConversionFormat::MP3Audio,
@@ -517,12 +917,22 @@ pub async fn convert_video_file(
             input_file: PathBuf::from(inputFile.clone()),
             output_file: PathBuf::from(outputFile.clone()),
             format: parsed_format,
+            hardware_acceleration: hardwareAcceleration.unwrap_or(true),
+            clips,
             progress_tx: Some(conversion_tx),
         };
         
-        match ffmpeg.convert_video(conversion_request).await {
-            Ok(output_path) => {
-                println!("Conversion completed successfully: {:?}", output_path);
+        // Clip extraction can produce one file per range; all of them are returned
+        let result = if conversion_request.clips.is_some() {
+            ffmpeg.extract_clips(conversion_request).await
+                .and_then(|paths| if paths.is_empty() { Err(anyhow::anyhow!("No clips were extracted")) } else { Ok(paths) })
+        } else {
+            ffmpeg.convert_video(conversion_request).await.map(|path| vec![path])
+        };
+        
+        match result {
+            Ok(output_paths) => {
+                println!("Conversion completed successfully: {:?}", output_paths);
                 
                 // Small delay to ensure FFmpeg controller's final progress update is processed first
                 tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -537,7 +947,7 @@ pub async fn convert_video_file(
                     downloaded_bytes: None,
                     total_bytes: None,
                     error: None,
-                    file_path: Some(output_path.to_string_lossy().to_string()),
+                    file_path: Some(output_paths[0].to_string_lossy().to_string()),
                 };
                 println!("=== COMMANDS: Emitting conversion completed progress event: {:?} ===", completed_progress);
                 let _ = app_handle.emit("download-progress", &completed_progress);
@@ -545,7 +955,7 @@ pub async fn convert_video_file(
                 // Also emit legacy conversion event for compatibility
                 println!("=== COMMANDS: Emitting legacy conversion-completed event for id: {} ===", id);
                 let _ = app_handle.emit("conversion-completed", &id);
-                Ok(())
+                Ok(output_paths.iter().map(|path| path.to_string_lossy().to_string()).collect())
             }
             Err(e) => {
                 let error_msg = format!("Conversion failed: {}", e);
@@ -577,6 +987,90 @@ pub async fn convert_video_file(
     }
 }
 
+#[tauri::command]
+pub async fn probe_media(#[allow(non_snake_case)] filePath: String) -> Result<VideoInfo, String> {
+    println!("=== PROBE_MEDIA CALLED ===");
+    println!("File: {}", filePath);
+    
+    let ffmpeg = get_ffmpeg_controller().await?;
+    ffmpeg.probe_video_info(&PathBuf::from(&filePath))
+        .await
+        .map_err(|e| format!("Failed to probe media: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_encoder_capabilities() -> Result<EncoderCapabilities, String> {
+    let ffmpeg = get_ffmpeg_controller().await?;
+    Ok(ffmpeg.get_capabilities().clone())
+}
+
+#[tauri::command]
+pub async fn export_chapters(
+    chapters: Vec<Chapter>,
+    format: String,
+    #[allow(non_snake_case)] outputPath: String,
+    title: Option<String>,
+    #[allow(non_snake_case)] frameRate: Option<f64>,
+) -> Result<String, String> {
+    println!("=== EXPORT_CHAPTERS CALLED ===");
+    println!("Chapters: {}, format: {}, output: {}", chapters.len(), format, outputPath);
+    
+    if chapters.is_empty() {
+        return Err("No chapters to export".to_string());
+    }
+    
+    let security_manager = get_security_manager();
+    let output_path = security_manager.sanitize_file_path(&outputPath)
+        .map_err(|e| format!("Invalid file path: {}", e))?;
+    
+    let frame_rate = frameRate.unwrap_or(30.0);
+    let contents = match format.as_str() {
+        "edl" => chapters_to_edl(&chapters, title.as_deref().unwrap_or("Chapters"), frame_rate),
+        "csv" => chapters_to_csv(&chapters, frame_rate),
+        _ => return Err("Invalid chapter export format".to_string()),
+    };
+    
+    fs::write(&output_path, contents)
+        .map_err(|e| format!("Failed to write chapter file: {}", e))?;
+    
+    Ok(output_path)
+}
+
+#[tauri::command]
+pub async fn clear_thumbnail_cache(app_handle: AppHandle) -> Result<(), String> {
+    get_thumbnail_manager(&app_handle).clear()
+        .await
+        .map_err(|e| format!("Failed to clear thumbnail cache: {}", e))
+}
+
+#[tauri::command]
+pub async fn clear_metadata_cache(app_handle: AppHandle) -> Result<usize, String> {
+    get_metadata_cache(&app_handle).clear()
+        .await
+        .map_err(|e| format!("Failed to clear metadata cache: {}", e))
+}
+
+#[tauri::command]
+pub async fn list_credential_profiles(app_handle: AppHandle) -> Result<Vec<CredentialProfile>, String> {
+    Ok(get_credential_manager(&app_handle).list_profiles())
+}
+
+#[tauri::command]
+pub async fn save_credential_profile(
+    app_handle: AppHandle,
+    profile: CredentialProfile,
+    secret: Option<String>,
+) -> Result<CredentialProfile, String> {
+    get_credential_manager(&app_handle).save_profile(profile, secret)
+        .map_err(|e| format!("Failed to save credential profile: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_credential_profile(app_handle: AppHandle, id: String) -> Result<(), String> {
+    get_credential_manager(&app_handle).delete_profile(&id)
+        .map_err(|e| format!("Failed to delete credential profile: {}", e))
+}
+
 #[tauri::command]
 pub async fn check_file_exists(
     #[allow(non_snake_case)] filePath: String,
@@ -618,6 +1112,7 @@ pub async fn generate_conversion_filename(
     // Determine the correct file extension based on format
     let extension = match format.as_str() {
         "h264" => "mp4",
+        "hevc" => "mp4",
         "dnxhr" => "mov", 
         "prores" => "mov",
         "mp3" => "mp3",
@@ -745,7 +1240,11 @@ pub async fn install_yt_dlp(app_handle: AppHandle) -> Result<(), String> {
     
     manager.install_yt_dlp(&app_handle)
         .await
-        .map_err(|e| format!("Failed to install yt-dlp: {}", e))
+        .map_err(|e| format!("Failed to install yt-dlp: {}", e))?;
+    
+    // Downloads pick up the new binary instead of the one found before
+    get_download_manager().lock().await.reset_tools();
+    Ok(())
 }
 
 #[tauri::command]
@@ -755,7 +1254,11 @@ pub async fn install_ffmpeg(app_handle: AppHandle) -> Result<(), String> {
     
     manager.install_ffmpeg(&app_handle)
         .await
-        .map_err(|e| format!("Failed to install FFmpeg: {}", e))
+        .map_err(|e| format!("Failed to install FFmpeg: {}", e))?;
+    
+    // Downloads pick up the new binary instead of the one found before
+    get_download_manager().lock().await.reset_tools();
+    Ok(())
 }
 
 #[tauri::command]
@@ -765,7 +1268,10 @@ pub async fn uninstall_dependency(app_handle: AppHandle, dependency: String) ->
     
     manager.uninstall_dependency(&dependency)
         .await
-        .map_err(|e| format!("Failed to uninstall {}: {}", dependency, e))
+        .map_err(|e| format!("Failed to uninstall {}: {}", dependency, e))?;
+    
+    get_download_manager().lock().await.reset_tools();
+    Ok(())
 }
 
 #[tauri::command]
@@ -0,0 +1,598 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tokio::process::Command as AsyncCommand;
+use crate::url_parser::match_host_patterns;
+
+const KEYRING_SERVICE: &str = "com.grabzilla.credentials";
+const NONCE_LEN: usize = 12;
+
+// Browsers yt-dlp can read cookies from
+const SUPPORTED_BROWSERS: &[&str] = &[
+    "brave", "chrome", "chromium", "edge", "firefox", "opera", "safari", "vivaldi", "whale",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum CredentialSource {
+    /// Netscape cookies.txt
+    #[serde(rename = "cookies_file")]
+    CookiesFile { path: String },
+    #[serde(rename = "browser")]
+    Browser {
+        browser: String,
+        #[serde(default)]
+        profile: Option<String>,
+    },
+    /// Password is kept in the secret store, never in the profile file
+    #[serde(rename = "password")]
+    Password { username: String },
+    #[serde(rename = "netrc")]
+    Netrc {
+        #[serde(default)]
+        path: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialProfile {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    /// Used automatically for these hosts; "youtube.com" also covers "www.youtube.com"
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    pub source: CredentialSource,
+    /// Reported to the frontend, ignored when saving
+    #[serde(default)]
+    pub has_secret: bool,
+}
+
+impl CredentialProfile {
+    fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow!("Profile name is empty"));
+        }
+        match self.source {
+            CredentialSource::CookiesFile { ref path } => {
+                if !Path::new(path).is_file() {
+                    return Err(anyhow!("Cookies file not found: {}", path));
+                }
+            }
+            CredentialSource::Browser { ref browser, ref profile } => {
+                if !SUPPORTED_BROWSERS.contains(&browser.as_str()) {
+                    return Err(anyhow!("Unsupported browser: {}", browser));
+                }
+                // ':' would be read as the start of the container part of the yt-dlp spec
+                if profile.as_deref().is_some_and(|profile| profile.contains(':')) {
+                    return Err(anyhow!("Invalid browser profile name"));
+                }
+            }
+            CredentialSource::Password { ref username } => {
+                if username.trim().is_empty() {
+                    return Err(anyhow!("Username is empty"));
+                }
+            }
+            CredentialSource::Netrc { ref path } => {
+                if let Some(ref path) = path {
+                    if !Path::new(path).is_file() {
+                        return Err(anyhow!("netrc file not found: {}", path));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Credentials ready to hand to yt-dlp
+#[derive(Clone)]
+pub enum ResolvedCredentials {
+    CookiesFile(PathBuf),
+    Browser(String),
+    Password { username: String, password: String },
+    Netrc(Option<PathBuf>),
+}
+
+// Keeps the password out of the download manager's debug logging
+impl std::fmt::Debug for ResolvedCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolvedCredentials::CookiesFile(path) => f.debug_tuple("CookiesFile").field(path).finish(),
+            ResolvedCredentials::Browser(spec) => f.debug_tuple("Browser").field(spec).finish(),
+            ResolvedCredentials::Password { username, .. } => f.debug_struct("Password")
+                .field("username", username)
+                .field("password", &"<redacted>")
+                .finish(),
+            ResolvedCredentials::Netrc(path) => f.debug_tuple("Netrc").field(path).finish(),
+        }
+    }
+}
+
+/// Owner-only temporary yt-dlp config for options that shouldn't appear on the command line,
+/// where any process on the machine could read them. Removed when dropped
+pub struct PrivateConfigFile {
+    path: PathBuf,
+}
+
+impl PrivateConfigFile {
+    pub fn write(name: &str, options: &[(&str, &str)]) -> Result<Self> {
+        let contents: String = options.iter()
+            .map(|(option, value)| format!("{} {}\n", option, quote_config_value(value)))
+            .collect();
+        let path = write_private_file(&format!("grabzilla-{}-{}.conf", name, unique_suffix()), &contents)?;
+        Ok(Self { path })
+    }
+
+    /// Adds the config to a yt-dlp command; yt-dlp accepts the option more than once
+    pub fn add_to(&self, cmd: &mut AsyncCommand) {
+        cmd.arg("--config-locations").arg(&self.path);
+    }
+}
+
+impl Drop for PrivateConfigFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl ResolvedCredentials {
+    /// Adds the yt-dlp arguments for these credentials. Keep the returned guard alive until
+    /// yt-dlp has exited
+    pub fn apply(&self, cmd: &mut AsyncCommand) -> Result<Option<PrivateConfigFile>> {
+        match self {
+            ResolvedCredentials::CookiesFile(path) => {
+                cmd.arg("--cookies").arg(path);
+            }
+            ResolvedCredentials::Browser(spec) => {
+                cmd.arg("--cookies-from-browser").arg(spec);
+            }
+            ResolvedCredentials::Netrc(path) => {
+                cmd.arg("--netrc");
+                if let Some(path) = path {
+                    cmd.arg("--netrc-location").arg(path);
+                }
+            }
+            ResolvedCredentials::Password { username, password } => {
+                let config = PrivateConfigFile::write("auth", &[("--username", username), ("--password", password)])?;
+                config.add_to(cmd);
+                return Ok(Some(config));
+            }
+        }
+        Ok(None)
+    }
+}
+
+// yt-dlp splits config files with shlex, so double quotes with \ and " escaped
+fn quote_config_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub(crate) fn unique_suffix() -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+fn write_private_file(name: &str, contents: &str) -> Result<PathBuf> {
+    let path = std::env::temp_dir().join(name);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    use std::io::Write;
+    options.open(&path)?.write_all(contents.as_bytes())?;
+    Ok(path)
+}
+
+pub trait SecretStore: Send + Sync {
+    fn get(&self, id: &str) -> Result<Option<String>>;
+    fn set(&self, id: &str, secret: &str) -> Result<()>;
+    fn delete(&self, id: &str) -> Result<()>;
+}
+
+pub struct KeyringStore;
+
+impl KeyringStore {
+    /// None when the platform has no usable keyring (e.g. Linux without a secret service)
+    pub fn probe() -> Option<Self> {
+        let entry = keyring::Entry::new(KEYRING_SERVICE, "probe").ok()?;
+        match entry.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Some(KeyringStore),
+            Err(e) => {
+                println!("=== CREDENTIAL MANAGER: OS keyring unavailable: {} ===", e);
+                None
+            }
+        }
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn get(&self, id: &str) -> Result<Option<String>> {
+        match keyring::Entry::new(KEYRING_SERVICE, id)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set(&self, id: &str, secret: &str) -> Result<()> {
+        Ok(keyring::Entry::new(KEYRING_SERVICE, id)?.set_password(secret)?)
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        match keyring::Entry::new(KEYRING_SERVICE, id)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// AES-GCM encrypted secrets file. The key lives in a separate owner-only file, which keeps
+/// passwords out of plain-text backups and casual reads but is no substitute for a keyring
+pub struct EncryptedFileStore {
+    secrets_path: PathBuf,
+    key_path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        Ok(Self {
+            secrets_path: dir.join("credentials.enc"),
+            key_path: dir.join("credentials.key"),
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn cipher(&self) -> Result<Aes256Gcm> {
+        let key = match std::fs::read(&self.key_path) {
+            Ok(key) if key.len() == 32 => key,
+            Ok(_) => return Err(anyhow!("Credential key file is corrupt")),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = Aes256Gcm::generate_key(OsRng).to_vec();
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).create_new(true);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::OpenOptionsExt;
+                    options.mode(0o600);
+                }
+                use std::io::Write;
+                options.open(&self.key_path)?.write_all(&key)?;
+                key
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+
+    fn load(&self) -> Result<HashMap<String, String>> {
+        let data = match std::fs::read(&self.secrets_path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if data.len() < NONCE_LEN {
+            return Err(anyhow!("Credential store is corrupt"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self.cipher()?
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Failed to decrypt credential store"))?;
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    fn save(&self, secrets: &HashMap<String, String>) -> Result<()> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher()?
+            .encrypt(&nonce, serde_json::to_vec(secrets)?.as_slice())
+            .map_err(|_| anyhow!("Failed to encrypt credential store"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        std::fs::write(&self.secrets_path, data)?;
+        Ok(())
+    }
+
+    fn guard(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, id: &str) -> Result<Option<String>> {
+        let _guard = self.guard();
+        Ok(self.load()?.remove(id))
+    }
+
+    fn set(&self, id: &str, secret: &str) -> Result<()> {
+        let _guard = self.guard();
+        let mut secrets = self.load()?;
+        secrets.insert(id.to_string(), secret.to_string());
+        self.save(&secrets)
+    }
+
+    fn delete(&self, id: &str) -> Result<()> {
+        let _guard = self.guard();
+        let mut secrets = self.load()?;
+        if secrets.remove(id).is_some() {
+            self.save(&secrets)?;
+        }
+        Ok(())
+    }
+}
+
+/// The OS keyring when one is usable, otherwise the encrypted file store in `config_dir`
+pub fn open_secret_store(config_dir: &Path) -> Result<Box<dyn SecretStore>> {
+    Ok(match KeyringStore::probe() {
+        Some(keyring) => Box::new(keyring),
+        None => {
+            println!("=== CREDENTIAL MANAGER: Falling back to encrypted file store ===");
+            Box::new(EncryptedFileStore::new(config_dir)?)
+        }
+    })
+}
+
+/// Per-site credential profiles for members-only, age-restricted and private content
+pub struct CredentialManager {
+    profiles_path: PathBuf,
+    profiles: Mutex<Vec<CredentialProfile>>,
+    secrets: Box<dyn SecretStore>,
+}
+
+impl CredentialManager {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let config_dir = app_handle
+            .path()
+            .app_config_dir()
+            .map_err(|e| anyhow!("Failed to get app config directory: {}", e))?;
+        std::fs::create_dir_all(&config_dir)?;
+
+        Self::with_store(config_dir.join("credential_profiles.json"), open_secret_store(&config_dir)?)
+    }
+
+    pub fn with_store(profiles_path: PathBuf, secrets: Box<dyn SecretStore>) -> Result<Self> {
+        let profiles = match std::fs::read_to_string(&profiles_path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            profiles_path,
+            profiles: Mutex::new(profiles),
+            secrets,
+        })
+    }
+
+    pub fn list_profiles(&self) -> Vec<CredentialProfile> {
+        self.lock_profiles().iter()
+            .cloned()
+            .map(|mut profile| {
+                profile.has_secret = matches!(profile.source, CredentialSource::Password { .. })
+                    && matches!(self.secrets.get(&profile.id), Ok(Some(_)));
+                profile
+            })
+            .collect()
+    }
+
+    /// Creates or updates a profile. A password profile keeps its stored password when
+    /// `secret` is None
+    pub fn save_profile(&self, mut profile: CredentialProfile, secret: Option<String>) -> Result<CredentialProfile> {
+        profile.validate()?;
+        if profile.id.is_empty() {
+            profile.id = format!("profile-{}", unique_suffix());
+        }
+        profile.hosts = profile.hosts.iter()
+            .map(|host| host.trim().to_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        profile.has_secret = false;
+
+        match (&profile.source, secret) {
+            (CredentialSource::Password { .. }, Some(secret)) => self.secrets.set(&profile.id, &secret)?,
+            (CredentialSource::Password { .. }, None) => {}
+            // Switching away from a password profile shouldn't leave the password behind
+            _ => self.secrets.delete(&profile.id)?,
+        }
+
+        {
+            let mut profiles = self.lock_profiles();
+            match profiles.iter_mut().find(|existing| existing.id == profile.id) {
+                Some(existing) => *existing = profile.clone(),
+                None => profiles.push(profile.clone()),
+            }
+            self.persist(&profiles)?;
+        }
+        println!("=== CREDENTIAL MANAGER: Saved profile '{}' ===", profile.name);
+        Ok(profile)
+    }
+
+    pub fn delete_profile(&self, id: &str) -> Result<()> {
+        self.secrets.delete(id)?;
+        let mut profiles = self.lock_profiles();
+        profiles.retain(|profile| profile.id != id);
+        self.persist(&profiles)
+    }
+
+    /// The explicitly selected profile, or else the one whose host pattern matches the URL
+    /// most specifically
+    pub fn resolve(&self, profile_id: Option<&str>, url: &str) -> Result<Option<ResolvedCredentials>> {
+        let profile = {
+            let profiles = self.lock_profiles();
+            match profile_id.filter(|id| !id.is_empty()) {
+                Some(id) => Some(profiles.iter()
+                    .find(|profile| profile.id == id)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Credential profile not found: {}", id))?),
+                None => {
+                    let host = url::Url::parse(url).ok()
+                        .and_then(|url| url.host_str().map(|host| host.to_lowercase()));
+                    host.and_then(|host| {
+                        profiles.iter()
+                            .filter_map(|profile| match_host_patterns(&profile.hosts, &host).map(|len| (len, profile)))
+                            .max_by_key(|(len, _)| *len)
+                            .map(|(_, profile)| profile.clone())
+                    })
+                }
+            }
+        };
+
+        let Some(profile) = profile else {
+            return Ok(None);
+        };
+        println!("=== CREDENTIAL MANAGER: Using profile '{}' for {} ===", profile.name, url);
+
+        Ok(Some(match profile.source {
+            CredentialSource::CookiesFile { path } => ResolvedCredentials::CookiesFile(PathBuf::from(path)),
+            CredentialSource::Browser { browser, profile } => ResolvedCredentials::Browser(match profile {
+                Some(profile) if !profile.is_empty() => format!("{}:{}", browser, profile),
+                _ => browser,
+            }),
+            CredentialSource::Password { username } => {
+                let password = self.secrets.get(&profile.id)?
+                    .ok_or_else(|| anyhow!("No password stored for profile '{}'", profile.name))?;
+                ResolvedCredentials::Password { username, password }
+            }
+            CredentialSource::Netrc { path } => ResolvedCredentials::Netrc(path.map(PathBuf::from)),
+        }))
+    }
+
+    fn persist(&self, profiles: &[CredentialProfile]) -> Result<()> {
+        std::fs::write(&self.profiles_path, serde_json::to_string_pretty(profiles)?)?;
+        Ok(())
+    }
+
+    fn lock_profiles(&self) -> std::sync::MutexGuard<'_, Vec<CredentialProfile>> {
+        self.profiles.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("grabzilla-credentials-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn profile(name: &str, hosts: &[&str], source: CredentialSource) -> CredentialProfile {
+        CredentialProfile {
+            id: String::new(),
+            name: name.to_string(),
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            source,
+            has_secret: false,
+        }
+    }
+
+    #[test]
+    fn test_encrypted_file_store() {
+        let dir = test_dir("store");
+        let store = EncryptedFileStore::new(&dir).unwrap();
+
+        store.set("a", "hunter2").unwrap();
+        store.set("b", "correct horse").unwrap();
+        assert_eq!(store.get("a").unwrap().as_deref(), Some("hunter2"));
+
+        // Nothing readable on disk
+        let raw = std::fs::read(dir.join("credentials.enc")).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("hunter2"));
+
+        // Survives a restart with the same key, not with a different one
+        let reopened = EncryptedFileStore::new(&dir).unwrap();
+        assert_eq!(reopened.get("b").unwrap().as_deref(), Some("correct horse"));
+        reopened.delete("a").unwrap();
+        assert_eq!(reopened.get("a").unwrap(), None);
+
+        std::fs::write(dir.join("credentials.key"), [7u8; 32]).unwrap();
+        assert!(reopened.get("b").is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_profile_resolution() {
+        let dir = test_dir("profiles");
+        let cookies = dir.join("cookies.txt");
+        std::fs::write(&cookies, "# Netscape HTTP Cookie File\n").unwrap();
+
+        let manager = CredentialManager::with_store(
+            dir.join("profiles.json"),
+            Box::new(EncryptedFileStore::new(&dir).unwrap()),
+        ).unwrap();
+
+        manager.save_profile(profile("Google", &["youtube.com"], CredentialSource::Browser {
+            browser: "firefox".to_string(),
+            profile: Some("default-release".to_string()),
+        }), None).unwrap();
+        manager.save_profile(profile("Music", &["music.youtube.com"], CredentialSource::CookiesFile {
+            path: cookies.to_string_lossy().to_string(),
+        }), None).unwrap();
+        let vimeo = manager.save_profile(profile("Vimeo", &["vimeo.com"], CredentialSource::Password {
+            username: "me@example.com".to_string(),
+        }), Some("s3cret".to_string())).unwrap();
+
+        assert!(matches!(
+            manager.resolve(None, "https://www.youtube.com/watch?v=dQw4w9WgXcQ").unwrap(),
+            Some(ResolvedCredentials::Browser(ref spec)) if spec == "firefox:default-release"
+        ));
+        // The more specific host wins
+        assert!(matches!(
+            manager.resolve(None, "https://music.youtube.com/watch?v=x").unwrap(),
+            Some(ResolvedCredentials::CookiesFile(_))
+        ));
+        assert!(manager.resolve(None, "https://notyoutube.com/video").unwrap().is_none());
+
+        // An explicit selection overrides host matching
+        let resolved = manager.resolve(Some(&vimeo.id), "https://www.youtube.com/watch?v=x").unwrap().unwrap();
+        assert!(matches!(resolved, ResolvedCredentials::Password { ref password, .. } if password == "s3cret"));
+        assert!(!format!("{:?}", resolved).contains("s3cret"));
+        assert!(manager.resolve(Some("missing"), "https://vimeo.com/1").is_err());
+
+        // Profiles come back from disk, the password only from the secret store
+        let stored = std::fs::read_to_string(dir.join("profiles.json")).unwrap();
+        assert!(!stored.contains("s3cret"));
+        assert!(manager.list_profiles().iter().any(|p| p.id == vimeo.id && p.has_secret));
+
+        assert!(manager.save_profile(profile("Bad", &[], CredentialSource::Browser {
+            browser: "netscape".to_string(),
+            profile: None,
+        }), None).is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_password_config_file() {
+        let credentials = ResolvedCredentials::Password {
+            username: "me".to_string(),
+            password: "pa\"ss\\word".to_string(),
+        };
+        let mut cmd = AsyncCommand::new("yt-dlp");
+        let applied = credentials.apply(&mut cmd).unwrap().unwrap();
+
+        let path = applied.path.clone();
+        let config = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(config, "--username \"me\"\n--password \"pa\\\"ss\\\\word\"\n");
+        assert!(!format!("{:?}", cmd).contains("word"));
+
+        drop(applied);
+        assert!(!path.exists());
+    }
+}
@@ -3,9 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::fs;
 use tokio::process::Command;
-use reqwest;
 use tauri::{AppHandle, Manager, Emitter};
 use crate::security_manager::SecurityManager;
+use crate::proxy_manager::http_client;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DependencyInfo {
@@ -20,6 +20,7 @@ pub struct DependencyInfo {
 pub struct DependencyStatus {
     pub yt_dlp: DependencyInfo,
     pub ffmpeg: DependencyInfo,
+    pub ffprobe: DependencyInfo,
     pub all_installed: bool,
 }
 
@@ -55,13 +56,15 @@ impl DependencyManager {
 
     pub async fn check_dependencies(&self) -> Result<DependencyStatus> {
         let yt_dlp_info = self.check_yt_dlp().await?;
-        let ffmpeg_info = self.check_ffmpeg().await?;
+        let ffmpeg_info = self.check_ffmpeg_tool("ffmpeg", &self.get_ffmpeg_path()).await?;
+        let ffprobe_info = self.check_ffmpeg_tool("ffprobe", &self.get_ffprobe_path()).await?;
         
-        let all_installed = yt_dlp_info.installed && ffmpeg_info.installed;
+        let all_installed = yt_dlp_info.installed && ffmpeg_info.installed && ffprobe_info.installed;
         
         Ok(DependencyStatus {
             yt_dlp: yt_dlp_info,
             ffmpeg: ffmpeg_info,
+            ffprobe: ffprobe_info,
             all_installed,
         })
     }
@@ -101,11 +104,10 @@ impl DependencyManager {
         Ok(info)
     }
 
-    async fn check_ffmpeg(&self) -> Result<DependencyInfo> {
-        let ffmpeg_path = self.get_ffmpeg_path();
-        
+    // ffmpeg and ffprobe ship together and report their version the same way
+    async fn check_ffmpeg_tool(&self, name: &str, ffmpeg_path: &Path) -> Result<DependencyInfo> {
         let mut info = DependencyInfo {
-            name: "ffmpeg".to_string(),
+            name: name.to_string(),
             version: None,
             installed: false,
             path: None,
@@ -114,15 +116,15 @@ impl DependencyManager {
 
         if ffmpeg_path.exists() {
             info.installed = true;
-            info.path = Some(ffmpeg_path.clone());
+            info.path = Some(ffmpeg_path.to_path_buf());
             
             // Get file size
-            if let Ok(metadata) = fs::metadata(&ffmpeg_path) {
+            if let Ok(metadata) = fs::metadata(ffmpeg_path) {
                 info.size = Some(metadata.len());
             }
             
             // Get version
-            if let Ok(output) = Command::new(&ffmpeg_path)
+            if let Ok(output) = Command::new(ffmpeg_path)
                 .arg("-version")
                 .output()
                 .await
@@ -151,7 +153,7 @@ impl DependencyManager {
         let download_url = self.get_yt_dlp_download_url()?;
         
         // Download the file
-        let response = reqwest::get(&download_url).await?;
+        let response = http_client()?.get(&download_url).send().await?;
         if !response.status().is_success() {
             return Err(anyhow!("Failed to download yt-dlp: HTTP {}", response.status()));
         }
@@ -201,7 +203,7 @@ impl DependencyManager {
         let download_url = self.get_ffmpeg_download_url()?;
         
         // Download the archive
-        let response = reqwest::get(&download_url).await?;
+        let response = http_client()?.get(&download_url).send().await?;
         if !response.status().is_success() {
             return Err(anyhow!("Failed to download FFmpeg: HTTP {}", response.status()));
         }
@@ -213,28 +215,57 @@ impl DependencyManager {
         self.emit_progress(app_handle, "ffmpeg", "extracting", 50.0, "Extracting FFmpeg...").await;
         
         // Save and extract archive
-        let archive_path = ffmpeg_dir.join("ffmpeg.zip");
+        let archive_name = if download_url.ends_with(".tar.xz") { "ffmpeg.tar.xz" } else { "ffmpeg.zip" };
+        let archive_path = ffmpeg_dir.join(archive_name);
         fs::write(&archive_path, &bytes)?;
         
         // Extract archive (this is a simplified version - you may want to use a proper zip library)
         self.extract_ffmpeg_archive(&archive_path, &ffmpeg_dir).await?;
         
+        // Clean up archive
+        let _ = fs::remove_file(&archive_path);
+        
+        // Some builds ship ffprobe as a separate download
+        if let Some(ffprobe_url) = self.get_ffprobe_download_url() {
+            self.emit_progress(app_handle, "ffmpeg", "downloading", 60.0, "Downloading ffprobe...").await;
+            
+            let response = http_client()?.get(&ffprobe_url).send().await?;
+            if !response.status().is_success() {
+                return Err(anyhow!("Failed to download ffprobe: HTTP {}", response.status()));
+            }
+            
+            let bytes = response.bytes().await?;
+            let archive_path = ffmpeg_dir.join("ffprobe.zip");
+            fs::write(&archive_path, &bytes)?;
+            
+            self.extract_ffmpeg_archive(&archive_path, &ffmpeg_dir).await?;
+            let _ = fs::remove_file(&archive_path);
+        }
+        
         self.emit_progress(app_handle, "ffmpeg", "verifying", 75.0, "Verifying installation...").await;
         
         // Verify installation
-        let ffmpeg_path = self.get_ffmpeg_path();
-        let output = Command::new(&ffmpeg_path)
-            .arg("-version")
-            .output()
-            .await?;
+        for tool_path in [self.get_ffmpeg_path(), self.get_ffprobe_path()] {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                if let Ok(metadata) = fs::metadata(&tool_path) {
+                    let mut perms = metadata.permissions();
+                    perms.set_mode(0o755);
+                    fs::set_permissions(&tool_path, perms)?;
+                }
+            }
             
-        if !output.status.success() {
-            return Err(anyhow!("FFmpeg installation verification failed"));
+            let output = Command::new(&tool_path)
+                .arg("-version")
+                .output()
+                .await?;
+                
+            if !output.status.success() {
+                return Err(anyhow!("FFmpeg installation verification failed for {}", tool_path.display()));
+            }
         }
         
-        // Clean up archive
-        let _ = fs::remove_file(&archive_path);
-        
         self.emit_progress(app_handle, "ffmpeg", "completed", 100.0, "FFmpeg installed successfully!").await;
         
         Ok(())
@@ -277,6 +308,23 @@ impl DependencyManager {
             }
         }
         
+        #[cfg(target_os = "linux")]
+        {
+            // Static builds unpack into a versioned folder, strip it so the binaries land in extract_dir
+            let output = Command::new("tar")
+                .arg("-xJf")
+                .arg(archive_path)
+                .arg("-C")
+                .arg(extract_dir)
+                .arg("--strip-components=1")
+                .output()
+                .await?;
+                
+            if !output.status.success() {
+                return Err(anyhow!("Failed to extract FFmpeg archive"));
+            }
+        }
+        
         Ok(())
     }
 
@@ -300,6 +348,14 @@ impl DependencyManager {
         Ok(url.to_string())
     }
 
+    // Only needed where the FFmpeg archive doesn't already contain ffprobe
+    fn get_ffprobe_download_url(&self) -> Option<String> {
+        match std::env::consts::OS {
+            "macos" => Some("https://evermeet.cx/ffmpeg/getrelease/ffprobe/zip".to_string()),
+            _ => None,
+        }
+    }
+
     pub fn get_yt_dlp_path(&self) -> PathBuf {
         let filename = if cfg!(windows) { "yt-dlp.exe" } else { "yt-dlp" };
         self.app_data_dir.join(filename)
@@ -325,6 +381,17 @@ impl DependencyManager {
         self.app_data_dir.join("ffmpeg").join(filename)
     }
 
+    // ffprobe is always taken from the same directory as ffmpeg so the two never mismatch
+    pub fn get_ffprobe_path(&self) -> PathBuf {
+        let filename = if cfg!(windows) { "ffprobe.exe" } else { "ffprobe" };
+        let ffmpeg_path = self.get_ffmpeg_path();
+        
+        match ffmpeg_path.parent() {
+            Some(dir) => dir.join(filename),
+            None => self.app_data_dir.join("ffmpeg").join(filename),
+        }
+    }
+
     async fn emit_progress(&self, app_handle: &AppHandle, dependency: &str, stage: &str, progress: f32, message: &str) {
         let progress_data = InstallProgress {
             dependency: dependency.to_string(),
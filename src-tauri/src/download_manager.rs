@@ -1,40 +1,311 @@
 use anyhow::{Result, anyhow};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::process::Command as AsyncCommand;
 use tokio::sync::mpsc;
-use crate::ffmpeg_controller::{FFmpegController, ConversionFormat, ConversionRequest, ConversionProgress};
+use crate::ffmpeg_controller::{FFmpegController, ConversionFormat, ConversionRequest, ConversionProgress, Chapter, ClipOptions, ClipOutput, ClipSelection, SeekMode};
 use crate::security_manager::SecurityManager;
+use crate::url_parser::{classify_url, Platform, UrlKind};
+use crate::metadata_resolver::MetadataResolver;
+use crate::metadata_sidecar::{write_sidecars, Provenance, SidecarFormat};
+use crate::credential_manager::ResolvedCredentials;
+use crate::ytdlp_invocation::YtDlpInvocation;
 // use crate::dependency_manager::DependencyManager; // Unused import
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct VideoMetadata {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default = "unknown_title", deserialize_with = "null_as_default_title")]
     pub title: String,
-    pub duration: Option<String>,
+    /// Length in seconds
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
     pub uploader: Option<String>,
+    #[serde(default)]
+    pub uploader_id: Option<String>,
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub channel_id: Option<String>,
+    #[serde(default)]
+    pub channel_url: Option<String>,
+    #[serde(default)]
     pub description: Option<String>,
+    #[serde(default)]
     pub thumbnail: Option<String>,
+    #[serde(default)]
     pub view_count: Option<u64>,
-    pub upload_date: Option<String>,
+    #[serde(default)]
+    pub like_count: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_ytdlp_date")]
+    pub upload_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "deserialize_ytdlp_date")]
+    pub release_date: Option<NaiveDate>,
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
+    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "string_list")]
+    pub tags: Vec<String>,
+    #[serde(default, deserialize_with = "string_list")]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub live_status: Option<LiveStatus>,
+    #[serde(default)]
+    pub availability: Option<Availability>,
+    #[serde(default)]
+    pub age_limit: Option<u32>,
+    #[serde(default)]
+    pub license: Option<String>,
+    #[serde(default)]
+    pub webpage_url: Option<String>,
+    #[serde(default)]
+    pub extractor_key: Option<String>,
+    #[serde(default)]
+    pub playlist_count: Option<u64>,
+    /// What the URL turned out to be, once yt-dlp has looked at it
+    #[serde(default)]
+    pub url_kind: Option<UrlKind>,
+    #[serde(default, deserialize_with = "lenient_list")]
     pub formats: Vec<VideoFormat>,
+    #[serde(default, deserialize_with = "lenient_list")]
+    pub chapters: Vec<Chapter>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub subtitles: HashMap<String, Vec<SubtitleTrack>>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub automatic_captions: HashMap<String, Vec<SubtitleTrack>>,
+    // Full yt-dlp info dict, kept for sidecars but not sent to the frontend
+    #[serde(skip)]
+    pub info_json: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum LiveStatus {
+    #[serde(rename = "not_live")]
+    NotLive,
+    #[serde(rename = "is_live")]
+    IsLive,
+    #[serde(rename = "is_upcoming")]
+    IsUpcoming,
+    #[serde(rename = "was_live")]
+    WasLive,
+    #[serde(rename = "post_live")]
+    PostLive,
+    #[serde(other, rename = "unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Availability {
+    #[serde(rename = "public")]
+    Public,
+    #[serde(rename = "unlisted")]
+    Unlisted,
+    #[serde(rename = "private")]
+    Private,
+    #[serde(rename = "premium_only")]
+    PremiumOnly,
+    #[serde(rename = "subscriber_only")]
+    SubscriberOnly,
+    #[serde(rename = "needs_auth")]
+    NeedsAuth,
+    #[serde(other, rename = "unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleTrack {
+    #[serde(default)]
+    pub ext: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoFormat {
     pub format_id: String,
+    #[serde(default = "unknown_ext")]
     pub ext: String,
+    #[serde(default)]
     pub resolution: Option<String>,
+    #[serde(default)]
     pub filesize: Option<u64>,
+    #[serde(default)]
     pub vcodec: Option<String>,
+    #[serde(default)]
     pub acodec: Option<String>,
+    #[serde(default)]
     pub abr: Option<f32>,
+    #[serde(default)]
     pub vbr: Option<f32>,
 }
 
+impl VideoMetadata {
+    /// Builds the typed model from a yt-dlp info dict, keeping the raw dict alongside
+    pub fn from_info_json(json_value: serde_json::Value) -> Result<Self> {
+        let mut metadata = VideoMetadata::deserialize(&json_value)?;
+        
+        if metadata.uploader.is_none() {
+            metadata.uploader = metadata.channel.clone();
+        }
+        if metadata.thumbnail.is_none() {
+            // For playlists, try to get thumbnail from entries
+            metadata.thumbnail = json_value["entries"].as_array()
+                .and_then(|entries| entries.first())
+                .and_then(|first_entry| first_entry["thumbnail"].as_str())
+                .map(String::from);
+        }
+        
+        metadata.info_json = Some(json_value);
+        Ok(metadata)
+    }
+    
+    /// "1:02:03" for videos, "12 videos" for playlists
+    pub fn formatted_duration(&self) -> Option<String> {
+        match (self.playlist_count, self.duration) {
+            (Some(count), _) => Some(format!("{} videos", count)),
+            (None, Some(seconds)) => Some(format_duration(seconds.round() as u64)),
+            (None, None) => None,
+        }
+    }
+    
+    /// Upload date as YYYY-MM-DD
+    pub fn formatted_upload_date(&self) -> Option<String> {
+        self.upload_date.map(|date| date.format("%Y-%m-%d").to_string())
+    }
+    
+    pub fn is_live(&self) -> bool {
+        matches!(self.live_status, Some(LiveStatus::IsLive) | Some(LiveStatus::IsUpcoming))
+    }
+}
+
+fn unknown_title() -> String {
+    "Unknown".to_string()
+}
+
+fn unknown_ext() -> String {
+    "unknown".to_string()
+}
+
+// yt-dlp emits null for many fields it couldn't extract
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+fn null_as_default_title<'de, D>(deserializer: D) -> std::result::Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_else(unknown_title))
+}
+
+// Skips entries that don't match the expected shape instead of failing the whole list
+pub(crate) fn lenient_list<'de, D, T>(deserializer: D) -> std::result::Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let values: Vec<serde_json::Value> = null_as_default(deserializer)?;
+    Ok(values.into_iter().filter_map(|value| serde_json::from_value(value).ok()).collect())
+}
+
+// Some extractors leave null entries in tag and category lists
+fn string_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let values: Vec<Option<String>> = null_as_default(deserializer)?;
+    Ok(values.into_iter().flatten().collect())
+}
+
+// yt-dlp dates are YYYYMMDD; ISO dates are accepted too so serialized metadata reads back
+pub(crate) fn deserialize_ytdlp_date<'de, D>(deserializer: D) -> std::result::Result<Option<NaiveDate>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date = Option::<String>::deserialize(deserializer)?;
+    Ok(date.and_then(|date| {
+        NaiveDate::parse_from_str(&date, "%Y%m%d")
+            .or_else(|_| NaiveDate::parse_from_str(&date, "%Y-%m-%d"))
+            .ok()
+    }))
+}
+
+// Unix seconds from yt-dlp, or RFC 3339 from serialized metadata
+pub(crate) fn deserialize_timestamp<'de, D>(deserializer: D) -> std::result::Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::Number(seconds)) => seconds.as_f64()
+            .and_then(|seconds| DateTime::from_timestamp(seconds as i64, 0)),
+        Some(serde_json::Value::String(date)) => DateTime::parse_from_rfc3339(&date)
+            .ok()
+            .map(|date| date.with_timezone(&Utc)),
+        _ => None,
+    })
+}
+
+/// Runs yt-dlp for one URL's metadata. Doesn't need the download manager, so callers
+/// can fetch without holding its lock
+pub async fn fetch_video_metadata(ytdlp_path: &Path, url: &str, credentials: Option<&ResolvedCredentials>) -> Result<VideoMetadata> {
+    // Playlists and channels are listed flat instead of resolving every video
+    let kind = classify_url(url);
+
+    let mut invocation = YtDlpInvocation::new(ytdlp_path);
+    invocation.arg("--dump-json");
+    for arg in kind.metadata_args() {
+        invocation.arg(arg);
+    }
+    
+    invocation.credentials(credentials)?.url(url)?;
+    invocation.command()
+       .stdout(Stdio::piped())
+       .stderr(Stdio::piped());
+
+    let output = invocation.output().await?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("Failed to get video metadata: {}", error));
+    }
+
+    let json_str = String::from_utf8(output.stdout)?;
+    
+    // For playlists, yt-dlp might output multiple JSON lines
+    // We need to handle this properly
+    let json_value: serde_json::Value = if kind.is_multi_video() {
+        // Try to parse as a single JSON object first (playlist info)
+        match serde_json::from_str(&json_str) {
+            Ok(value) => value,
+            Err(_) => {
+                // If that fails, try to get the first line (which should be playlist info)
+                let first_line = json_str.lines().next().unwrap_or("{}");
+                serde_json::from_str(first_line)?
+            }
+        }
+    } else {
+        serde_json::from_str(&json_str)?
+    };
+
+    // Parse the JSON into our VideoMetadata struct
+    let mut metadata = VideoMetadata::from_info_json(json_value)?;
+    metadata.url_kind = Some(kind.refine(&metadata));
+
+    Ok(metadata)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DownloadStatus {
     #[serde(rename = "queued")]
@@ -75,6 +346,28 @@ pub struct DownloadRequest {
     pub output_dir: PathBuf,
     pub convert_format: Option<ConversionFormat>,
     pub keep_original: bool,
+    pub clips: Option<ClipOptions>,
+    pub split_chapters: bool,
+    /// Keep the whole file next to its chapter files instead of deleting it
+    #[serde(default)]
+    pub keep_unsplit: bool,
+    pub write_thumbnail: bool,
+    pub embed_thumbnail: bool,
+    pub sidecars: Vec<SidecarFormat>,
+    /// Resolved from the selected credential profile, or by host match when none is selected
+    #[serde(skip)]
+    pub credentials: Option<ResolvedCredentials>,
+}
+
+// Prefixes for the lines yt-dlp prints with the final path of each downloaded file
+// and, when sidecars are requested, the full info dict
+const OUTPUT_FILE_MARKER: &str = "GRABZILLA_OUTPUT:";
+const INFO_JSON_MARKER: &str = "GRABZILLA_INFO:";
+
+#[derive(Debug, Default)]
+struct YtDlpOutput {
+    files: Vec<PathBuf>,
+    info_json: Option<serde_json::Value>,
 }
 
 pub struct DownloadManager {
@@ -86,6 +379,7 @@ pub struct DownloadManager {
     max_concurrent_downloads: usize,
     processing_queue: Arc<Mutex<bool>>,
     security_manager: SecurityManager,
+    metadata_resolver: MetadataResolver,
 }
 
 struct DownloadHandle {
@@ -105,10 +399,17 @@ impl DownloadManager {
             max_concurrent_downloads: 5, // Default to 5 concurrent downloads
             processing_queue: Arc::new(Mutex::new(false)),
             security_manager: SecurityManager::new()?,
+            metadata_resolver: MetadataResolver::new()?,
         })
     }
 
     pub async fn initialize(&mut self) -> Result<()> {
+        // Every command calls this, so the (slow) tool discovery only runs again when a tool
+        // it found has since been removed
+        if self.tools_present() {
+            return Ok(());
+        }
+
         self.ensure_ytdlp().await?;
         
         // Initialize FFmpeg controller
@@ -119,6 +420,22 @@ impl DownloadManager {
         Ok(())
     }
 
+    /// Forgets the tools found so far, so the next `initialize` looks again after an install or update
+    pub fn reset_tools(&mut self) {
+        self.ytdlp_path = None;
+        self.ffmpeg_controller = None;
+    }
+
+    fn tools_present(&self) -> bool {
+        // Bare names were found on PATH and can't be checked without running them
+        let present = |path: &Path| !path.is_absolute() || path.exists();
+        let ffmpeg_present = match self.ffmpeg_controller.as_ref() {
+            Some(ffmpeg) => ffmpeg.get_ffmpeg_path().map(present).unwrap_or(true),
+            None => false,
+        };
+        ffmpeg_present && self.ytdlp_path.as_deref().is_some_and(present)
+    }
+
     async fn ensure_ytdlp(&mut self) -> Result<()> {
         // First, try to get yt-dlp path from the global static (set in commands.rs)
         if let Some(dependency_manager) = crate::commands::get_dependency_manager_if_initialized() {
@@ -164,278 +481,69 @@ impl DownloadManager {
         Err(anyhow!("yt-dlp not found. Please install yt-dlp using the Dependencies tab."))
     }
 
-    pub async fn get_video_metadata(&self, url: &str) -> Result<VideoMetadata> {
-        let ytdlp_path = self.ytdlp_path.as_ref()
-            .ok_or_else(|| anyhow!("yt-dlp not initialized"))?;
-
-        // Detect if this is a playlist URL
-        let is_playlist = url.contains("list=") || url.contains("playlist") || url.contains("/channel/") || url.contains("/c/");
-
-        let mut cmd = AsyncCommand::new(ytdlp_path);
-        cmd.arg("--dump-json")
-           .arg("--user-agent")
-           .arg("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-           .arg("--extractor-retries")
-           .arg("5")
-           .arg("--sleep-interval")
-           .arg("1")
-           .arg("--max-sleep-interval")
-           .arg("5")
-           .arg("--no-check-certificate");
-        
-        if is_playlist {
-            // For playlists, get playlist info instead of individual videos
-            cmd.arg("--flat-playlist");
-        } else {
-            // For individual videos, don't process playlists
-            cmd.arg("--no-playlist");
-        }
-        
-        cmd.arg(url)
-           .stdout(Stdio::piped())
-           .stderr(Stdio::piped());
-
-        let output = cmd.output().await?;
+    pub fn get_ytdlp_path(&self) -> Option<&PathBuf> {
+        self.ytdlp_path.as_ref()
+    }
 
-        if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to get video metadata: {}", error));
+    // Lightweight metadata fetch for when the main method fails due to bot detection
+    pub async fn get_basic_video_info(&self, url: &str, platform: &Platform, credentials: Option<&ResolvedCredentials>) -> Result<VideoMetadata> {
+        let resolved = self.metadata_resolver.resolve(url, platform).await;
+        if let Ok(ref metadata) = resolved {
+            if metadata.duration.is_some() {
+                return resolved;
+            }
         }
 
-        let json_str = String::from_utf8(output.stdout)?;
-        
-        // For playlists, yt-dlp might output multiple JSON lines
-        // We need to handle this properly
-        let json_value: serde_json::Value = if is_playlist {
-            // Try to parse as a single JSON object first (playlist info)
-            match serde_json::from_str(&json_str) {
-                Ok(value) => value,
-                Err(_) => {
-                    // If that fails, try to get the first line (which should be playlist info)
-                    let first_line = json_str.lines().next().unwrap_or("{}");
-                    serde_json::from_str(first_line)?
-                }
+        // oEmbed and the page often lack a duration, yt-dlp's lightweight print may still work
+        match (resolved, self.print_basic_info(url, credentials).await) {
+            (Ok(mut metadata), Some(printed)) => {
+                metadata.duration = printed.duration;
+                metadata.uploader = metadata.uploader.or(printed.uploader);
+                Ok(metadata)
             }
-        } else {
-            serde_json::from_str(&json_str)?
-        };
-
-        // Parse the JSON into our VideoMetadata struct
-        let metadata = VideoMetadata {
-            title: json_value["title"].as_str().unwrap_or("Unknown").to_string(),
-            duration: if is_playlist {
-                // For playlists, show entry count if available
-                json_value["playlist_count"].as_u64()
-                    .map(|count| format!("{} videos", count))
-                    .or_else(|| json_value["duration"].as_u64().map(|d| format_duration(d)))
-            } else {
-                json_value["duration"].as_u64().map(|d| format_duration(d))
-            },
-            uploader: json_value["uploader"].as_str()
-                .or_else(|| json_value["channel"].as_str())
-                .map(String::from),
-            description: json_value["description"].as_str().map(String::from),
-            thumbnail: json_value["thumbnail"].as_str()
-                .or_else(|| {
-                    // For playlists, try to get thumbnail from entries
-                    json_value["entries"].as_array()
-                        .and_then(|entries| entries.first())
-                        .and_then(|first_entry| first_entry["thumbnail"].as_str())
-                })
-                .map(String::from),
-            view_count: json_value["view_count"].as_u64(),
-            upload_date: json_value["upload_date"].as_str().map(String::from),
-            formats: parse_formats(&json_value["formats"]),
-        };
-
-        Ok(metadata)
+            (Ok(metadata), None) => Ok(metadata),
+            (Err(_), Some(printed)) => Ok(printed),
+            (Err(e), None) => Err(e),
+        }
     }
 
-    pub async fn extract_playlist_videos(&self, playlist_url: &str) -> Result<Vec<String>> {
-        println!("=== EXTRACT_PLAYLIST_VIDEOS CALLED ===");
-        println!("Playlist URL: {}", playlist_url);
-        
-        let ytdlp_path = self.ytdlp_path.as_ref()
-            .ok_or_else(|| anyhow!("yt-dlp not initialized"))?;
-
-        // Use --flat-playlist and --get-url to get individual video URLs directly
-        // Also add user-agent to reduce bot detection
-        let output = AsyncCommand::new(ytdlp_path)
-            .arg("--flat-playlist")
-            .arg("--get-url")
-            .arg("--user-agent")
-            .arg("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-            .arg("--extractor-retries")
-            .arg("5")
-            .arg("--sleep-interval")
-            .arg("1")
-            .arg("--max-sleep-interval")
-            .arg("5")
-            .arg("--no-check-certificate")
-            .arg(playlist_url)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .await?;
+    async fn print_basic_info(&self, url: &str, credentials: Option<&ResolvedCredentials>) -> Option<VideoMetadata> {
+        let ytdlp_path = self.ytdlp_path.as_ref()?;
 
+        let mut invocation = YtDlpInvocation::new(ytdlp_path);
+        invocation.arg("--no-playlist")
+           .arg("--print")
+           .arg("%(title)s|%(duration)s|%(uploader)s")
+           .arg("--quiet")
+           .arg("--no-warnings")
+           .credentials(credentials)
+           .ok()?
+           .url(url)
+           .ok()?;
+        invocation.command()
+           .stdout(Stdio::piped())
+           .stderr(Stdio::piped());
+        let output = invocation.output().await.ok()?;
         if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("yt-dlp error: {}", error);
-            return Err(anyhow!("Failed to extract playlist videos: {}", error));
+            return None;
         }
 
-        let output_str = String::from_utf8(output.stdout)?;
-        println!("Raw yt-dlp output: {}", output_str);
-        
-        let video_urls: Vec<String> = output_str
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .filter(|line| line.starts_with("http")) // Only keep actual URLs
-            .map(|line| line.trim().to_string())
-            .collect();
-
-        println!("Extracted {} video URLs: {:?}", video_urls.len(), video_urls);
-        Ok(video_urls)
-    }
-
-    // Lightweight metadata fetch for when the main method fails due to bot detection
-    pub async fn get_basic_video_info(&self, url: &str) -> Result<VideoMetadata> {
-        // First try to get video title by scraping the page directly
-        let scraped_metadata = self.scrape_video_metadata(url).await;
-        
-        // Try yt-dlp lightweight approach as backup
-        let ytdlp_path = self.ytdlp_path.as_ref()
-            .ok_or_else(|| anyhow!("yt-dlp not initialized"))?;
-
-        let output = AsyncCommand::new(ytdlp_path)
-            .arg("--no-playlist")
-            .arg("--print")
-            .arg("%(title)s|%(duration)s|%(uploader)s")
-            .arg("--user-agent")
-            .arg("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-            .arg("--quiet")
-            .arg("--no-warnings")
-            .arg(url)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .await;
-
-        let video_id = self.extract_video_id(url);
-        
-        // If yt-dlp works, use it for duration but prefer scraped title
-        if let Ok(output) = output {
-            if output.status.success() {
-                let output_str = String::from_utf8_lossy(&output.stdout);
-                let parts: Vec<&str> = output_str.trim().split('|').collect();
-                
-                let ytdlp_title = parts.get(0).unwrap_or(&"").to_string();
-                let duration = parts.get(1)
-                    .and_then(|d| d.parse::<u64>().ok())
-                    .map(|seconds| format_duration(seconds))
-                    .unwrap_or_else(|| "0:00".to_string());
-                let uploader = parts.get(2).unwrap_or(&"YouTube").to_string();
-                
-                // Use scraped title if available and not generic, otherwise use yt-dlp title
-                let final_title = if let Some(ref scraped) = scraped_metadata {
-                    if scraped.title.len() > 20 && !scraped.title.contains("YouTube") {
-                        scraped.title.clone()
-                    } else {
-                        ytdlp_title
-                    }
-                } else {
-                    ytdlp_title
-                };
-                
-                                 return Ok(VideoMetadata {
-                     title: if final_title.is_empty() || final_title == "Unknown" {
-                         format!("YouTube Video{}", video_id.as_ref().map(|id| format!(" ({})", id)).unwrap_or_default())
-                     } else {
-                         final_title
-                     },
-                     duration: Some(duration),
-                     uploader: Some(uploader),
-                     description: Some("Basic metadata only".to_string()),
-                     thumbnail: video_id.map(|id| format!("https://img.youtube.com/vi/{}/hqdefault.jpg", id)),
-                     view_count: None,
-                     upload_date: None,
-                     formats: vec![],
-                 });
-            }
-        }
-        
-        // If yt-dlp failed, use scraped metadata if available
-        if let Some(scraped) = scraped_metadata {
-            return Ok(scraped);
-        }
-        
-                 // Final fallback - return basic info with video ID
-         Ok(VideoMetadata {
-             title: format!("YouTube Video{}", video_id.as_ref().map(|id| format!(" ({})", id)).unwrap_or_default()),
-             duration: Some("0:00".to_string()),
-             uploader: Some("YouTube".to_string()),
-             description: Some("Duration unavailable due to platform restrictions".to_string()),
-             thumbnail: video_id.map(|id| format!("https://img.youtube.com/vi/{}/hqdefault.jpg", id)),
-             view_count: None,
-             upload_date: None,
-             formats: vec![],
-         })
-    }
+        // Split from the right, titles contain '|' far more often than uploader names
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        let mut parts = output_str.trim().rsplitn(3, '|');
+        let uploader = parts.next().filter(|u| !u.is_empty() && *u != "NA").map(str::to_string);
+        let duration = parts.next().and_then(|d| d.parse::<f64>().ok());
+        let title = parts.next().filter(|t| !t.is_empty() && *t != "NA")?.to_string();
 
-    async fn scrape_video_metadata(&self, url: &str) -> Option<VideoMetadata> {
-        // Create a simple HTTP client to fetch the page
-        let client = reqwest::Client::builder()
-            .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36")
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .ok()?;
-        
-        let response = client.get(url).send().await.ok()?;
-        let html = response.text().await.ok()?;
-        
-        // Extract title from HTML
-        let title = if let Some(start) = html.find("<title>") {
-            if let Some(end) = html[start + 7..].find("</title>") {
-                let raw_title = &html[start + 7..start + 7 + end];
-                // Clean up the title (YouTube adds " - YouTube" at the end)
-                raw_title.replace(" - YouTube", "").trim().to_string()
-            } else {
-                return None;
-            }
-        } else {
-            return None;
-        };
-        
-        let video_id = self.extract_video_id(url);
-        
         Some(VideoMetadata {
             title,
-            duration: Some("0:00".to_string()), // Duration not available from scraping
-            uploader: Some("YouTube".to_string()),
-            description: Some("Title extracted from page".to_string()),
-            thumbnail: video_id.map(|id| format!("https://img.youtube.com/vi/{}/hqdefault.jpg", id)),
-            view_count: None,
-            upload_date: None,
-            formats: vec![],
+            duration,
+            uploader,
+            webpage_url: Some(url.to_string()),
+            ..Default::default()
         })
     }
 
-    fn extract_video_id(&self, url: &str) -> Option<String> {
-        if url.contains("v=") {
-            url.split("v=").nth(1)
-                .and_then(|s| s.split("&").next())
-                .filter(|s| s.len() == 11)
-                .map(|s| s.to_string())
-        } else if url.contains("youtu.be/") {
-            url.split("youtu.be/").nth(1)
-                .and_then(|s| s.split("?").next())
-                .filter(|s| s.len() == 11)
-                .map(|s| s.to_string())
-        } else {
-            None
-        }
-    }
-
     pub fn set_max_concurrent_downloads(&mut self, max: usize) {
         self.max_concurrent_downloads = max.clamp(1, 10);
     }
@@ -600,63 +708,83 @@ impl DownloadManager {
                         println!("=== Quality suffix for filename: '{}' ===", quality_suffix);
                         println!("=== Quality selector for yt-dlp: '{}' ===", quality_selector);
                         
-                        let mut cmd = tokio::process::Command::new(&ytdlp_path);
+                        let mut invocation = YtDlpInvocation::new(&ytdlp_path);
+                        let cmd = invocation.command();
                         cmd.arg("--progress")
                            .arg("--newline")
-                           .arg("--user-agent")
-                           .arg("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-                           .arg("--extractor-retries")
-                           .arg("5")
-                           .arg("--sleep-interval")
-                           .arg("1")
-                           .arg("--max-sleep-interval")
-                           .arg("5")
-                           .arg("--no-check-certificate")
                            .arg("-f")
-                           .arg(&quality_selector)
-                           .arg("-o")
-                           .arg(request.output_dir.join(&filename_template))
-                           .arg(&request.url);
+                           .arg(&quality_selector);
+                        
+                        // Report the final path of every file yt-dlp writes, so post-processing
+                        // doesn't have to guess which file in the output directory is ours
+                        cmd.arg("--no-simulate")
+                           .arg("--print")
+                           .arg(format!("after_move:{}%(filepath)s", OUTPUT_FILE_MARKER));
+                        if !request.sidecars.is_empty() {
+                            cmd.arg("--print")
+                               .arg(format!("after_move:{}%()j", INFO_JSON_MARKER));
+                        }
+                        
+                        let output_template = match request.clips {
+                            Some(ref clips) => {
+                                add_clip_args(cmd, clips);
+                                format!("%(title)s{}_%(section_start)d-%(section_end)ds.%(ext)s", quality_suffix)
+                            }
+                            None => filename_template,
+                        };
+                        
+                        // Chapters are embedded so FFmpeg can split the file afterwards
+                        if request.split_chapters {
+                            cmd.arg("--embed-chapters");
+                        }
+                        
+                        // Full-size thumbnail next to the video and/or as cover art. Converted to
+                        // JPEG since WebP cover art isn't supported by most players
+                        if request.write_thumbnail {
+                            cmd.arg("--write-thumbnail");
+                        }
+                        if request.embed_thumbnail {
+                            cmd.arg("--embed-thumbnail");
+                        }
+                        if request.write_thumbnail || request.embed_thumbnail {
+                            cmd.arg("--convert-thumbnails").arg("jpg");
+                        }
+                        
+                        // yt-dlp's own post-processing (sections, chapters, cover art) needs FFmpeg.
+                        // A bare "ffmpeg" means it is on PATH, where yt-dlp finds it anyway
+                        if let Some(ffmpeg_path) = ffmpeg_controller_inner.as_ref()
+                            .and_then(|ffmpeg| ffmpeg.get_ffmpeg_path())
+                            .filter(|path| path.is_absolute())
+                        {
+                            cmd.arg("--ffmpeg-location").arg(ffmpeg_path);
+                        }
+                        
+                        cmd.arg("-o")
+                           .arg(request.output_dir.join(&output_template));
+                        
+                        // The invocation lives until the task ends, since a password is passed
+                        // through a temporary config file it owns
+                        invocation.credentials(request.credentials.as_ref())?
+                            .url(&request.url)?;
                            
                         // Set up stdio
-                        cmd.stdout(std::process::Stdio::piped())
+                        invocation.command()
+                           .stdout(std::process::Stdio::piped())
                            .stderr(std::process::Stdio::piped());
                            
-                        println!("=== DOWNLOAD MANAGER: Executing command: {:?} ===", cmd);
+                        println!("=== DOWNLOAD MANAGER: Executing command: {:?} ===", invocation);
                         
-                        let mut child = cmd.spawn()?;
+                        let mut child = invocation.spawn()?;
+                        let ytdlp_output = Arc::new(Mutex::new(YtDlpOutput::default()));
                         
-                        // Monitor download progress
+                        // Monitor download progress. yt-dlp moves progress to stderr when --print
+                        // is used, so both streams are watched
+                        let mut readers = Vec::new();
                         if let Some(stdout) = child.stdout.take() {
-                            let tx = progress_tx_inner.clone();
-                            let id = download_id.clone();
-                            
-                            tokio::spawn(async move {
-                                use tokio::io::{AsyncBufReadExt, BufReader};
-                                let reader = BufReader::new(stdout);
-                                let mut lines = reader.lines();
-                                
-                                while let Ok(Some(line)) = lines.next_line().await {
-                                    println!("=== DOWNLOAD MANAGER: yt-dlp output: {} ===", line);
-                                    
-                                    // Parse yt-dlp progress line
-                                    if let Some((progress, speed, eta, downloaded_bytes, total_bytes)) = parse_ytdlp_progress(line.as_str()) {
-                                        if let Some(ref tx) = tx {
-                                            let _ = tx.send(DownloadProgress {
-                                                id: id.clone(),
-                                                status: DownloadStatus::Downloading,
-                                                progress,
-                                                speed,
-                                                eta,
-                                                downloaded_bytes,
-                                                total_bytes,
-                                                error: None,
-                                                file_path: None,
-                                            });
-                                        }
-                                    }
-                                }
-                            });
+                            readers.push(tokio::spawn(monitor_ytdlp_output(stdout, download_id.clone(), progress_tx_inner.clone(), ytdlp_output.clone())));
+                        }
+                        if let Some(stderr) = child.stderr.take() {
+                            readers.push(tokio::spawn(monitor_ytdlp_output(stderr, download_id.clone(), progress_tx_inner.clone(), ytdlp_output.clone())));
                         }
                         
                         // Wait for download to complete or cancellation
@@ -667,68 +795,35 @@ impl DownloadManager {
                                         if status.success() {
                                             println!("=== DOWNLOAD MANAGER: Download completed successfully ===");
                                             
-                                            // If conversion is needed
-                                            if let Some(convert_format) = request.convert_format {
-                                                if let Some(ffmpeg) = ffmpeg_controller_inner {
-                                                    println!("=== DOWNLOAD MANAGER: Starting conversion ===");
-                                                    
-                                                    // Update status to Converting
+                                            // The readers end once yt-dlp's pipes close, after the last lines are in
+                                            for reader in readers {
+                                                let _ = reader.await;
+                                            }
+                                            let YtDlpOutput { files: mut downloaded_files, info_json } = std::mem::take(&mut *ytdlp_output.lock().await);
+                                            if downloaded_files.is_empty() {
+                                                if let Ok(path) = find_downloaded_file(&request.output_dir).await {
+                                                    downloaded_files.push(path);
+                                                }
+                                            }
+                                            
+                                            let final_files = match post_process_download(&request, downloaded_files, info_json, ffmpeg_controller_inner.as_ref(), &progress_tx_inner).await {
+                                                Ok(files) => files,
+                                                Err(e) => {
                                                     if let Some(ref tx) = progress_tx_inner {
                                                         let _ = tx.send(DownloadProgress {
                                                             id: download_id.clone(),
-                                                            status: DownloadStatus::Converting,
-                                                            progress: 0.0,
-                                                            speed: None,
-                                                            eta: None,
-                                                            downloaded_bytes: None,
-                                                            total_bytes: None,
-                                                            error: None,
-                                                            file_path: None,
+                                                            status: DownloadStatus::Failed,
+                                                            error: Some(e.to_string()),
+                                                            ..Default::default()
                                                         });
                                                     }
-
-                                                    // Find the downloaded file
-                                                    match find_downloaded_file(&request.output_dir).await {
-                                                        Ok(downloaded_file) => {
-                                                                                                        let conversion_request = ConversionRequest {
-                                                id: download_id.clone(),
-                                                input_file: downloaded_file.clone(),
-                                                output_file: downloaded_file.with_extension(ffmpeg.get_output_extension(&convert_format)),
-                                                format: convert_format,
-                                                progress_tx: None, // Progress adapter can't be accessed from here
-                                            };
-                                                            
-                                                            if let Err(e) = ffmpeg.convert_video(conversion_request).await {
-                                                                if let Some(ref tx) = progress_tx_inner {
-                                                                    let _ = tx.send(DownloadProgress {
-                                                                        id: download_id.clone(),
-                                                                        status: DownloadStatus::Failed,
-                                                                        error: Some(format!("Conversion failed: {}", e)),
-                                                                        ..Default::default()
-                                                                    });
-                                                                }
-                                                                return Err(e);
-                                                            }
-                                                        }
-                                                        Err(e) => {
-                                                            if let Some(ref tx) = progress_tx_inner {
-                                                                let _ = tx.send(DownloadProgress {
-                                                                    id: download_id.clone(),
-                                                                    status: DownloadStatus::Failed,
-                                                                    error: Some(format!("Could not find downloaded file: {}", e)),
-                                                                    ..Default::default()
-                                                                });
-                                                            }
-                                                            return Err(e);
-                                                        }
-                                                    }
+                                                    return Err(e);
                                                 }
-                                            }
-
-                                            // Find the downloaded file and update status to Completed
-                                            let downloaded_file_path = find_downloaded_file(&request.output_dir).await
-                                                .map(|path| path.to_string_lossy().to_string())
-                                                .ok();
+                                            };
+                                            
+                                            // Update status to Completed
+                                            let downloaded_file_path = final_files.first()
+                                                .map(|path| path.to_string_lossy().to_string());
                                             
                                             if let Some(ref tx) = progress_tx_inner {
                                                 let _ = tx.send(DownloadProgress {
@@ -920,36 +1015,193 @@ fn format_duration(seconds: u64) -> String {
     }
 }
 
-fn parse_formats(formats_json: &serde_json::Value) -> Vec<VideoFormat> {
-    let mut formats = Vec::new();
+async fn monitor_ytdlp_output<R>(
+    reader: R,
+    id: String,
+    tx: Option<mpsc::UnboundedSender<DownloadProgress>>,
+    output: Arc<Mutex<YtDlpOutput>>,
+) where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    let mut lines = BufReader::new(reader).lines();
+    
+    while let Ok(Some(line)) = lines.next_line().await {
+        if !line.starts_with(INFO_JSON_MARKER) {
+            println!("=== DOWNLOAD MANAGER: yt-dlp output: {} ===", line);
+        }
+        
+        if let Some(path) = line.trim().strip_prefix(OUTPUT_FILE_MARKER) {
+            output.lock().await.files.push(PathBuf::from(path));
+            continue;
+        }
+        if let Some(info) = line.trim().strip_prefix(INFO_JSON_MARKER) {
+            // Sections and chapters print one info dict each; the first one describes the video
+            let mut output = output.lock().await;
+            if output.info_json.is_none() {
+                output.info_json = serde_json::from_str(info).ok();
+            }
+            continue;
+        }
+        
+        // Parse yt-dlp progress line
+        if let Some((progress, speed, eta, downloaded_bytes, total_bytes)) = parse_ytdlp_progress(line.as_str()) {
+            if let Some(ref tx) = tx {
+                let _ = tx.send(DownloadProgress {
+                    id: id.clone(),
+                    status: DownloadStatus::Downloading,
+                    progress,
+                    speed,
+                    eta,
+                    downloaded_bytes,
+                    total_bytes,
+                    error: None,
+                    file_path: None,
+                });
+            }
+        }
+    }
+}
+
+// yt-dlp cuts the sections itself; --force-keyframes-at-cuts re-encodes around the cut
+// points so clips start exactly where requested instead of at the previous keyframe
+fn add_clip_args(cmd: &mut AsyncCommand, clips: &ClipOptions) {
+    for selection in &clips.selections {
+        let section = match selection {
+            ClipSelection::Range { start, end } => format!("*{:.3}-{:.3}", start, end),
+            // Without the leading "*" yt-dlp treats the section as a chapter title regex
+            ClipSelection::Chapter { title } => format!("^{}$", regex::escape(title.trim())),
+        };
+        cmd.arg("--download-sections").arg(section);
+    }
     
-    if let Some(formats_array) = formats_json.as_array() {
-        for format_json in formats_array {
-            if let Some(format_id) = format_json["format_id"].as_str() {
-                formats.push(VideoFormat {
-                    format_id: format_id.to_string(),
-                    ext: format_json["ext"].as_str().unwrap_or("unknown").to_string(),
-                    resolution: format_json["resolution"].as_str().map(String::from),
-                    filesize: format_json["filesize"].as_u64(),
-                    vcodec: format_json["vcodec"].as_str().map(String::from),
-                    acodec: format_json["acodec"].as_str().map(String::from),
-                    abr: format_json["abr"].as_f64().map(|x| x as f32),
-                    vbr: format_json["vbr"].as_f64().map(|x| x as f32),
+    if clips.seek_mode == SeekMode::Accurate {
+        cmd.arg("--force-keyframes-at-cuts");
+    }
+}
+
+async fn post_process_download(
+    request: &DownloadRequest,
+    mut files: Vec<PathBuf>,
+    info_json: Option<serde_json::Value>,
+    ffmpeg: Option<&FFmpegController>,
+    progress_tx: &Option<mpsc::UnboundedSender<DownloadProgress>>,
+) -> Result<Vec<PathBuf>> {
+    if files.is_empty() {
+        return Err(anyhow!("Could not find downloaded file"));
+    }
+    
+    // Join the downloaded sections into one file if requested
+    let concatenate = request.clips.as_ref().map(|c| c.output == ClipOutput::Concatenate).unwrap_or(false);
+    if concatenate && files.len() > 1 {
+        let ffmpeg = ffmpeg.ok_or_else(|| anyhow!("FFmpeg is required to join clips"))?;
+        let output_file = concatenated_clip_path(&files[0]);
+        
+        println!("=== DOWNLOAD MANAGER: Joining {} clips into {:?} ===", files.len(), output_file);
+        ffmpeg.concatenate_files(&files, &output_file).await?;
+        for file in &files {
+            let _ = tokio::fs::remove_file(file).await;
+        }
+        files = vec![output_file];
+    }
+    
+    // One file per chapter, named by chapter title. Videos without chapters are kept whole.
+    // The whole file is removed once it's split unless it was asked for
+    if request.split_chapters {
+        let ffmpeg = ffmpeg.ok_or_else(|| anyhow!("FFmpeg is required to split chapters"))?;
+        let mut chapter_files = Vec::new();
+        for file in &files {
+            match ffmpeg.split_by_chapters(file).await {
+                Ok(parts) if !parts.is_empty() => {
+                    chapter_files.extend(parts);
+                    if !request.keep_unsplit {
+                        let _ = tokio::fs::remove_file(file).await;
+                    }
+                }
+                Ok(_) => {
+                    println!("=== DOWNLOAD MANAGER: No chapters found in {:?}, keeping the whole file ===", file);
+                    chapter_files.push(file.clone());
+                }
+                Err(e) => return Err(anyhow!("Failed to split chapters: {}", e)),
+            }
+        }
+        files = chapter_files;
+    }
+    
+    // If conversion is needed
+    if let Some(ref convert_format) = request.convert_format {
+        if let Some(ffmpeg) = ffmpeg {
+            println!("=== DOWNLOAD MANAGER: Starting conversion ===");
+            
+            // Update status to Converting
+            if let Some(ref tx) = progress_tx {
+                let _ = tx.send(DownloadProgress {
+                    id: request.id.clone(),
+                    status: DownloadStatus::Converting,
+                    ..Default::default()
                 });
             }
+            
+            let mut converted_files = Vec::new();
+            for downloaded_file in &files {
+                let conversion_request = ConversionRequest {
+                    id: request.id.clone(),
+                    input_file: downloaded_file.clone(),
+                    output_file: downloaded_file.with_extension(ffmpeg.get_output_extension(convert_format)),
+                    format: convert_format.clone(),
+                    hardware_acceleration: true,
+                    clips: None,
+                    progress_tx: None, // Progress adapter can't be accessed from here
+                };
+                
+                let converted = ffmpeg.convert_video(conversion_request)
+                    .await
+                    .map_err(|e| anyhow!("Conversion failed: {}", e))?;
+                converted_files.push(converted);
+            }
+            files = converted_files;
+        }
+    }
+    
+    // Provenance sidecars for asset management, built from yt-dlp's info dict
+    if !request.sidecars.is_empty() {
+        let metadata = match info_json {
+            Some(info) => VideoMetadata::from_info_json(info)?,
+            None => VideoMetadata {
+                title: files[0].file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+                webpage_url: Some(request.url.clone()),
+                ..Default::default()
+            },
+        };
+        let provenance = Provenance::new(&request.url);
+        for file in &files {
+            write_sidecars(file, &metadata, &provenance, &request.sidecars)
+                .map_err(|e| anyhow!("Failed to write metadata sidecars: {}", e))?;
         }
     }
     
-    formats
+    Ok(files)
+}
+
+// Video_720_30-60s.mp4 -> Video_720_clips.mp4 (see the section output template)
+fn concatenated_clip_path(first_clip: &Path) -> PathBuf {
+    let stem = first_clip.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let base = stem.rsplit_once('_').map(|(base, _)| base.to_string()).unwrap_or(stem);
+    let mut name = format!("{}_clips", base);
+    if let Some(ext) = first_clip.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    first_clip.with_file_name(name)
 }
 
+/// Video and audio file extensions the app writes or accepts back from the user
+pub const MEDIA_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "3gp", "ogv",
+    "mp3", "m4a", "flac", "wav", "ogg", "aac", "opus", "wma"
+];
+
 async fn find_downloaded_file(output_dir: &PathBuf) -> Result<PathBuf> {
-    // Valid video file extensions
-    let video_extensions = vec![
-        "mp4", "mkv", "avi", "mov", "wmv", "flv", "webm", "m4v", "3gp", "ogv",
-        "mp3", "m4a", "flac", "wav", "ogg", "aac", "opus", "wma"
-    ];
-    
     let mut read_dir = tokio::fs::read_dir(output_dir).await?;
     let mut entries = Vec::new();
     
@@ -966,7 +1218,7 @@ async fn find_downloaded_file(output_dir: &PathBuf) -> Result<PathBuf> {
         
         // Check if it's a video/audio file
         if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
-            if video_extensions.contains(&extension.to_lowercase().as_str()) {
+            if MEDIA_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
                 entries.push(entry);
             }
         }
@@ -1168,4 +1420,56 @@ fn parse_size_string(size_str: &str) -> Option<u64> {
     } else {
         None
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_metadata_from_info_json() {
+        let info = serde_json::json!({
+            "id": "dQw4w9WgXcQ",
+            "title": "Never Gonna Give You Up",
+            "duration": 212.0,
+            "uploader": null,
+            "channel": "Rick Astley",
+            "channel_id": "UCuAXFkgsw1L7xaCfnd5JJOw",
+            "upload_date": "20091025",
+            "timestamp": 1256453681,
+            "tags": null,
+            "categories": ["Music", null],
+            "like_count": 18000000,
+            "live_status": "not_live",
+            "availability": "some_future_value",
+            "age_limit": 0,
+            "extractor_key": "Youtube",
+            "formats": [
+                {"format_id": "18", "ext": "mp4", "resolution": "640x360", "filesize": null},
+                {"ext": "webm"}
+            ],
+            "chapters": [{"start_time": 0.0, "end_time": 30.0, "title": "Intro"}],
+            "subtitles": {"en": [{"ext": "vtt", "url": "https://example.com/en.vtt"}]}
+        });
+
+        let metadata = VideoMetadata::from_info_json(info).unwrap();
+        assert_eq!(metadata.duration, Some(212.0));
+        assert_eq!(metadata.formatted_duration().as_deref(), Some("3:32"));
+        assert_eq!(metadata.uploader.as_deref(), Some("Rick Astley"));
+        assert_eq!(metadata.formatted_upload_date().as_deref(), Some("2009-10-25"));
+        assert_eq!(metadata.timestamp.map(|t| t.timestamp()), Some(1256453681));
+        assert!(metadata.tags.is_empty());
+        assert_eq!(metadata.categories, vec!["Music".to_string()]);
+        assert_eq!(metadata.live_status, Some(LiveStatus::NotLive));
+        assert_eq!(metadata.availability, Some(Availability::Unknown));
+        // Formats without an id are skipped rather than failing the whole model
+        assert_eq!(metadata.formats.len(), 1);
+        assert_eq!(metadata.chapters[0].title.as_deref(), Some("Intro"));
+        assert_eq!(metadata.subtitles["en"][0].ext.as_deref(), Some("vtt"));
+        assert!(metadata.info_json.is_some());
+
+        // Serialized metadata reads back with the same typed values
+        let round_trip: VideoMetadata = serde_json::from_value(serde_json::to_value(&metadata).unwrap()).unwrap();
+        assert_eq!(round_trip.upload_date, metadata.upload_date);
+        assert_eq!(round_trip.timestamp, metadata.timestamp);
+    }
+}
@@ -1,14 +1,18 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::process::Command as AsyncCommand;
 use tokio::sync::mpsc;
+use crate::proxy_manager::apply_ffmpeg_proxy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConversionFormat {
     #[serde(rename = "h264")]
     H264HighProfile,
+    #[serde(rename = "hevc")]
+    HEVC,
     #[serde(rename = "dnxhr")]
     DNxHRSQ,
     #[serde(rename = "prores")]
@@ -34,26 +38,288 @@ pub struct ConversionRequest {
     pub input_file: PathBuf,
     pub output_file: PathBuf,
     pub format: ConversionFormat,
+    pub hardware_acceleration: bool,
+    pub clips: Option<ClipOptions>,
     pub progress_tx: Option<mpsc::UnboundedSender<ConversionProgress>>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClipSelection {
+    #[serde(rename = "range")]
+    Range { start: f64, end: f64 },
+    #[serde(rename = "chapter")]
+    Chapter { title: String },
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ClipOutput {
+    #[default]
+    #[serde(rename = "separate")]
+    Separate,
+    #[serde(rename = "concatenate")]
+    Concatenate,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SeekMode {
+    #[default]
+    #[serde(rename = "accurate")]
+    Accurate,
+    #[serde(rename = "keyframe_copy")]
+    KeyframeCopy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipOptions {
+    pub selections: Vec<ClipSelection>,
+    #[serde(default)]
+    pub output: ClipOutput,
+    #[serde(default)]
+    pub seek_mode: SeekMode,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ResolvedClip {
+    start: f64,
+    end: f64,
+    label: Option<String>,
+}
+
+impl ClipOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.selections.is_empty() {
+            return Err(anyhow!("At least one clip has to be selected"));
+        }
+        if self.selections.len() > u8::MAX as usize {
+            return Err(anyhow!("Too many clips requested ({})", self.selections.len()));
+        }
+        for selection in &self.selections {
+            match selection {
+                ClipSelection::Range { start, end } => {
+                    if !start.is_finite() || !end.is_finite() || *start < 0.0 || end <= start {
+                        return Err(anyhow!("Invalid clip range {} - {}", start, end));
+                    }
+                }
+                ClipSelection::Chapter { title } => {
+                    if title.trim().is_empty() {
+                        return Err(anyhow!("Chapter selection needs a title"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, chapters: &[Chapter]) -> Result<Vec<ResolvedClip>> {
+        self.selections.iter()
+            .map(|selection| match selection {
+                ClipSelection::Range { start, end } => Ok(ResolvedClip {
+                    start: *start,
+                    end: *end,
+                    label: None,
+                }),
+                ClipSelection::Chapter { title } => chapters.iter()
+                    .find(|c| c.title.as_deref().map(|t| t.trim().eq_ignore_ascii_case(title.trim())).unwrap_or(false))
+                    .map(|c| ResolvedClip {
+                        start: c.start_time,
+                        end: c.end_time,
+                        label: c.title.clone(),
+                    })
+                    .ok_or_else(|| anyhow!("Chapter '{}' not found", title)),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum VideoCodec {
+    #[serde(rename = "h264")]
+    H264,
+    #[serde(rename = "hevc")]
+    Hevc,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HardwareBackend {
+    #[serde(rename = "videotoolbox")]
+    VideoToolbox,
+    #[serde(rename = "nvenc")]
+    Nvenc,
+    #[serde(rename = "qsv")]
+    Qsv,
+    #[serde(rename = "vaapi")]
+    Vaapi,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VideoEncoder {
+    pub name: String,
+    pub codec: VideoCodec,
+    pub hardware: Option<HardwareBackend>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncoderCapabilities {
+    pub encoders: Vec<String>,
+    pub hwaccels: Vec<String>,
+    pub hardware_encoders: Vec<VideoEncoder>,
+}
+
+// Preference order when several hardware encoders are usable
+const HARDWARE_BACKENDS: [HardwareBackend; 4] = [
+    HardwareBackend::VideoToolbox,
+    HardwareBackend::Nvenc,
+    HardwareBackend::Qsv,
+    HardwareBackend::Vaapi,
+];
+
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+impl HardwareBackend {
+    fn encoder_name(&self, codec: VideoCodec) -> &'static str {
+        match (self, codec) {
+            (HardwareBackend::VideoToolbox, VideoCodec::H264) => "h264_videotoolbox",
+            (HardwareBackend::VideoToolbox, VideoCodec::Hevc) => "hevc_videotoolbox",
+            (HardwareBackend::Nvenc, VideoCodec::H264) => "h264_nvenc",
+            (HardwareBackend::Nvenc, VideoCodec::Hevc) => "hevc_nvenc",
+            (HardwareBackend::Qsv, VideoCodec::H264) => "h264_qsv",
+            (HardwareBackend::Qsv, VideoCodec::Hevc) => "hevc_qsv",
+            (HardwareBackend::Vaapi, VideoCodec::H264) => "h264_vaapi",
+            (HardwareBackend::Vaapi, VideoCodec::Hevc) => "hevc_vaapi",
+        }
+    }
+
+    // The hwaccel that has to be present for the encoder to be usable, if any
+    fn required_hwaccel(&self) -> Option<&'static str> {
+        match self {
+            HardwareBackend::VideoToolbox => Some("videotoolbox"),
+            HardwareBackend::Nvenc => None,
+            HardwareBackend::Qsv => Some("qsv"),
+            HardwareBackend::Vaapi => Some("vaapi"),
+        }
+    }
+}
+
+impl VideoEncoder {
+    fn software(codec: VideoCodec) -> Self {
+        let name = match codec {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::Hevc => "libx265",
+        };
+        VideoEncoder {
+            name: name.to_string(),
+            codec,
+            hardware: None,
+        }
+    }
+}
+
+impl EncoderCapabilities {
+    fn from_ffmpeg_output(encoders_output: &str, hwaccels_output: &str) -> Self {
+        let encoders = parse_encoder_list(encoders_output);
+        let hwaccels = parse_hwaccel_list(hwaccels_output);
+
+        let mut hardware_encoders = Vec::new();
+        for backend in HARDWARE_BACKENDS {
+            if let Some(hwaccel) = backend.required_hwaccel() {
+                if !hwaccels.iter().any(|h| h == hwaccel) {
+                    continue;
+                }
+            }
+            for codec in [VideoCodec::H264, VideoCodec::Hevc] {
+                let name = backend.encoder_name(codec);
+                if encoders.iter().any(|e| e == name) {
+                    hardware_encoders.push(VideoEncoder {
+                        name: name.to_string(),
+                        codec,
+                        hardware: Some(backend),
+                    });
+                }
+            }
+        }
+
+        Self {
+            encoders,
+            hwaccels,
+            hardware_encoders,
+        }
+    }
+
+    pub fn select_video_encoder(&self, codec: VideoCodec, prefer_hardware: bool) -> VideoEncoder {
+        if prefer_hardware {
+            if let Some(encoder) = self.hardware_encoders.iter().find(|e| e.codec == codec) {
+                return encoder.clone();
+            }
+        }
+        VideoEncoder::software(codec)
+    }
+}
+
 #[derive(Clone)]
 pub struct FFmpegController {
     ffmpeg_path: Option<PathBuf>,
+    ffprobe_path: Option<PathBuf>,
+    capabilities: EncoderCapabilities,
 }
 
 impl FFmpegController {
     pub fn new() -> Result<Self> {
         Ok(FFmpegController {
             ffmpeg_path: None,
+            ffprobe_path: None,
+            capabilities: EncoderCapabilities::default(),
         })
     }
 
     pub async fn initialize(&mut self) -> Result<()> {
         self.ensure_ffmpeg().await?;
+        self.ensure_ffprobe().await;
+        self.detect_capabilities().await;
         Ok(())
     }
 
+    pub fn get_capabilities(&self) -> &EncoderCapabilities {
+        &self.capabilities
+    }
+
+    pub fn get_ffmpeg_path(&self) -> Option<&Path> {
+        self.ffmpeg_path.as_deref()
+    }
+
+    async fn detect_capabilities(&mut self) {
+        let Some(ffmpeg_path) = self.ffmpeg_path.as_ref() else {
+            return;
+        };
+
+        let run = |arg: &'static str| {
+            AsyncCommand::new(ffmpeg_path)
+                .arg("-hide_banner")
+                .arg(arg)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .output()
+        };
+
+        let encoders_output = match run("-encoders").await {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+            _ => {
+                println!("Could not list FFmpeg encoders, hardware encoding disabled");
+                return;
+            }
+        };
+        let hwaccels_output = match run("-hwaccels").await {
+            Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).to_string(),
+            _ => String::new(),
+        };
+
+        self.capabilities = EncoderCapabilities::from_ffmpeg_output(&encoders_output, &hwaccels_output);
+        println!(
+            "Detected hardware encoders: {:?}",
+            self.capabilities.hardware_encoders.iter().map(|e| e.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
     async fn ensure_ffmpeg(&mut self) -> Result<()> {
         // First, try to get FFmpeg path from the global dependency manager
         if let Some(dependency_manager) = crate::commands::get_dependency_manager_if_initialized() {
@@ -96,28 +362,300 @@ impl FFmpegController {
         Err(anyhow!("FFmpeg not found. Please install FFmpeg using the Dependencies tab."))
     }
 
+    // ffprobe must come from the same installation as the chosen ffmpeg, otherwise a
+    // bundled ffmpeg could end up paired with an unrelated (or missing) system ffprobe
+    async fn ensure_ffprobe(&mut self) {
+        let Some(ffmpeg_path) = self.ffmpeg_path.as_ref() else {
+            return;
+        };
+
+        let ffprobe_path = ffprobe_path_for(ffmpeg_path);
+        if let Ok(output) = AsyncCommand::new(&ffprobe_path)
+            .arg("-version")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+        {
+            if output.status.success() {
+                println!("Using ffprobe at: {}", ffprobe_path.display());
+                self.ffprobe_path = Some(ffprobe_path);
+                return;
+            }
+        }
+
+        println!("ffprobe not found next to FFmpeg at: {}", ffmpeg_path.display());
+    }
+
     pub async fn convert_video(&self, request: ConversionRequest) -> Result<PathBuf> {
         println!("=== FFMPEG CONTROLLER: Starting conversion ===");
         println!("Input file: {:?}", request.input_file);
         println!("Output file: {:?}", request.output_file);
         println!("Format: {:?}", request.format);
         
-        let ffmpeg_path = self.ffmpeg_path.as_ref()
-            .ok_or_else(|| anyhow!("FFmpeg not initialized"))?;
-
         // Ensure output directory exists
         if let Some(parent) = request.output_file.parent() {
             tokio::fs::create_dir_all(parent).await?;
             println!("=== FFMPEG CONTROLLER: Created output directory: {:?} ===", parent);
         }
 
+        if let Err(error) = self.run_with_fallback(&request, None, &request.output_file).await {
+            println!("=== FFMPEG CONTROLLER: Conversion failed with error: {} ===", error);
+            send_conversion_failure(&request, &error);
+            return Err(anyhow!("FFmpeg conversion failed: {}", error));
+        }
+
+        println!("=== FFMPEG CONTROLLER: Conversion completed successfully ===");
+        
+        // Send completion progress update
+        if let Some(ref progress_tx) = request.progress_tx {
+            println!("=== FFMPEG CONTROLLER: Sending completion progress update ===");
+            let _ = progress_tx.send(ConversionProgress {
+                id: request.id,
+                progress: 100.0,
+                speed: None,
+                eta: None,
+                current_pass: None,
+                total_passes: None,
+                error: None,
+            });
+        }
+
+        println!("=== FFMPEG CONTROLLER: Returning output file: {:?} ===", request.output_file);
+        Ok(request.output_file)
+    }
+
+    /// Cut the requested time ranges out of the input, either as one file per range or joined into one
+    pub async fn extract_clips(&self, request: ConversionRequest) -> Result<Vec<PathBuf>> {
+        println!("=== FFMPEG CONTROLLER: Starting clip extraction ===");
+        println!("Input file: {:?}", request.input_file);
+        println!("Output file: {:?}", request.output_file);
+        
+        let clip_options = request.clips.as_ref()
+            .ok_or_else(|| anyhow!("No clips requested"))?;
+        clip_options.validate()?;
+
+        // Chapter selections are resolved against the chapters stored in the input file
+        let chapters = if clip_options.selections.iter().any(|s| matches!(s, ClipSelection::Chapter { .. })) {
+            self.probe_video_info(&request.input_file).await?.chapters
+        } else {
+            Vec::new()
+        };
+        let clips = clip_options.resolve(&chapters)?;
+
+        if let Some(parent) = request.output_file.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Stream copy can't change the container, so keep the input's extension
+        let output_file = if clip_options.seek_mode == SeekMode::KeyframeCopy {
+            match request.input_file.extension() {
+                Some(ext) => request.output_file.with_extension(ext),
+                None => request.output_file.clone(),
+            }
+        } else {
+            request.output_file.clone()
+        };
+
+        let concatenate = clip_options.output == ClipOutput::Concatenate && clips.len() > 1;
+        let mut clip_files = Vec::new();
+
+        for (index, clip) in clips.iter().enumerate() {
+            let clip_file = if concatenate {
+                concat_part_path(&output_file, index)
+            } else {
+                clip_output_path(&output_file, index, clip.label.as_deref())
+            };
+            println!("=== FFMPEG CONTROLLER: Extracting clip {} ({:.3}s - {:.3}s) to {:?} ===", index + 1, clip.start, clip.end, clip_file);
+
+            if let Err(error) = self.run_with_fallback(&request, Some(clip), &clip_file).await {
+                println!("=== FFMPEG CONTROLLER: Clip extraction failed with error: {} ===", error);
+                if concatenate {
+                    for part in &clip_files {
+                        let _ = tokio::fs::remove_file(part).await;
+                    }
+                }
+                send_conversion_failure(&request, &error);
+                return Err(anyhow!("FFmpeg clip extraction failed: {}", error));
+            }
+            clip_files.push(clip_file);
+
+            if let Some(ref progress_tx) = request.progress_tx {
+                let _ = progress_tx.send(ConversionProgress {
+                    id: request.id.clone(),
+                    progress: (index + 1) as f32 / clips.len() as f32 * 100.0,
+                    speed: None,
+                    eta: None,
+                    current_pass: Some((index + 1) as u8),
+                    total_passes: Some(clips.len() as u8),
+                    error: None,
+                });
+            }
+        }
+
+        if concatenate {
+            let result = self.concatenate_files(&clip_files, &output_file).await;
+            for part in &clip_files {
+                let _ = tokio::fs::remove_file(part).await;
+            }
+            if let Err(error) = result {
+                send_conversion_failure(&request, &error.to_string());
+                return Err(error);
+            }
+            clip_files = vec![output_file];
+        }
+
+        println!("=== FFMPEG CONTROLLER: Clip extraction completed: {:?} ===", clip_files);
+        Ok(clip_files)
+    }
+
+    /// Split a file into one stream-copied file per chapter, named by chapter title.
+    /// Returns an empty list when the file has no chapters
+    pub async fn split_by_chapters(&self, input_file: &Path) -> Result<Vec<PathBuf>> {
+        println!("=== FFMPEG CONTROLLER: Splitting {:?} by chapters ===", input_file);
+        
+        let chapters = self.probe_video_info(input_file).await?.chapters;
+        let clips: Vec<ResolvedClip> = chapters.iter()
+            .filter(|c| c.end_time > c.start_time)
+            .map(|c| ResolvedClip {
+                start: c.start_time,
+                end: c.end_time,
+                label: c.title.clone(),
+            })
+            .collect();
+        if clips.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request = ConversionRequest {
+            id: String::new(),
+            input_file: input_file.to_path_buf(),
+            output_file: input_file.to_path_buf(),
+            format: ConversionFormat::H264HighProfile,
+            hardware_acceleration: false,
+            clips: Some(ClipOptions {
+                selections: Vec::new(),
+                output: ClipOutput::Separate,
+                seek_mode: SeekMode::KeyframeCopy,
+            }),
+            progress_tx: None,
+        };
+
+        let mut chapter_files = Vec::new();
+        for (index, clip) in clips.iter().enumerate() {
+            let chapter_file = chapter_output_path(input_file, index, clip.label.as_deref());
+            if let Err(error) = self.run_with_fallback(&request, Some(clip), &chapter_file).await {
+                return Err(anyhow!("Failed to extract chapter {}: {}", index + 1, error));
+            }
+            chapter_files.push(chapter_file);
+        }
+
+        println!("=== FFMPEG CONTROLLER: Wrote {} chapter files ===", chapter_files.len());
+        Ok(chapter_files)
+    }
+
+    /// Join files with identical codecs into one without re-encoding
+    pub async fn concatenate_files(&self, inputs: &[PathBuf], output_file: &Path) -> Result<()> {
+        let ffmpeg_path = self.ffmpeg_path.as_ref()
+            .ok_or_else(|| anyhow!("FFmpeg not initialized"))?;
+
+        // The concat demuxer reads its inputs from a list file; single quotes have to be escaped
+        let list_path = output_file.with_extension("concat.txt");
+        let list = inputs.iter()
+            .map(|path| format!("file '{}'", path.to_string_lossy().replace('\'', "'\\''")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        tokio::fs::write(&list_path, list).await?;
+
+        let output = AsyncCommand::new(ffmpeg_path)
+            .arg("-f").arg("concat")
+            .arg("-safe").arg("0")
+            .arg("-i").arg(&list_path)
+            .arg("-map").arg("0")
+            .arg("-c").arg("copy")
+            .arg("-y")
+            .arg(output_file)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await;
+        let _ = tokio::fs::remove_file(&list_path).await;
+        let output = output?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("Failed to concatenate clips: {}", error));
+        }
+
+        Ok(())
+    }
+
+    async fn run_with_fallback(&self, request: &ConversionRequest, clip: Option<&ResolvedClip>, output_file: &Path) -> Result<(), String> {
+        let stream_copy = clip.is_some() && request.clips.as_ref().map(|c| c.seek_mode) == Some(SeekMode::KeyframeCopy);
+        let encoder = video_codec_for(&request.format)
+            .filter(|_| !stream_copy)
+            .map(|codec| self.capabilities.select_video_encoder(codec, request.hardware_acceleration));
+
+        let result = self.run_conversion(request, encoder.as_ref(), clip, output_file).await;
+
+        // Hardware encoders can be listed but still fail at runtime (no GPU, driver
+        // mismatch, unsupported resolution), so retry the job in software
+        if result.is_err() {
+            if let Some(failed_encoder) = encoder.as_ref().filter(|e| e.hardware.is_some()) {
+                println!("=== FFMPEG CONTROLLER: Hardware encoder {} failed, falling back to software ===", failed_encoder.name);
+                let software_encoder = VideoEncoder::software(failed_encoder.codec);
+                return self.run_conversion(request, Some(&software_encoder), clip, output_file).await;
+            }
+        }
+
+        result
+    }
+
+    // Runs a single FFmpeg pass, returning FFmpeg's stderr as the error on failure
+    async fn run_conversion(&self, request: &ConversionRequest, encoder: Option<&VideoEncoder>, clip: Option<&ResolvedClip>, output_file: &Path) -> Result<(), String> {
+        let ffmpeg_path = self.ffmpeg_path.as_ref()
+            .ok_or_else(|| "FFmpeg not initialized".to_string())?;
+
         let mut cmd = AsyncCommand::new(ffmpeg_path);
         
-        // Input file
+        // Some hardware encoders need their device set up before the input
+        if let Some(encoder) = encoder {
+            add_encoder_input_args(&mut cmd, encoder);
+        }
+        
+        // Seeking on the input is fast, and frame accurate whenever the output is re-encoded
+        if let Some(clip) = clip {
+            cmd.arg("-ss").arg(format!("{:.3}", clip.start));
+        }
+        
+        // Input file. Network inputs go through the configured proxy
+        apply_ffmpeg_proxy(&mut cmd, &request.input_file.to_string_lossy())
+            .map_err(|e| e.to_string())?;
         cmd.arg("-i").arg(&request.input_file);
         
-        // Add format-specific arguments
-        self.add_format_args(&mut cmd, &request.format)?;
+        if let Some(clip) = clip {
+            cmd.arg("-t").arg(format!("{:.3}", clip.end - clip.start));
+        }
+        
+        let stream_copy = clip.is_some() && request.clips.as_ref().map(|c| c.seek_mode) == Some(SeekMode::KeyframeCopy);
+        if stream_copy {
+            // Keyframe copy: cuts snap to the nearest keyframe but nothing is re-encoded
+            // Chapters of the whole source don't line up with a clip, so none are copied
+            cmd.arg("-map").arg("0")
+               .arg("-c").arg("copy")
+               .arg("-avoid_negative_ts").arg("make_zero")
+               .arg("-map_chapters").arg("-1");
+        } else {
+            // Add format-specific arguments
+            self.add_format_args(&mut cmd, &request.format, encoder);
+            
+            // Keep chapter markers and global tags in the MP4/MOV outputs. Clips and
+            // chapter files cover part of the source, so its chapter list is left out
+            if !matches!(request.format, ConversionFormat::MP3Audio) {
+                cmd.arg("-map_metadata").arg("0")
+                   .arg("-map_chapters").arg(if clip.is_some() { "-1" } else { "0" });
+            }
+        }
         
         // Progress reporting
         cmd.arg("-progress").arg("pipe:1");
@@ -126,17 +664,17 @@ impl FFmpegController {
         cmd.arg("-y");
         
         // Output file
-        cmd.arg(&request.output_file);
+        cmd.arg(output_file);
 
-        // Log the full command
-        println!("=== FFMPEG CONTROLLER: Executing command: {:?} ===", cmd);
+        // Log the full command. Only the arguments, since the environment may hold a proxy login
+        println!("=== FFMPEG CONTROLLER: Executing command: {:?} {:?} ===", ffmpeg_path, cmd.as_std().get_args().collect::<Vec<_>>());
 
         // Set up stdio
         cmd.stdout(Stdio::piped())
            .stderr(Stdio::piped());
 
         println!("=== FFMPEG CONTROLLER: Spawning FFmpeg process ===");
-        let mut child = cmd.spawn()?;
+        let mut child = cmd.spawn().map_err(|e| e.to_string())?;
 
         // Monitor conversion progress
         if let Some(ref progress_tx) = request.progress_tx {
@@ -178,58 +716,23 @@ impl FFmpegController {
 
         // Wait for conversion to complete
         println!("=== FFMPEG CONTROLLER: Waiting for conversion to complete ===");
-        let output = child.wait_with_output().await?;
+        let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
 
         if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            println!("=== FFMPEG CONTROLLER: Conversion failed with error: {} ===", error);
-            
-            // Send error progress update
-            if let Some(ref progress_tx) = request.progress_tx {
-                let _ = progress_tx.send(ConversionProgress {
-                    id: request.id.clone(),
-                    progress: 0.0,
-                    speed: None,
-                    eta: None,
-                    current_pass: None,
-                    total_passes: None,
-                    error: Some(error.to_string()),
-                });
-            }
-            
-            return Err(anyhow!("FFmpeg conversion failed: {}", error));
+            return Err(String::from_utf8_lossy(&output.stderr).to_string());
         }
 
-        println!("=== FFMPEG CONTROLLER: Conversion completed successfully ===");
-        
-        // Send completion progress update
-        if let Some(ref progress_tx) = request.progress_tx {
-            println!("=== FFMPEG CONTROLLER: Sending completion progress update ===");
-            let _ = progress_tx.send(ConversionProgress {
-                id: request.id,
-                progress: 100.0,
-                speed: None,
-                eta: None,
-                current_pass: None,
-                total_passes: None,
-                error: None,
-            });
-        }
-
-        println!("=== FFMPEG CONTROLLER: Returning output file: {:?} ===", request.output_file);
-        Ok(request.output_file)
+        Ok(())
     }
 
-    fn add_format_args(&self, cmd: &mut AsyncCommand, format: &ConversionFormat) -> Result<()> {
+    fn add_format_args(&self, cmd: &mut AsyncCommand, format: &ConversionFormat, encoder: Option<&VideoEncoder>) {
         match format {
-            ConversionFormat::H264HighProfile => {
-                // H.264 High Profile @ Level 4.1
-                cmd.arg("-c:v").arg("libx264")
-                   .arg("-profile:v").arg("high")
-                   .arg("-level:v").arg("4.1")
-                   .arg("-preset").arg("medium")
-                   .arg("-crf").arg("18")
-                   .arg("-c:a").arg("aac")
+            ConversionFormat::H264HighProfile | ConversionFormat::HEVC => {
+                let encoder = encoder.cloned().unwrap_or_else(|| {
+                    VideoEncoder::software(video_codec_for(format).unwrap_or(VideoCodec::H264))
+                });
+                add_video_encoder_args(cmd, &encoder);
+                cmd.arg("-c:a").arg("aac")
                    .arg("-b:a").arg("192k")
                    .arg("-movflags").arg("+faststart");
             }
@@ -255,12 +758,12 @@ impl FFmpegController {
                    .arg("-q:a").arg("0");
             }
         }
-        Ok(())
     }
 
     pub fn get_output_extension(&self, format: &ConversionFormat) -> &str {
         match format {
             ConversionFormat::H264HighProfile => "mp4",
+            ConversionFormat::HEVC => "mp4",
             ConversionFormat::DNxHRSQ => "mov",
             ConversionFormat::ProResProxy => "mov",
             ConversionFormat::MP3Audio => "mp3",
@@ -268,20 +771,28 @@ impl FFmpegController {
     }
 
     pub async fn probe_video_info(&self, file_path: &Path) -> Result<VideoInfo> {
-        let _ffmpeg_path = self.ffmpeg_path.as_ref()
-            .ok_or_else(|| anyhow!("FFmpeg not initialized"))?;
+        let ffprobe_path = self.ffprobe_path.as_ref()
+            .ok_or_else(|| anyhow!("ffprobe not found. Please reinstall FFmpeg using the Dependencies tab."))?;
+
+        if !file_path.is_file() {
+            return Err(anyhow!("File not found: {}", file_path.display()));
+        }
 
-        let output = AsyncCommand::new("ffprobe")
+        let output = AsyncCommand::new(ffprobe_path)
             .arg("-v").arg("quiet")
             .arg("-print_format").arg("json")
             .arg("-show_format")
             .arg("-show_streams")
+            .arg("-show_chapters")
             .arg(file_path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .output()
             .await?;
 
         if !output.status.success() {
-            return Err(anyhow!("Failed to probe video info"));
+            let error = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("Failed to probe video info: {}", error));
         }
 
         let json_str = String::from_utf8(output.stdout)?;
@@ -291,27 +802,219 @@ impl FFmpegController {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+fn send_conversion_failure(request: &ConversionRequest, error: &str) {
+    if let Some(ref progress_tx) = request.progress_tx {
+        let _ = progress_tx.send(ConversionProgress {
+            id: request.id.clone(),
+            progress: 0.0,
+            speed: None,
+            eta: None,
+            current_pass: None,
+            total_passes: None,
+            error: Some(error.to_string()),
+        });
+    }
+}
+
+// video.mp4 -> video_clip01.mp4, or video_clip02_Intro.mp4 for chapter selections
+fn clip_output_path(output_file: &Path, index: usize, label: Option<&str>) -> PathBuf {
+    let stem = output_file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let mut name = format!("{}_clip{:02}", stem, index + 1);
+    if let Some(label) = label.map(sanitize_file_name).filter(|l| !l.is_empty()) {
+        name.push('_');
+        name.push_str(&label);
+    }
+    if let Some(ext) = output_file.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    output_file.with_file_name(name)
+}
+
+// video.mp4 -> "video - 03 - Chapter title.mp4"
+fn chapter_output_path(input_file: &Path, index: usize, title: Option<&str>) -> PathBuf {
+    let stem = input_file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let title = title.map(sanitize_file_name)
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| format!("Chapter {}", index + 1));
+    let mut name = format!("{} - {:02} - {}", stem, index + 1, title);
+    if let Some(ext) = input_file.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    input_file.with_file_name(name)
+}
+
+fn concat_part_path(output_file: &Path, index: usize) -> PathBuf {
+    let stem = output_file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = output_file.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    output_file.with_file_name(format!(".{}.part{:02}.{}", stem, index + 1, ext))
+}
+
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string()
+}
+
+fn video_codec_for(format: &ConversionFormat) -> Option<VideoCodec> {
+    match format {
+        ConversionFormat::H264HighProfile => Some(VideoCodec::H264),
+        ConversionFormat::HEVC => Some(VideoCodec::Hevc),
+        _ => None,
+    }
+}
+
+fn add_encoder_input_args(cmd: &mut AsyncCommand, encoder: &VideoEncoder) {
+    if encoder.hardware == Some(HardwareBackend::Vaapi) {
+        cmd.arg("-vaapi_device").arg(VAAPI_DEVICE);
+    }
+}
+
+fn add_video_encoder_args(cmd: &mut AsyncCommand, encoder: &VideoEncoder) {
+    cmd.arg("-c:v").arg(&encoder.name);
+
+    match (encoder.hardware, encoder.codec) {
+        (None, VideoCodec::H264) => {
+            // H.264 High Profile @ Level 4.1
+            cmd.arg("-profile:v").arg("high")
+               .arg("-level:v").arg("4.1")
+               .arg("-preset").arg("medium")
+               .arg("-crf").arg("18");
+        }
+        (None, VideoCodec::Hevc) => {
+            cmd.arg("-preset").arg("medium")
+               .arg("-crf").arg("20");
+        }
+        (Some(HardwareBackend::VideoToolbox), _) => {
+            cmd.arg("-q:v").arg("65");
+        }
+        (Some(HardwareBackend::Nvenc), _) => {
+            cmd.arg("-preset").arg("p5")
+               .arg("-rc").arg("vbr")
+               .arg("-cq").arg("19")
+               .arg("-b:v").arg("0");
+        }
+        (Some(HardwareBackend::Qsv), _) => {
+            cmd.arg("-preset").arg("medium")
+               .arg("-global_quality").arg("20");
+        }
+        (Some(HardwareBackend::Vaapi), _) => {
+            // Frames have to be uploaded to the GPU before a VAAPI encoder can use them
+            cmd.arg("-vf").arg("format=nv12,hwupload")
+               .arg("-qp").arg("20");
+        }
+    }
+
+    match encoder.codec {
+        VideoCodec::H264 => {
+            if encoder.hardware.is_some() {
+                cmd.arg("-profile:v").arg("high");
+            }
+        }
+        VideoCodec::Hevc => {
+            // Apple players only recognise HEVC in MP4 with the hvc1 tag
+            cmd.arg("-tag:v").arg("hvc1");
+        }
+    }
+}
+
+// Parses `ffmpeg -encoders`, whose entries follow a "------" separator line:
+//  V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+fn parse_encoder_list(output: &str) -> Vec<String> {
+    output.lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let _flags = parts.next()?;
+            parts.next().map(String::from)
+        })
+        .collect()
+}
+
+// Parses `ffmpeg -hwaccels`: a header line followed by one method per line
+fn parse_hwaccel_list(output: &str) -> Vec<String> {
+    output.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .map(String::from)
+        .collect()
+}
+
+fn ffprobe_path_for(ffmpeg_path: &Path) -> PathBuf {
+    let filename = if cfg!(windows) { "ffprobe.exe" } else { "ffprobe" };
+
+    // A bare "ffmpeg" means it was found on PATH, so look for ffprobe there as well
+    match ffmpeg_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.join(filename),
+        _ => PathBuf::from(filename),
+    }
+}
+
+#[derive(Debug, Deserialize)]
 struct ProbeData {
     format: ProbeFormat,
+    #[serde(default)]
     streams: Vec<ProbeStream>,
+    #[serde(default)]
+    chapters: Vec<ProbeChapter>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize)]
 struct ProbeFormat {
+    format_name: Option<String>,
+    format_long_name: Option<String>,
     duration: Option<String>,
     size: Option<String>,
     bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Deserialize)]
 struct ProbeStream {
-    codec_type: String,
-    codec_name: String,
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    codec_long_name: Option<String>,
+    profile: Option<String>,
     width: Option<u32>,
     height: Option<u32>,
     r_frame_rate: Option<String>,
+    avg_frame_rate: Option<String>,
+    pix_fmt: Option<String>,
+    bits_per_raw_sample: Option<String>,
+    color_range: Option<String>,
+    color_space: Option<String>,
+    color_transfer: Option<String>,
+    color_primaries: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
     bit_rate: Option<String>,
+    duration: Option<String>,
+    #[serde(default)]
+    disposition: HashMap<String, i64>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+    #[serde(default)]
+    side_data_list: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProbeChapter {
+    start_time: Option<String>,
+    end_time: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -324,41 +1027,311 @@ pub struct VideoInfo {
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub frame_rate: Option<f64>,
+    pub container: Option<String>,
+    pub container_long_name: Option<String>,
+    pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<Chapter>,
+    pub tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum StreamKind {
+    #[serde(rename = "video")]
+    Video,
+    #[serde(rename = "audio")]
+    Audio,
+    #[serde(rename = "subtitle")]
+    Subtitle,
+    #[serde(rename = "data")]
+    Data,
+    #[serde(rename = "attachment")]
+    Attachment,
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: StreamKind,
+    pub codec: Option<String>,
+    pub codec_long_name: Option<String>,
+    pub profile: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    pub bit_rate: Option<u64>,
+    pub duration: Option<f64>,
+    // Video
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<f64>,
+    pub pixel_format: Option<String>,
+    pub bit_depth: Option<u32>,
+    pub color: Option<ColorInfo>,
+    // Audio
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorInfo {
+    pub range: Option<String>,
+    pub space: Option<String>,
+    pub transfer: Option<String>,
+    pub primaries: Option<String>,
+    pub hdr: Option<HdrFormat>,
+    pub mastering_display: Option<MasteringDisplay>,
+    pub content_light_level: Option<ContentLightLevel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum HdrFormat {
+    #[serde(rename = "hdr10")]
+    Hdr10,
+    #[serde(rename = "hlg")]
+    Hlg,
+    #[serde(rename = "dolby_vision")]
+    DolbyVision,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MasteringDisplay {
+    pub red_x: Option<String>,
+    pub red_y: Option<String>,
+    pub green_x: Option<String>,
+    pub green_y: Option<String>,
+    pub blue_x: Option<String>,
+    pub blue_y: Option<String>,
+    pub white_point_x: Option<String>,
+    pub white_point_y: Option<String>,
+    pub min_luminance: Option<String>,
+    pub max_luminance: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentLightLevel {
+    pub max_content: Option<u32>,
+    pub max_average: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start_time: f64,
+    pub end_time: f64,
+    pub title: Option<String>,
+}
+
+/// Chapter list as CSV, one row per chapter with seconds and timecodes
+pub fn chapters_to_csv(chapters: &[Chapter], frame_rate: f64) -> String {
+    let mut csv = String::from("index,start_seconds,end_seconds,start_timecode,end_timecode,title\n");
+    for (index, chapter) in chapters.iter().enumerate() {
+        let title = chapter.title.clone().unwrap_or_else(|| format!("Chapter {}", index + 1));
+        csv.push_str(&format!(
+            "{},{:.3},{:.3},{},{},\"{}\"\n",
+            index + 1,
+            chapter.start_time,
+            chapter.end_time,
+            seconds_to_timecode(chapter.start_time, frame_rate),
+            seconds_to_timecode(chapter.end_time, frame_rate),
+            title.replace('"', "\"\""),
+        ));
+    }
+    csv
+}
+
+/// Chapter list as a CMX3600 EDL with one event per chapter
+pub fn chapters_to_edl(chapters: &[Chapter], title: &str, frame_rate: f64) -> String {
+    let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", title.lines().next().unwrap_or_default());
+    for (index, chapter) in chapters.iter().enumerate() {
+        let start = seconds_to_timecode(chapter.start_time, frame_rate);
+        let end = seconds_to_timecode(chapter.end_time, frame_rate);
+        edl.push_str(&format!("{:03}  AX       V     C        {} {} {} {}\n", index + 1, start, end, start, end));
+        let name = chapter.title.clone().unwrap_or_else(|| format!("Chapter {}", index + 1));
+        edl.push_str(&format!("* FROM CLIP NAME: {}\n\n", name.replace(['\r', '\n'], " ")));
+    }
+    edl
+}
+
+// Non-drop-frame HH:MM:SS:FF using the nominal (rounded) frame rate
+fn seconds_to_timecode(seconds: f64, frame_rate: f64) -> String {
+    let fps = if frame_rate.is_finite() && frame_rate >= 1.0 { frame_rate.round() as u64 } else { 30 };
+    let total_frames = (seconds.max(0.0) * fps as f64).round() as u64;
+    let frames = total_frames % fps;
+    let total_seconds = total_frames / fps;
+    format!("{:02}:{:02}:{:02}:{:02}", total_seconds / 3600, (total_seconds / 60) % 60, total_seconds % 60, frames)
 }
 
 impl VideoInfo {
     fn from_probe_data(data: ProbeData) -> Self {
-        let video_stream = data.streams.iter().find(|s| s.codec_type == "video");
-        let audio_stream = data.streams.iter().find(|s| s.codec_type == "audio");
+        let streams: Vec<StreamInfo> = data.streams.into_iter().map(StreamInfo::from_probe_stream).collect();
+        let video_stream = streams.iter().find(|s| s.kind == StreamKind::Video);
+        let audio_stream = streams.iter().find(|s| s.kind == StreamKind::Audio);
+
+        let chapters = data.chapters.into_iter()
+            .filter_map(|c| {
+                Some(Chapter {
+                    start_time: c.start_time?.parse().ok()?,
+                    end_time: c.end_time?.parse().ok()?,
+                    title: tag_value(&c.tags, "title"),
+                })
+            })
+            .collect();
 
         Self {
             duration: data.format.duration.and_then(|d| d.parse::<f64>().ok()),
             file_size: data.format.size.and_then(|s| s.parse::<u64>().ok()),
             bit_rate: data.format.bit_rate.and_then(|b| b.parse::<u64>().ok()),
-            video_codec: video_stream.map(|s| s.codec_name.clone()),
-            audio_codec: audio_stream.map(|s| s.codec_name.clone()),
+            video_codec: video_stream.and_then(|s| s.codec.clone()),
+            audio_codec: audio_stream.and_then(|s| s.codec.clone()),
             width: video_stream.and_then(|s| s.width),
             height: video_stream.and_then(|s| s.height),
-            frame_rate: video_stream.and_then(|s| {
-                s.r_frame_rate.as_ref().and_then(|r| {
-                    let parts: Vec<&str> = r.split('/').collect();
-                    if parts.len() == 2 {
-                        let num = parts[0].parse::<f64>().ok()?;
-                        let den = parts[1].parse::<f64>().ok()?;
-                        if den != 0.0 {
-                            Some(num / den)
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                })
-            }),
+            frame_rate: video_stream.and_then(|s| s.frame_rate),
+            container: data.format.format_name,
+            container_long_name: data.format.format_long_name,
+            chapters,
+            tags: data.format.tags,
+            streams,
+        }
+    }
+
+    pub fn has_video(&self) -> bool {
+        self.streams.iter().any(|s| s.kind == StreamKind::Video)
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.streams.iter().any(|s| s.kind == StreamKind::Audio)
+    }
+
+    pub fn is_hdr(&self) -> bool {
+        self.streams.iter().any(|s| s.color.as_ref().and_then(|c| c.hdr.as_ref()).is_some())
+    }
+}
+
+impl StreamInfo {
+    fn from_probe_stream(stream: ProbeStream) -> Self {
+        let kind = match stream.codec_type.as_deref() {
+            Some("video") => StreamKind::Video,
+            Some("audio") => StreamKind::Audio,
+            Some("subtitle") => StreamKind::Subtitle,
+            Some("data") => StreamKind::Data,
+            Some("attachment") => StreamKind::Attachment,
+            _ => StreamKind::Unknown,
+        };
+
+        let color = if kind == StreamKind::Video {
+            Some(ColorInfo::from_probe_stream(&stream))
+        } else {
+            None
+        };
+
+        // Prefer the real frame rate, but fall back to the average for VFR sources
+        let frame_rate = stream.r_frame_rate.as_deref()
+            .and_then(parse_frame_rate)
+            .or_else(|| stream.avg_frame_rate.as_deref().and_then(parse_frame_rate));
+
+        Self {
+            index: stream.index,
+            codec: stream.codec_name,
+            codec_long_name: stream.codec_long_name,
+            profile: stream.profile,
+            language: tag_value(&stream.tags, "language").filter(|l| l != "und"),
+            title: tag_value(&stream.tags, "title"),
+            is_default: stream.disposition.get("default").copied().unwrap_or(0) == 1,
+            is_forced: stream.disposition.get("forced").copied().unwrap_or(0) == 1,
+            bit_rate: stream.bit_rate.and_then(|b| b.parse().ok()),
+            duration: stream.duration.and_then(|d| d.parse().ok()),
+            width: stream.width,
+            height: stream.height,
+            frame_rate: if kind == StreamKind::Video { frame_rate } else { None },
+            pixel_format: stream.pix_fmt,
+            bit_depth: stream.bits_per_raw_sample.and_then(|b| b.parse().ok()),
+            color,
+            sample_rate: stream.sample_rate.and_then(|r| r.parse().ok()),
+            channels: stream.channels,
+            channel_layout: stream.channel_layout,
+            kind,
         }
     }
 }
 
+impl ColorInfo {
+    fn from_probe_stream(stream: &ProbeStream) -> Self {
+        let side_data_of = |kind: &str| {
+            stream.side_data_list.iter()
+                .find(|entry| entry["side_data_type"].as_str() == Some(kind))
+        };
+
+        let mastering_display = side_data_of("Mastering display metadata").map(|entry| {
+            let field = |key: &str| entry[key].as_str().map(String::from);
+            MasteringDisplay {
+                red_x: field("red_x"),
+                red_y: field("red_y"),
+                green_x: field("green_x"),
+                green_y: field("green_y"),
+                blue_x: field("blue_x"),
+                blue_y: field("blue_y"),
+                white_point_x: field("white_point_x"),
+                white_point_y: field("white_point_y"),
+                min_luminance: field("min_luminance"),
+                max_luminance: field("max_luminance"),
+            }
+        });
+
+        let content_light_level = side_data_of("Content light level metadata").map(|entry| {
+            ContentLightLevel {
+                max_content: entry["max_content"].as_u64().map(|v| v as u32),
+                max_average: entry["max_average"].as_u64().map(|v| v as u32),
+            }
+        });
+
+        let hdr = if side_data_of("DOVI configuration record").is_some() {
+            Some(HdrFormat::DolbyVision)
+        } else {
+            match stream.color_transfer.as_deref() {
+                Some("smpte2084") => Some(HdrFormat::Hdr10),
+                Some("arib-std-b67") => Some(HdrFormat::Hlg),
+                _ => None,
+            }
+        };
+
+        Self {
+            range: stream.color_range.clone(),
+            space: stream.color_space.clone(),
+            transfer: stream.color_transfer.clone(),
+            primaries: stream.color_primaries.clone(),
+            hdr,
+            mastering_display,
+            content_light_level,
+        }
+    }
+}
+
+// Tag keys differ in case between containers (e.g. "language" in MKV, "LANGUAGE" in some MXF)
+fn tag_value(tags: &HashMap<String, String>, key: &str) -> Option<String> {
+    tags.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_frame_rate(rate: &str) -> Option<f64> {
+    let parts: Vec<&str> = rate.split('/').collect();
+    if parts.len() == 2 {
+        let num = parts[0].parse::<f64>().ok()?;
+        let den = parts[1].parse::<f64>().ok()?;
+        if den != 0.0 && num != 0.0 {
+            Some(num / den)
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
 fn parse_ffmpeg_progress(line: &str) -> Option<f32> {
     // FFmpeg progress output with -progress pipe:1 gives us key=value pairs
     // We need to track out_time and duration to calculate percentage
@@ -383,4 +1356,266 @@ fn parse_ffmpeg_progress(line: &str) -> Option<f32> {
     
     // For now, we'll just return None and rely on the completion detection
     None
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HDR_PROBE: &str = r#"{
+        "streams": [
+            {
+                "index": 0, "codec_name": "hevc", "codec_long_name": "H.265 / HEVC", "profile": "Main 10",
+                "codec_type": "video", "width": 3840, "height": 2160, "pix_fmt": "yuv420p10le",
+                "color_range": "tv", "color_space": "bt2020nc", "color_transfer": "smpte2084",
+                "color_primaries": "bt2020", "r_frame_rate": "24000/1001", "avg_frame_rate": "24000/1001",
+                "disposition": { "default": 1, "forced": 0 },
+                "tags": { "language": "und" },
+                "side_data_list": [
+                    { "side_data_type": "Mastering display metadata", "red_x": "34000/50000", "max_luminance": "10000000/10000", "min_luminance": "50/10000" },
+                    { "side_data_type": "Content light level metadata", "max_content": 1000, "max_average": 400 }
+                ]
+            },
+            {
+                "index": 1, "codec_name": "eac3", "codec_type": "audio", "sample_rate": "48000",
+                "channels": 6, "channel_layout": "5.1(side)", "bit_rate": "640000",
+                "disposition": { "default": 1, "forced": 0 },
+                "tags": { "language": "eng", "title": "Surround" }
+            },
+            {
+                "index": 2, "codec_name": "subrip", "codec_type": "subtitle",
+                "disposition": { "default": 0, "forced": 1 },
+                "tags": { "LANGUAGE": "ger" }
+            }
+        ],
+        "chapters": [
+            { "id": 0, "start_time": "0.000000", "end_time": "65.500000", "tags": { "title": "Intro" } },
+            { "id": 1, "start_time": "65.500000", "end_time": "300.000000", "tags": {} }
+        ],
+        "format": {
+            "format_name": "matroska,webm", "format_long_name": "Matroska / WebM",
+            "duration": "300.000000", "size": "123456789", "bit_rate": "3292181",
+            "tags": { "title": "Sample", "ENCODER": "Lavf60.3.100" }
+        }
+    }"#;
+
+    #[test]
+    fn test_probe_parsing_streams_and_hdr() {
+        let probe_data: ProbeData = serde_json::from_str(HDR_PROBE).unwrap();
+        let info = VideoInfo::from_probe_data(probe_data);
+
+        assert_eq!(info.streams.len(), 3);
+        assert_eq!(info.video_codec.as_deref(), Some("hevc"));
+        assert_eq!(info.audio_codec.as_deref(), Some("eac3"));
+        assert_eq!(info.width, Some(3840));
+        assert!((info.frame_rate.unwrap() - 23.976).abs() < 0.001);
+        assert!(info.is_hdr());
+
+        let video = &info.streams[0];
+        assert_eq!(video.language, None); // "und" is not a language
+        assert_eq!(video.bit_depth, None);
+        let color = video.color.as_ref().unwrap();
+        assert_eq!(color.hdr, Some(HdrFormat::Hdr10));
+        assert_eq!(color.space.as_deref(), Some("bt2020nc"));
+        assert_eq!(color.content_light_level.as_ref().unwrap().max_content, Some(1000));
+        assert_eq!(color.mastering_display.as_ref().unwrap().max_luminance.as_deref(), Some("10000000/10000"));
+
+        let audio = &info.streams[1];
+        assert_eq!(audio.language.as_deref(), Some("eng"));
+        assert_eq!(audio.channels, Some(6));
+        assert_eq!(audio.sample_rate, Some(48000));
+        assert!(audio.color.is_none());
+
+        let subtitle = &info.streams[2];
+        assert_eq!(subtitle.kind, StreamKind::Subtitle);
+        assert_eq!(subtitle.language.as_deref(), Some("ger"));
+        assert!(subtitle.is_forced);
+    }
+
+    #[test]
+    fn test_probe_parsing_chapters_and_tags() {
+        let probe_data: ProbeData = serde_json::from_str(HDR_PROBE).unwrap();
+        let info = VideoInfo::from_probe_data(probe_data);
+
+        assert_eq!(info.chapters.len(), 2);
+        assert_eq!(info.chapters[0].title.as_deref(), Some("Intro"));
+        assert_eq!(info.chapters[1].title, None);
+        assert_eq!(info.chapters[1].end_time, 300.0);
+        assert_eq!(info.container.as_deref(), Some("matroska,webm"));
+        assert_eq!(info.tags.get("ENCODER").map(String::as_str), Some("Lavf60.3.100"));
+    }
+
+    #[test]
+    fn test_ffprobe_resolved_next_to_ffmpeg() {
+        let ffprobe = if cfg!(windows) { "ffprobe.exe" } else { "ffprobe" };
+
+        assert_eq!(ffprobe_path_for(Path::new("ffmpeg")), PathBuf::from(ffprobe));
+        assert_eq!(
+            ffprobe_path_for(Path::new("/opt/grabzilla/ffmpeg/bin/ffmpeg")),
+            Path::new("/opt/grabzilla/ffmpeg/bin").join(ffprobe)
+        );
+    }
+
+    const ENCODERS_OUTPUT: &str = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V....D h264_nvenc           NVIDIA NVENC H.264 encoder (codec h264)
+ V....D h264_vaapi           H.264/AVC (VAAPI) (codec h264)
+ V....D hevc_vaapi           H.265/HEVC (VAAPI) (codec hevc)
+ V....D libx265              libx265 H.265 / HEVC (codec hevc)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+
+    #[test]
+    fn test_encoder_detection_and_preference() {
+        let hwaccels = "Hardware acceleration methods:\nvdpau\ncuda\nvaapi\n\n";
+        let capabilities = EncoderCapabilities::from_ffmpeg_output(ENCODERS_OUTPUT, hwaccels);
+
+        assert!(capabilities.encoders.contains(&"aac".to_string()));
+        assert_eq!(capabilities.hwaccels, vec!["vdpau", "cuda", "vaapi"]);
+
+        let names: Vec<_> = capabilities.hardware_encoders.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["h264_nvenc", "h264_vaapi", "hevc_vaapi"]);
+
+        // NVENC is preferred over VAAPI for H.264, HEVC only has VAAPI available
+        assert_eq!(capabilities.select_video_encoder(VideoCodec::H264, true).name, "h264_nvenc");
+        assert_eq!(capabilities.select_video_encoder(VideoCodec::Hevc, true).name, "hevc_vaapi");
+        assert_eq!(capabilities.select_video_encoder(VideoCodec::H264, false).name, "libx264");
+    }
+
+    #[test]
+    fn test_vaapi_encoder_ignored_without_hwaccel() {
+        let capabilities = EncoderCapabilities::from_ffmpeg_output(ENCODERS_OUTPUT, "Hardware acceleration methods:\n");
+
+        let names: Vec<_> = capabilities.hardware_encoders.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["h264_nvenc"]);
+        assert_eq!(capabilities.select_video_encoder(VideoCodec::Hevc, true), VideoEncoder::software(VideoCodec::Hevc));
+    }
+
+    #[cfg(unix)]
+    fn write_fake_ffmpeg(name: &str, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("grabzilla-fake-ffmpeg-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("ffmpeg");
+        std::fs::write(&path, script.replace("$DIR", &dir.to_string_lossy())).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_fake_ffmpeg_without_hardware_encoders() {
+        let ffmpeg_path = write_fake_ffmpeg("software-only", r#"#!/bin/sh
+case "$*" in
+  *-encoders*) printf 'Encoders:\n ------\n V....D libx264 libx264 H.264\n A....D aac AAC\n' ;;
+  *-hwaccels*) printf 'Hardware acceleration methods:\n\n' ;;
+esac
+"#);
+
+        let mut controller = FFmpegController::new().unwrap();
+        controller.ffmpeg_path = Some(ffmpeg_path);
+        controller.detect_capabilities().await;
+
+        assert!(controller.get_capabilities().hardware_encoders.is_empty());
+        assert_eq!(controller.get_capabilities().select_video_encoder(VideoCodec::H264, true).name, "libx264");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hardware_failure_falls_back_to_software() {
+        let ffmpeg_path = write_fake_ffmpeg("nvenc-broken", r#"#!/bin/sh
+case "$*" in
+  *-encoders*) printf 'Encoders:\n ------\n V....D libx264 libx264 H.264\n V....D h264_nvenc NVENC\n' ;;
+  *-hwaccels*) printf 'Hardware acceleration methods:\ncuda\n' ;;
+  *h264_nvenc*) echo "Cannot load libcuda.so.1" >&2; exit 1 ;;
+  *) echo "$*" > "$DIR/last_run" ;;
+esac
+"#);
+        let log_path = ffmpeg_path.parent().unwrap().join("last_run");
+
+        let mut controller = FFmpegController::new().unwrap();
+        controller.ffmpeg_path = Some(ffmpeg_path);
+        controller.detect_capabilities().await;
+        assert_eq!(controller.get_capabilities().select_video_encoder(VideoCodec::H264, true).name, "h264_nvenc");
+
+        let result = controller.convert_video(ConversionRequest {
+            id: "fallback".to_string(),
+            input_file: PathBuf::from("input.webm"),
+            output_file: std::env::temp_dir().join("grabzilla-fallback-output.mp4"),
+            format: ConversionFormat::H264HighProfile,
+            hardware_acceleration: true,
+            clips: None,
+            progress_tx: None,
+        }).await;
+
+        assert!(result.is_ok());
+        let last_run = std::fs::read_to_string(log_path).unwrap();
+        assert!(last_run.contains("-c:v libx264"));
+    }
+
+    #[test]
+    fn test_clip_selection_resolution() {
+        let options: ClipOptions = serde_json::from_str(r#"{
+            "selections": [
+                {"type": "range", "start": 10.0, "end": 25.5},
+                {"type": "chapter", "title": "outro"}
+            ],
+            "output": "concatenate"
+        }"#).unwrap();
+        assert!(options.validate().is_ok());
+        assert_eq!(options.output, ClipOutput::Concatenate);
+        assert_eq!(options.seek_mode, SeekMode::Accurate);
+
+        let chapters = vec![
+            Chapter { start_time: 0.0, end_time: 60.0, title: Some("Intro".to_string()) },
+            Chapter { start_time: 60.0, end_time: 90.0, title: Some("Outro".to_string()) },
+        ];
+        let clips = options.resolve(&chapters).unwrap();
+        assert_eq!(clips[0], ResolvedClip { start: 10.0, end: 25.5, label: None });
+        assert_eq!(clips[1], ResolvedClip { start: 60.0, end: 90.0, label: Some("Outro".to_string()) });
+
+        assert!(options.resolve(&chapters[..1]).is_err());
+
+        let invalid = ClipOptions {
+            selections: vec![ClipSelection::Range { start: 30.0, end: 10.0 }],
+            output: ClipOutput::Separate,
+            seek_mode: SeekMode::KeyframeCopy,
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_clip_output_path() {
+        let output = Path::new("/videos/talk.mp4");
+        assert_eq!(clip_output_path(output, 0, None), PathBuf::from("/videos/talk_clip01.mp4"));
+        assert_eq!(clip_output_path(output, 1, Some("Q&A: Part 1/2")), PathBuf::from("/videos/talk_clip02_Q&A_ Part 1_2.mp4"));
+    }
+
+    #[test]
+    fn test_chapter_export() {
+        let chapters = vec![
+            Chapter { start_time: 0.0, end_time: 61.5, title: Some("Intro, \"cold open\"".to_string()) },
+            Chapter { start_time: 61.5, end_time: 3725.0, title: None },
+        ];
+
+        let csv = chapters_to_csv(&chapters, 25.0);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[1], r#"1,0.000,61.500,00:00:00:00,00:01:01:13,"Intro, ""cold open""""#);
+        assert_eq!(rows[2], r#"2,61.500,3725.000,00:01:01:13,01:02:05:00,"Chapter 2""#);
+
+        let edl = chapters_to_edl(&chapters, "Talk", 29.97);
+        assert!(edl.starts_with("TITLE: Talk\nFCM: NON-DROP FRAME\n"));
+        assert!(edl.contains("001  AX       V     C        00:00:00:00 00:01:01:15 00:00:00:00 00:01:01:15\n"));
+        assert!(edl.contains("* FROM CLIP NAME: Chapter 2\n"));
+    }
+
+    #[test]
+    fn test_chapter_output_path() {
+        let input = Path::new("/videos/talk.mkv");
+        assert_eq!(chapter_output_path(input, 2, Some("Q&A")), PathBuf::from("/videos/talk - 03 - Q&A.mkv"));
+        assert_eq!(chapter_output_path(input, 0, Some("  ")), PathBuf::from("/videos/talk - 01 - Chapter 1.mkv"));
+    }
+}
@@ -8,6 +8,25 @@ mod ffmpeg_controller;
 mod download_manager;
 mod update_manager;
 mod dependency_manager;
+mod thumbnail_manager;
+mod metadata_sidecar;
+mod metadata_cache;
+mod metadata_prefetcher;
+mod metadata_resolver;
+mod playlist_expander;
+mod playlist_filter;
+mod credential_manager;
+mod proxy_manager;
+mod ytdlp_invocation;
+mod short_url_resolver;
+mod link_file_reader;
+mod bookmark_importer;
+mod text_preprocessor;
+mod page_video_finder;
+mod public_fetch;
+mod platform_registry;
+#[cfg(test)]
+mod test_server;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -24,28 +43,63 @@ pub fn run() {
       // The updater plugin can be re-enabled now that the manager is fixed
       app.handle().plugin(tauri_plugin_updater::Builder::new().build())?;
       
+      // Platform overrides, proxy routes and the yt-dlp profile have to be in effect before the first request goes out
+      commands::load_platform_registry(app.handle());
+      commands::get_proxy_manager(app.handle());
+      commands::get_ytdlp_profile_manager(app.handle());
+      
       Ok(())
     })
+    // Queue thumbnails are loaded as thumbnail://localhost/<encoded url> and served from the cache
+    .register_asynchronous_uri_scheme_protocol("thumbnail", |ctx, request, responder| {
+      let app_handle = ctx.app_handle().clone();
+      tauri::async_runtime::spawn(async move {
+        responder.respond(thumbnail_response(&app_handle, request.uri().path()).await);
+      });
+    })
     .invoke_handler(tauri::generate_handler![
       commands::extract_urls_from_text,
+      commands::extract_urls_from_file,
+      commands::extract_urls_from_file_contents,
+      commands::list_bookmark_folders,
+      commands::import_bookmarks,
+      commands::find_videos_on_page,
       commands::get_supported_platforms,
       commands::validate_single_url,
+      commands::classify_urls,
       commands::clean_url,
       commands::get_default_download_dir,
       commands::test_connection,
       commands::get_video_metadata,
-      commands::extract_playlist_videos,
+      commands::prefetch_metadata,
+      commands::expand_playlist,
+      commands::preview_playlist,
       commands::get_basic_video_metadata,
       commands::start_download,
+      commands::regenerate_sidecars,
       commands::set_max_concurrent_downloads,
       commands::cancel_download,
       commands::convert_video_file,
+      commands::probe_media,
+      commands::export_chapters,
+      commands::clear_thumbnail_cache,
+      commands::clear_metadata_cache,
+      commands::list_credential_profiles,
+      commands::save_credential_profile,
+      commands::delete_credential_profile,
+      commands::get_encoder_capabilities,
       commands::generate_conversion_filename,
       commands::check_file_exists,
       commands::check_privilege_elevation,
       commands::validate_file_path,
       commands::expand_path,
       commands::validate_network_url,
+      commands::get_proxy_settings,
+      commands::save_proxy_settings,
+      commands::test_proxy,
+      commands::get_ytdlp_profile,
+      commands::save_ytdlp_profile,
+      commands::reset_ytdlp_profile,
       commands::check_for_updates,
       commands::set_update_channel,
       commands::rollback_update,
@@ -60,3 +114,25 @@ pub fn run() {
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+async fn thumbnail_response(app_handle: &tauri::AppHandle, path: &str) -> tauri::http::Response<Vec<u8>> {
+  let source_url = urlencoding::decode(path.trim_start_matches('/'))
+    .map(|url| url.into_owned())
+    .unwrap_or_default();
+
+  let response = match commands::get_thumbnail_manager(app_handle).get_thumbnail(&source_url).await {
+    Ok(bytes) => tauri::http::Response::builder()
+      .status(200)
+      .header(tauri::http::header::CONTENT_TYPE, "image/jpeg")
+      .header(tauri::http::header::CACHE_CONTROL, "max-age=86400")
+      .body(bytes),
+    Err(e) => {
+      println!("=== THUMBNAIL: Failed to load {}: {} ===", source_url, e);
+      tauri::http::Response::builder()
+        .status(404)
+        .body(e.to_string().into_bytes())
+    }
+  };
+
+  response.unwrap_or_else(|_| tauri::http::Response::new(Vec::new()))
+}
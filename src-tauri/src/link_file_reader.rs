@@ -0,0 +1,383 @@
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+// Link lists and documents, not media; anything bigger is almost certainly the wrong file
+pub const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+// Limit for one decompressed part of a .docx, since a small archive can inflate enormously
+const MAX_DOCX_PART_SIZE: u64 = 20 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkFileKind {
+    Text,
+    InternetShortcut,
+    Webloc,
+    Html,
+    Csv,
+    Docx,
+    Pdf,
+}
+
+impl LinkFileKind {
+    /// Picks the kind from the extension, falling back to the file's leading bytes for
+    /// unknown or missing extensions
+    pub fn detect(file_name: &str, bytes: &[u8]) -> Self {
+        let extension = Path::new(file_name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        match extension.as_str() {
+            "txt" => Self::Text,
+            "url" => Self::InternetShortcut,
+            "webloc" => Self::Webloc,
+            "html" | "htm" => Self::Html,
+            "csv" => Self::Csv,
+            "docx" => Self::Docx,
+            "pdf" => Self::Pdf,
+            _ => Self::sniff(bytes),
+        }
+    }
+
+    fn sniff(bytes: &[u8]) -> Self {
+        let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+        let head = head.trim_start_matches('\u{feff}').trim_start();
+
+        if bytes.starts_with(b"%PDF-") {
+            Self::Pdf
+        } else if bytes.starts_with(b"PK\x03\x04") {
+            Self::Docx
+        } else if bytes.starts_with(b"bplist") || head.contains("<!doctype plist") || head.contains("<plist") {
+            Self::Webloc
+        } else if head.starts_with("[internetshortcut]") {
+            Self::InternetShortcut
+        } else if head.starts_with("<!doctype html") || head.starts_with("<html") {
+            Self::Html
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// Reads a dropped or imported file and returns the text to run URL extraction over
+pub fn read_link_file(path: &Path) -> Result<String> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_FILE_SIZE {
+        return Err(anyhow!("{} is too large to import ({} MB max)", path.display(), MAX_FILE_SIZE / 1024 / 1024));
+    }
+
+    let bytes = std::fs::read(path)?;
+    let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    extract_link_text(&file_name, &bytes)
+}
+
+/// The links in a file's contents, one per line; plain text files come back whole so
+/// URLs can be found anywhere in them
+pub fn extract_link_text(file_name: &str, bytes: &[u8]) -> Result<String> {
+    let kind = LinkFileKind::detect(file_name, bytes);
+    println!("=== LINK FILE: {} read as {:?} ===", file_name, kind);
+
+    let links = match kind {
+        LinkFileKind::Text => return Ok(decode_text(bytes)),
+        LinkFileKind::InternetShortcut => internet_shortcut_links(&decode_text(bytes)),
+        LinkFileKind::Webloc => webloc_links(bytes)?,
+        LinkFileKind::Html => html_links(&decode_text(bytes)),
+        LinkFileKind::Csv => csv_fields(bytes)?,
+        LinkFileKind::Docx => docx_links(bytes)?,
+        LinkFileKind::Pdf => pdf_links(bytes)?,
+    };
+    Ok(links.join("\n"))
+}
+
+// Notepad still writes UTF-16 with a BOM when asked for "Unicode"
+fn decode_text(bytes: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    if let Some(rest) = bytes.strip_prefix(b"\xff\xfe") {
+        utf16(rest, u16::from_le_bytes)
+    } else if let Some(rest) = bytes.strip_prefix(b"\xfe\xff") {
+        utf16(rest, u16::from_be_bytes)
+    } else {
+        let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+        String::from_utf8_lossy(bytes).to_string()
+    }
+}
+
+// Windows .url shortcuts are INI files; the target is URL= in [InternetShortcut]
+fn internet_shortcut_links(text: &str) -> Vec<String> {
+    let mut in_shortcut_section = false;
+    let mut links = Vec::new();
+
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_shortcut_section = line.eq_ignore_ascii_case("[InternetShortcut]");
+        } else if in_shortcut_section {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("URL") {
+                    links.push(value.trim().to_string());
+                }
+            }
+        }
+    }
+    links
+}
+
+// .webloc is a property list, XML or binary, with the target under "URL"
+fn webloc_links(bytes: &[u8]) -> Result<Vec<String>> {
+    let value = plist::Value::from_reader(Cursor::new(bytes))
+        .map_err(|e| anyhow!("Invalid .webloc file: {}", e))?;
+
+    Ok(value.as_dictionary()
+        .and_then(|dictionary| dictionary.get("URL"))
+        .and_then(|url| url.as_string())
+        .map(|url| vec![url.to_string()])
+        .unwrap_or_default())
+}
+
+fn html_links(html: &str) -> Vec<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("a[href], area[href]").unwrap();
+
+    document.select(&selector)
+        .filter_map(|element| element.value().attr("href"))
+        .map(|href| href.trim().to_string())
+        // Relative links have nothing to resolve against
+        .filter(|href| href.starts_with("http://") || href.starts_with("https://"))
+        .collect()
+}
+
+// Columns vary from export to export, so every field is a candidate
+fn csv_fields(bytes: &[u8]) -> Result<Vec<String>> {
+    let text = decode_text(bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut fields = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| anyhow!("Invalid CSV file: {}", e))?;
+        fields.extend(record.iter().map(str::trim).filter(|field| !field.is_empty()).map(str::to_string));
+    }
+    Ok(fields)
+}
+
+// Hyperlink targets live in the part relationships (document, headers, footnotes...) as
+// external Relationship entries; URLs typed as plain text are picked up from the body
+fn docx_links(bytes: &[u8]) -> Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| anyhow!("Invalid .docx file: {}", e))?;
+
+    let relationship_regex = Regex::new(r#"<Relationship\b[^>]*>"#).unwrap();
+    let attribute_regex = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+    // Text runs, plus the paragraph ends and line breaks that separate them
+    let text_regex = Regex::new(r#"<w:t(?:\s[^>]*)?>([^<]*)</w:t>|</w:p>|<w:(?:br|cr)\b[^>]*/>"#).unwrap();
+
+    let mut links = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        let name = file.name().to_string();
+        let is_relationships = name.starts_with("word/_rels/") && name.ends_with(".rels");
+        if !is_relationships && name != "word/document.xml" {
+            continue;
+        }
+
+        // The declared size can lie, so reading is capped as well
+        if file.size() > MAX_DOCX_PART_SIZE {
+            return Err(anyhow!("{} in the .docx file is too large", name));
+        }
+        let mut xml = String::new();
+        (&mut file).take(MAX_DOCX_PART_SIZE + 1).read_to_string(&mut xml)?;
+        if xml.len() as u64 > MAX_DOCX_PART_SIZE {
+            return Err(anyhow!("{} in the .docx file is too large", name));
+        }
+
+        if is_relationships {
+            for relationship in relationship_regex.find_iter(&xml) {
+                let attribute = |wanted: &str| attribute_regex.captures_iter(relationship.as_str())
+                    .find(|captures| &captures[1] == wanted)
+                    .map(|captures| captures[2].to_string());
+
+                let is_hyperlink = attribute("Type").is_some_and(|kind| kind.ends_with("/hyperlink"));
+                if let (true, Some(target)) = (is_hyperlink, attribute("Target")) {
+                    links.push(unescape_xml(&target));
+                }
+            }
+        } else {
+            // Runs are joined without spaces; Word splits URLs across runs when it spell-checks them.
+            // Paragraphs and breaks become newlines so a URL doesn't run into the next line
+            let text: String = text_regex.captures_iter(&xml)
+                .map(|captures| match captures.get(1) {
+                    Some(run) => unescape_xml(run.as_str()),
+                    None => "\n".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("");
+            links.push(text);
+        }
+    }
+    Ok(links)
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+// Link annotations with a URI action; text that merely looks like a URL isn't clickable in
+// the PDF either, so it is left alone
+fn pdf_links(bytes: &[u8]) -> Result<Vec<String>> {
+    use lopdf::{Document, Object};
+
+    let document = Document::load_mem(bytes).map_err(|e| anyhow!("Invalid PDF file: {}", e))?;
+    let resolve = |object: &Object| -> Option<Object> {
+        document.dereference(object).ok().map(|(_, object)| object.clone())
+    };
+
+    let mut links = Vec::new();
+    for page_id in document.get_pages().into_values() {
+        let Ok(page) = document.get_dictionary(page_id) else { continue };
+        let Some(annotations) = page.get(b"Annots").ok().and_then(resolve) else { continue };
+        let Ok(annotations) = annotations.as_array() else { continue };
+
+        for annotation in annotations.iter().filter_map(resolve) {
+            let Ok(annotation) = annotation.as_dict() else { continue };
+            let is_link = annotation.get(b"Subtype").and_then(Object::as_name).is_ok_and(|name| name == b"Link");
+            let Some(action) = annotation.get(b"A").ok().filter(|_| is_link).and_then(resolve) else { continue };
+            let Ok(action) = action.as_dict() else { continue };
+
+            if action.get(b"S").and_then(Object::as_name).is_ok_and(|name| name == b"URI") {
+                if let Ok(uri) = action.get(b"URI").and_then(Object::as_str) {
+                    links.push(String::from_utf8_lossy(uri).trim().to_string());
+                }
+            }
+        }
+    }
+    Ok(links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn extract(file_name: &str, bytes: &[u8]) -> Vec<String> {
+        let text = extract_link_text(file_name, bytes).unwrap();
+        crate::url_parser::URLExtractor::new().unwrap()
+            .extract_urls(&text).unwrap()
+            .urls.into_iter().map(|url| url.url).collect()
+    }
+
+    #[test]
+    fn test_shortcut_files() {
+        let shortcut = b"[DEFAULT]\r\nBASEURL=https://example.com/\r\n[InternetShortcut]\r\nURL=https://www.youtube.com/watch?v=dQw4w9WgXcQ\r\nIconIndex=0\r\n";
+        assert_eq!(extract("Video.url", shortcut), vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+
+        let webloc = br#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0"><dict><key>URL</key><string>https://vimeo.com/76979871</string></dict></plist>"#;
+        assert_eq!(extract("Video.webloc", webloc), vec!["https://vimeo.com/76979871"]);
+        // Same content without an extension is recognised from the header
+        assert_eq!(LinkFileKind::detect("dropped", webloc), LinkFileKind::Webloc);
+
+        // UTF-16 text as saved by Notepad
+        let mut utf16 = vec![0xff, 0xfe];
+        utf16.extend("see https://vimeo.com/1\r\n".encode_utf16().flat_map(u16::to_le_bytes));
+        assert_eq!(extract("links.txt", &utf16), vec!["https://vimeo.com/1"]);
+    }
+
+    #[test]
+    fn test_html_and_csv() {
+        let html = r#"<html><body>
+            <a href="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;list=PL1">one</a>
+            <a href="/relative">skipped</a>
+            <p>not linked: https://vimeo.com/2</p>
+        </body></html>"#;
+        assert_eq!(extract("bookmarks.html", html.as_bytes()), vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1"]);
+
+        let csv = "title,link,notes\n\"Talk, part 1\",https://vimeo.com/76979871,\"watch \"\"later\"\"\"\nOther,,https://www.twitch.tv/videos/123456\n";
+        assert_eq!(extract("export.csv", csv.as_bytes()), vec!["https://vimeo.com/76979871", "https://www.twitch.tv/videos/123456"]);
+    }
+
+    #[test]
+    fn test_docx_hyperlinks() {
+        let mut docx = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        docx.start_file("word/document.xml", options).unwrap();
+        docx.write_all(concat!(
+            r#"<w:document><w:body><w:p><w:r><w:t>Typed https://vimeo.com/</w:t></w:r><w:r><w:t xml:space="preserve">76979871 here</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>https://vimeo.com/1</w:t></w:r></w:p><w:p><w:r><w:t>Next paragraph</w:t><w:br/><w:t>https://vimeo.com/2</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>after a break</w:t></w:r></w:p></w:body></w:document>"#,
+        ).as_bytes()).unwrap();
+        docx.start_file("word/_rels/document.xml.rels", options).unwrap();
+        docx.write_all(br#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://www.youtube.com/watch?v=dQw4w9WgXcQ&amp;list=PL1" TargetMode="External"/></Relationships>"#).unwrap();
+        let bytes = docx.finish().unwrap().into_inner();
+
+        let urls = extract("notes.docx", &bytes);
+        assert!(urls.contains(&"https://vimeo.com/76979871".to_string()));
+        assert!(urls.contains(&"https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1".to_string()));
+        // Paragraph ends and line breaks keep URLs apart from the following text
+        assert!(urls.contains(&"https://vimeo.com/1".to_string()));
+        assert!(urls.contains(&"https://vimeo.com/2".to_string()));
+        assert_eq!(urls.len(), 4);
+
+        // A small archive that inflates past the limit is refused
+        let mut bomb = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        bomb.start_file("word/document.xml", options).unwrap();
+        bomb.write_all(&vec![b' '; MAX_DOCX_PART_SIZE as usize + 1]).unwrap();
+        let bytes = bomb.finish().unwrap().into_inner();
+        assert!(docx_links(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_pdf_link_annotations() {
+        use lopdf::{dictionary, Document, Object};
+
+        let mut document = Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let action_id = document.add_object(dictionary! {
+            "S" => "URI",
+            "URI" => Object::string_literal("https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+        });
+        let link = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Rect" => vec![0.into(), 0.into(), 100.into(), 20.into()],
+            "A" => action_id,
+        });
+        let text_note = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Text",
+            "Contents" => Object::string_literal("https://vimeo.com/1"),
+        });
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            "Annots" => vec![link.into(), text_note.into()],
+        });
+        document.objects.insert(pages_id, Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }));
+        let catalog_id = document.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        document.trailer.set("Root", catalog_id);
+
+        let mut bytes = Vec::new();
+        document.save_to(&mut bytes).unwrap();
+
+        assert_eq!(LinkFileKind::detect("scan", &bytes), LinkFileKind::Pdf);
+        assert_eq!(extract("handout.pdf", &bytes), vec!["https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+    }
+}
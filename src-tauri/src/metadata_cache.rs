@@ -0,0 +1,218 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use crate::download_manager::VideoMetadata;
+use crate::playlist_expander::{PlaylistEntry, PlaylistOptions};
+use crate::url_parser::Platform;
+
+const DEFAULT_TTL_HOURS: i64 = 24;
+// Playlists gain entries far more often than a video's metadata changes
+const PREVIEW_TTL_MINUTES: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedEntry<T> {
+    cached_at: DateTime<Utc>,
+    #[serde(alias = "metadata")]
+    value: T,
+}
+
+/// On-disk cache of yt-dlp metadata, one JSON file per platform + video id, and of
+/// expanded playlists, one per URL + options
+pub struct MetadataCache {
+    cache_dir: PathBuf,
+    ttl: Duration,
+    preview_ttl: Duration,
+}
+
+impl MetadataCache {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let cache_dir = app_handle
+            .path()
+            .app_cache_dir()
+            .map_err(|e| anyhow!("Failed to get app cache directory: {}", e))?
+            .join("metadata");
+
+        Self::with_cache_dir(cache_dir, Duration::hours(DEFAULT_TTL_HOURS))
+    }
+
+    pub fn with_cache_dir(cache_dir: PathBuf, ttl: Duration) -> Result<Self> {
+        std::fs::create_dir_all(&cache_dir)?;
+        Ok(Self { cache_dir, ttl, preview_ttl: Duration::minutes(PREVIEW_TTL_MINUTES).min(ttl) })
+    }
+
+    /// Returns the cached metadata if it is younger than the TTL; stale entries are removed
+    pub async fn get(&self, platform: &Platform, video_id: &str) -> Option<VideoMetadata> {
+        read_fresh(&self.entry_path(platform, video_id), self.ttl).await
+    }
+
+    pub async fn insert(&self, platform: &Platform, video_id: &str, metadata: &VideoMetadata) -> Result<()> {
+        self.insert_at(platform, video_id, metadata, Utc::now()).await
+    }
+
+    async fn insert_at(&self, platform: &Platform, video_id: &str, metadata: &VideoMetadata, cached_at: DateTime<Utc>) -> Result<()> {
+        write_entry(&self.entry_path(platform, video_id), metadata, cached_at).await
+    }
+
+    /// Returns the unfiltered entries of the same playlist with the same options, if they are
+    /// younger than the preview TTL. The filter isn't part of the key, so it can be applied afresh
+    pub async fn get_playlist_entries(&self, url: &str, options: &PlaylistOptions) -> Option<Vec<PlaylistEntry>> {
+        read_fresh(&self.preview_path(url, options), self.preview_ttl).await
+    }
+
+    pub async fn insert_playlist_entries(&self, url: &str, options: &PlaylistOptions, entries: &[PlaylistEntry]) -> Result<()> {
+        write_entry(&self.preview_path(url, options), &entries, Utc::now()).await
+    }
+
+    pub async fn remove(&self, platform: &Platform, video_id: &str) {
+        let _ = tokio::fs::remove_file(self.entry_path(platform, video_id)).await;
+    }
+
+    /// Deletes every cached entry, returning how many were removed
+    pub async fn clear(&self) -> Result<usize> {
+        let mut removed = 0;
+        let mut entries = tokio::fs::read_dir(&self.cache_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().extension().and_then(|e| e.to_str()) == Some("json")
+                && tokio::fs::remove_file(entry.path()).await.is_ok()
+            {
+                removed += 1;
+            }
+        }
+        println!("=== METADATA CACHE: Cleared {} entries ===", removed);
+        Ok(removed)
+    }
+
+    fn entry_path(&self, platform: &Platform, video_id: &str) -> PathBuf {
+        // Ids come from URLs and the user's platform file, keep only characters that are safe in a file name
+        let safe = |id: &str| -> String {
+            id.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')).collect()
+        };
+        self.cache_dir.join(format!("{}-{}.json", safe(platform.as_str()), safe(video_id)))
+    }
+
+    fn preview_path(&self, url: &str, options: &PlaylistOptions) -> PathBuf {
+        let unfiltered = PlaylistOptions { filter: None, ..options.clone() };
+        let options = serde_json::to_string(&unfiltered).unwrap_or_default();
+        let key = hex::encode(Sha256::digest(format!("{}\n{}", url, options).as_bytes()));
+        self.cache_dir.join(format!("playlist-{}.json", key))
+    }
+}
+
+// Expired entries, and ones written by a version with a different model, are removed
+async fn read_fresh<T: DeserializeOwned>(path: &Path, ttl: Duration) -> Option<T> {
+    let contents = tokio::fs::read(path).await.ok()?;
+    match serde_json::from_slice::<CachedEntry<T>>(&contents) {
+        Ok(entry) if Utc::now() - entry.cached_at < ttl => Some(entry.value),
+        _ => {
+            let _ = tokio::fs::remove_file(path).await;
+            None
+        }
+    }
+}
+
+async fn write_entry<T: Serialize>(path: &Path, value: &T, cached_at: DateTime<Utc>) -> Result<()> {
+    let entry = CachedEntry { cached_at, value };
+
+    // Write then rename so a concurrent lookup never reads half a file
+    let temp_path = path.with_extension("json.tmp");
+    tokio::fs::write(&temp_path, serde_json::to_vec(&entry)?).await?;
+    tokio::fs::rename(&temp_path, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playlist_filter::PlaylistFilter;
+
+    fn test_cache(name: &str) -> (MetadataCache, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("grabzilla-metadata-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        (MetadataCache::with_cache_dir(dir.clone(), Duration::hours(1)).unwrap(), dir)
+    }
+
+    fn sample() -> VideoMetadata {
+        VideoMetadata {
+            id: Some("dQw4w9WgXcQ".to_string()),
+            title: "Never Gonna Give You Up".to_string(),
+            duration: Some(212.0),
+            upload_date: chrono::NaiveDate::from_ymd_opt(2009, 10, 25),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metadata_cache_roundtrip() {
+        let (cache, dir) = test_cache("roundtrip");
+        assert!(cache.get(&Platform::new("youtube"), "dQw4w9WgXcQ").await.is_none());
+
+        cache.insert(&Platform::new("youtube"), "dQw4w9WgXcQ", &sample()).await.unwrap();
+        let cached = cache.get(&Platform::new("youtube"), "dQw4w9WgXcQ").await.unwrap();
+        assert_eq!(cached.title, "Never Gonna Give You Up");
+        assert_eq!(cached.upload_date, chrono::NaiveDate::from_ymd_opt(2009, 10, 25));
+
+        // Same id on another platform is a different video
+        assert!(cache.get(&Platform::new("vimeo"), "dQw4w9WgXcQ").await.is_none());
+
+        assert_eq!(cache.clear().await.unwrap(), 1);
+        assert!(cache.get(&Platform::new("youtube"), "dQw4w9WgXcQ").await.is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_cache_expiry() {
+        let (cache, dir) = test_cache("expiry");
+        let stale = Utc::now() - Duration::hours(2);
+        cache.insert_at(&Platform::new("youtube"), "abc", &sample(), stale).await.unwrap();
+
+        assert!(cache.get(&Platform::new("youtube"), "abc").await.is_none());
+        assert!(!dir.join("youtube-abc.json").exists());
+
+        // Path separators in an id can't escape the cache directory
+        cache.insert(&Platform::generic(), "../../etc", &sample()).await.unwrap();
+        assert!(dir.join("generic-etc.json").exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_playlist_preview_cache() {
+        let (cache, dir) = test_cache("preview");
+        let url = "https://www.youtube.com/playlist?list=PL123";
+        let options = PlaylistOptions { items: Some("1-10".to_string()), ..Default::default() };
+        let entries: Vec<PlaylistEntry> = (1..=10).map(|index| PlaylistEntry {
+            index,
+            id: None,
+            url: format!("https://www.youtube.com/watch?v=video{}", index),
+            title: None,
+            duration: None,
+            availability: None,
+            live_status: None,
+            upload_date: None,
+            channel: None,
+            thumbnail: None,
+            playlist_title: None,
+        }).collect();
+
+        cache.insert_playlist_entries(url, &options, &entries).await.unwrap();
+        assert_eq!(cache.get_playlist_entries(url, &options).await.unwrap().len(), 10);
+
+        // A different filter reuses the same expansion
+        let filtered = PlaylistOptions {
+            filter: Some(PlaylistFilter::MinDuration { seconds: 60.0 }),
+            ..options.clone()
+        };
+        assert_eq!(cache.get_playlist_entries(url, &filtered).await.unwrap().len(), 10);
+
+        // Other options select other entries
+        let reversed = PlaylistOptions { reverse: true, ..options.clone() };
+        assert!(cache.get_playlist_entries(url, &reversed).await.is_none());
+        assert!(cache.get_playlist_entries("https://www.youtube.com/playlist?list=PL456", &options).await.is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use crate::download_manager::VideoMetadata;
+
+pub const MAX_CONCURRENT_FETCHES: usize = 6;
+// yt-dlp itself sleeps between requests within one run; this spaces out separate runs
+pub const PER_HOST_INTERVAL: Duration = Duration::from_millis(750);
+
+/// Outcome of one URL in a prefetch batch
+#[derive(Debug, Clone, Serialize)]
+pub struct PrefetchResult {
+    /// Position of the URL in the submitted batch
+    pub index: usize,
+    pub url: String,
+    pub metadata: Option<VideoMetadata>,
+    pub error: Option<String>,
+}
+
+// Hands out start times per host so requests to one site are spaced at least `interval` apart
+struct HostRateLimiter {
+    interval: Duration,
+    next_slot: Mutex<HashMap<String, Instant>>,
+}
+
+impl HostRateLimiter {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_slot: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn wait(&self, host: &str) {
+        let start = {
+            let mut next_slot = self.next_slot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let now = Instant::now();
+            let start = next_slot.get(host).copied().filter(|slot| *slot > now).unwrap_or(now);
+            next_slot.insert(host.to_string(), start + self.interval);
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+}
+
+// youtu.be and www.youtube.com are different hosts but the same site
+fn rate_limit_key(url: &str) -> String {
+    let host = url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+        .unwrap_or_default();
+    let host = host.strip_prefix("www.").or_else(|| host.strip_prefix("m.")).unwrap_or(&host);
+    match host {
+        "youtu.be" | "music.youtube.com" => "youtube.com".to_string(),
+        "x.com" => "twitter.com".to_string(),
+        host => host.to_string(),
+    }
+}
+
+/// Fetches metadata for batches of URLs with a shared concurrency limit and per-host spacing.
+/// The limits apply across batches, so several pastes in a row still share them
+pub struct MetadataPrefetcher {
+    semaphore: Arc<Semaphore>,
+    rate_limiter: Arc<HostRateLimiter>,
+}
+
+impl MetadataPrefetcher {
+    pub fn new(max_concurrent: usize, per_host_interval: Duration) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            rate_limiter: Arc::new(HostRateLimiter::new(per_host_interval)),
+        }
+    }
+
+    /// Runs `fetch` for every URL and reports each result through `on_result` as soon as it
+    /// finishes, in completion order. Returns how many fetches succeeded
+    pub async fn prefetch<F, Fut, R>(&self, urls: Vec<(usize, String)>, fetch: F, on_result: R) -> usize
+    where
+        F: Fn(String) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = Result<VideoMetadata>> + Send + 'static,
+        R: Fn(PrefetchResult),
+    {
+        let mut tasks = JoinSet::new();
+        for (index, url) in urls {
+            let fetch = fetch.clone();
+            let semaphore = self.semaphore.clone();
+            let rate_limiter = self.rate_limiter.clone();
+
+            tasks.spawn(async move {
+                // Wait for the host slot before taking a permit, so a slow site doesn't
+                // hold permits other hosts could use
+                rate_limiter.wait(&rate_limit_key(&url)).await;
+                let result = match semaphore.acquire_owned().await {
+                    Ok(_permit) => fetch(url.clone()).await,
+                    Err(e) => Err(e.into()),
+                };
+                match result {
+                    Ok(metadata) => PrefetchResult { index, url, metadata: Some(metadata), error: None },
+                    Err(e) => PrefetchResult { index, url, metadata: None, error: Some(e.to_string()) },
+                }
+            });
+        }
+
+        let mut succeeded = 0;
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(result) => {
+                    if result.metadata.is_some() {
+                        succeeded += 1;
+                    }
+                    on_result(result);
+                }
+                Err(e) => eprintln!("Metadata prefetch task failed: {}", e),
+            }
+        }
+        succeeded
+    }
+}
+
+impl Default for MetadataPrefetcher {
+    fn default() -> Self {
+        Self::new(MAX_CONCURRENT_FETCHES, PER_HOST_INTERVAL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_rate_limit_key() {
+        assert_eq!(rate_limit_key("https://youtu.be/dQw4w9WgXcQ"), "youtube.com");
+        assert_eq!(rate_limit_key("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), "youtube.com");
+        assert_eq!(rate_limit_key("https://m.youtube.com/watch?v=dQw4w9WgXcQ"), "youtube.com");
+        assert_eq!(rate_limit_key("https://vimeo.com/123"), "vimeo.com");
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_limits() {
+        let prefetcher = MetadataPrefetcher::new(2, Duration::from_millis(40));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let urls: Vec<(usize, String)> = (0..6)
+            .map(|i| (i, format!("https://site{}.example/video/{}", i % 3, i)))
+            .chain(std::iter::once((6, "https://site0.example/broken".to_string())))
+            .collect();
+
+        let fetch = {
+            let running = running.clone();
+            let peak = peak.clone();
+            move |url: String| {
+                let running = running.clone();
+                let peak = peak.clone();
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    if url.ends_with("broken") {
+                        return Err(anyhow!("Video unavailable"));
+                    }
+                    Ok(VideoMetadata { title: url, ..Default::default() })
+                }
+            }
+        };
+
+        let started = std::time::Instant::now();
+        let results = Mutex::new(Vec::new());
+        let succeeded = prefetcher.prefetch(urls, fetch, |result| results.lock().unwrap().push(result)).await;
+
+        let results = results.into_inner().unwrap();
+        assert_eq!(succeeded, 6);
+        assert_eq!(results.len(), 7);
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+
+        let failed = results.iter().find(|r| r.index == 6).unwrap();
+        assert_eq!(failed.error.as_deref(), Some("Video unavailable"));
+
+        // site0 has three requests, so the last can't start before two intervals have passed
+        assert!(started.elapsed() >= Duration::from_millis(80));
+    }
+}
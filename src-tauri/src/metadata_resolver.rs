@@ -0,0 +1,472 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use crate::download_manager::VideoMetadata;
+use crate::platform_registry::registry;
+use crate::public_fetch::PublicFetcher;
+use crate::url_parser::Platform;
+
+// Pages (and oEmbed responses) above this size aren't worth parsing for a title
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// What each platform offers without running yt-dlp, from its registry entry. Instagram and
+/// Facebook oEmbed need an app token, so only the page is left for those
+struct PlatformAdapter {
+    platform: Platform,
+    oembed_endpoint: Option<String>,
+    /// Appended to <title> by the site, stripped when that is all we have
+    title_suffixes: Vec<String>,
+}
+
+impl PlatformAdapter {
+    fn for_platform(platform: &Platform) -> Self {
+        let definition = registry().get(platform);
+        PlatformAdapter {
+            platform: platform.clone(),
+            oembed_endpoint: definition.and_then(|d| d.oembed_endpoint.clone()),
+            title_suffixes: definition.map(|d| d.title_suffixes.clone()).unwrap_or_default(),
+        }
+    }
+
+    fn fallback_thumbnail(&self, url: &url::Url) -> Option<String> {
+        registry().fallback_thumbnail(&self.platform, url.as_str())
+    }
+}
+
+/// Fields found by one source; later sources only fill what earlier ones left empty
+#[derive(Debug, Default, Clone, PartialEq)]
+struct PartialMetadata {
+    id: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    uploader: Option<String>,
+    uploader_url: Option<String>,
+    thumbnail: Option<String>,
+    duration: Option<f64>,
+    upload_date: Option<NaiveDate>,
+}
+
+impl PartialMetadata {
+    fn fill_from(&mut self, other: PartialMetadata) {
+        self.id = self.id.take().or(other.id);
+        self.title = self.title.take().or(other.title);
+        self.description = self.description.take().or(other.description);
+        self.uploader = self.uploader.take().or(other.uploader);
+        self.uploader_url = self.uploader_url.take().or(other.uploader_url);
+        self.thumbnail = self.thumbnail.take().or(other.thumbnail);
+        self.duration = self.duration.take().or(other.duration);
+        self.upload_date = self.upload_date.take().or(other.upload_date);
+    }
+
+    // Everything the queue shows; once we have these there's no need to fetch the page
+    fn is_complete(&self) -> bool {
+        self.title.is_some() && self.uploader.is_some() && self.thumbnail.is_some() && self.duration.is_some()
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == PartialMetadata::default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OEmbed {
+    title: Option<String>,
+    author_name: Option<String>,
+    author_url: Option<String>,
+    thumbnail_url: Option<String>,
+    // Vimeo extensions
+    duration: Option<f64>,
+    upload_date: Option<String>,
+    description: Option<String>,
+    video_id: Option<serde_json::Value>,
+}
+
+/// Lightweight metadata from oEmbed, OpenGraph and JSON-LD, for when yt-dlp is blocked or too slow.
+/// Pasted URLs and endpoints discovered in pages get the same public-address checks as any page
+pub struct MetadataResolver {
+    fetcher: PublicFetcher,
+}
+
+impl MetadataResolver {
+    pub fn new() -> Result<Self> {
+        Ok(Self { fetcher: PublicFetcher::new(MAX_PAGE_BYTES)? })
+    }
+
+    pub async fn resolve(&self, url: &str, platform: &Platform) -> Result<VideoMetadata> {
+        let parsed = url::Url::parse(url)?;
+        if parsed.scheme() != "https" && parsed.scheme() != "http" {
+            return Err(anyhow!("Unsupported URL scheme: {}", parsed.scheme()));
+        }
+        let adapter = PlatformAdapter::for_platform(platform);
+        let mut resolved = PartialMetadata::default();
+
+        if let Some(ref endpoint) = adapter.oembed_endpoint {
+            match self.fetch_oembed(endpoint, url).await {
+                Ok(oembed) => resolved.fill_from(oembed),
+                Err(e) => println!("=== METADATA RESOLVER: oEmbed failed for {}: {} ===", url, e),
+            }
+        }
+
+        if !resolved.is_complete() {
+            match self.fetch_text(url).await {
+                Ok(html) => {
+                    let page = parse_page(&html, &parsed, &adapter);
+                    // Sites without a known endpoint may advertise one in the page
+                    if adapter.oembed_endpoint.is_none() {
+                        if let Some(ref discovered) = page.oembed_url {
+                            match self.fetch_text(discovered).await.and_then(|json| parse_oembed(&json)) {
+                                Ok(oembed) => resolved.fill_from(oembed),
+                                Err(e) => println!("=== METADATA RESOLVER: Discovered oEmbed failed: {} ===", e),
+                            }
+                        }
+                    }
+                    resolved.fill_from(page.open_graph);
+                    resolved.fill_from(page.json_ld);
+                    resolved.fill_from(page.fallback);
+                }
+                Err(e) => println!("=== METADATA RESOLVER: Page fetch failed for {}: {} ===", url, e),
+            }
+        }
+
+        if resolved.is_empty() {
+            return Err(anyhow!("No metadata found for {}", url));
+        }
+        Ok(into_video_metadata(resolved, &parsed, &adapter))
+    }
+
+    async fn fetch_oembed(&self, endpoint: &str, url: &str) -> Result<PartialMetadata> {
+        let mut request_url = url::Url::parse(endpoint)?;
+        request_url.query_pairs_mut()
+            .append_pair("url", url)
+            .append_pair("format", "json");
+        parse_oembed(&self.fetch_text(request_url.as_str()).await?)
+    }
+
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        let (_, response) = self.fetcher.get(url).await?;
+        let body = self.fetcher.read_body(response).await?;
+        Ok(String::from_utf8_lossy(&body).to_string())
+    }
+}
+
+fn into_video_metadata(resolved: PartialMetadata, url: &url::Url, adapter: &PlatformAdapter) -> VideoMetadata {
+    let title = resolved.title
+        .or_else(|| resolved.uploader.as_ref().map(|uploader| format!("Video by {}", uploader)))
+        .unwrap_or_else(|| format!("Video from {}", url.host_str().unwrap_or("unknown site")));
+
+    VideoMetadata {
+        id: resolved.id,
+        title,
+        description: resolved.description,
+        uploader: resolved.uploader,
+        channel_url: resolved.uploader_url,
+        thumbnail: resolved.thumbnail.or_else(|| adapter.fallback_thumbnail(url)),
+        duration: resolved.duration,
+        upload_date: resolved.upload_date,
+        webpage_url: Some(url.to_string()),
+        ..Default::default()
+    }
+}
+
+fn parse_oembed(json: &str) -> Result<PartialMetadata> {
+    let oembed: OEmbed = serde_json::from_str(json)?;
+    Ok(PartialMetadata {
+        id: oembed.video_id.map(|id| match id {
+            serde_json::Value::String(id) => id,
+            other => other.to_string(),
+        }),
+        title: non_empty(oembed.title),
+        description: non_empty(oembed.description),
+        uploader: non_empty(oembed.author_name),
+        uploader_url: non_empty(oembed.author_url),
+        thumbnail: non_empty(oembed.thumbnail_url),
+        duration: oembed.duration.filter(|d| *d > 0.0),
+        upload_date: oembed.upload_date.as_deref().and_then(parse_date),
+    })
+}
+
+struct ParsedPage {
+    oembed_url: Option<String>,
+    open_graph: PartialMetadata,
+    json_ld: PartialMetadata,
+    fallback: PartialMetadata,
+}
+
+fn parse_page(html: &str, url: &url::Url, adapter: &PlatformAdapter) -> ParsedPage {
+    let document = Html::parse_document(html);
+
+    let oembed_url = select_attr(&document, r#"link[type="application/json+oembed"]"#, "href")
+        .and_then(|href| url.join(&href).ok())
+        .map(|href| href.to_string());
+
+    let title = select_text(&document, "title").map(|title| {
+        adapter.title_suffixes.iter()
+            .fold(title, |title, suffix| title.strip_suffix(suffix).map(str::to_string).unwrap_or(title))
+    });
+
+    ParsedPage {
+        oembed_url,
+        open_graph: parse_open_graph(&document, url),
+        json_ld: parse_json_ld(&document, url),
+        fallback: PartialMetadata { title, ..Default::default() },
+    }
+}
+
+// OpenGraph, Twitter card and schema.org microdata meta tags
+fn parse_open_graph(document: &Html, url: &url::Url) -> PartialMetadata {
+    let meta = |names: &[&str]| {
+        names.iter().find_map(|name| {
+            select_attr(document, &format!(r#"meta[property="{0}"], meta[name="{0}"], meta[itemprop="{0}"]"#, name), "content")
+        })
+    };
+
+    PartialMetadata {
+        id: None,
+        title: meta(&["og:title", "twitter:title"]),
+        description: meta(&["og:description", "twitter:description", "description"]),
+        uploader: meta(&["author", "article:author"])
+            .or_else(|| select_attr(document, r#"[itemprop="author"] [itemprop="name"]"#, "content")),
+        uploader_url: select_attr(document, r#"[itemprop="author"] [itemprop="url"]"#, "href"),
+        thumbnail: meta(&["og:image", "og:image:url", "twitter:image", "thumbnailUrl"])
+            .and_then(|image| url.join(&image).ok())
+            .map(|image| image.to_string()),
+        duration: meta(&["video:duration", "og:video:duration"])
+            .and_then(|seconds| seconds.parse::<f64>().ok())
+            .or_else(|| meta(&["duration"]).as_deref().and_then(parse_iso8601_duration)),
+        upload_date: meta(&["uploadDate", "video:release_date", "article:published_time"])
+            .as_deref()
+            .and_then(parse_date),
+    }
+}
+
+fn parse_json_ld(document: &Html, url: &url::Url) -> PartialMetadata {
+    let Ok(selector) = Selector::parse(r#"script[type="application/ld+json"]"#) else {
+        return PartialMetadata::default();
+    };
+
+    let mut objects = Vec::new();
+    for script in document.select(&selector) {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&script.text().collect::<String>()) {
+            collect_json_ld_objects(value, &mut objects);
+        }
+    }
+
+    let Some(video) = objects.iter().find(|object| has_type(object, "VideoObject")) else {
+        return PartialMetadata::default();
+    };
+
+    let author = video.get("author").or_else(|| video.get("creator")).map(|author| match author {
+        serde_json::Value::Array(authors) => authors.first().cloned().unwrap_or_default(),
+        author => author.clone(),
+    });
+
+    PartialMetadata {
+        id: None,
+        title: json_string(video.get("name")),
+        description: json_string(video.get("description")),
+        uploader: author.as_ref().and_then(|author| match author {
+            serde_json::Value::String(name) => Some(name.clone()),
+            author => json_string(author.get("name")),
+        }),
+        uploader_url: author.as_ref().and_then(|author| json_string(author.get("url"))),
+        thumbnail: video.get("thumbnailUrl")
+            .or_else(|| video.get("thumbnail"))
+            .and_then(first_url)
+            .and_then(|image| url.join(&image).ok())
+            .map(|image| image.to_string()),
+        duration: json_string(video.get("duration")).as_deref().and_then(parse_iso8601_duration),
+        upload_date: json_string(video.get("uploadDate")).as_deref().and_then(parse_date),
+    }
+}
+
+// JSON-LD can be one object, an array of them, or an @graph
+fn collect_json_ld_objects(value: serde_json::Value, objects: &mut Vec<serde_json::Value>) {
+    match value {
+        serde_json::Value::Array(values) => {
+            for value in values {
+                collect_json_ld_objects(value, objects);
+            }
+        }
+        serde_json::Value::Object(mut object) => {
+            if let Some(graph) = object.remove("@graph") {
+                collect_json_ld_objects(graph, objects);
+            }
+            objects.push(serde_json::Value::Object(object));
+        }
+        _ => {}
+    }
+}
+
+fn has_type(object: &serde_json::Value, expected: &str) -> bool {
+    match object.get("@type") {
+        Some(serde_json::Value::String(kind)) => kind == expected,
+        Some(serde_json::Value::Array(kinds)) => kinds.iter().any(|kind| kind.as_str() == Some(expected)),
+        _ => false,
+    }
+}
+
+// thumbnailUrl may be a string, a list of strings or an ImageObject
+fn first_url(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(url) => Some(url.clone()),
+        serde_json::Value::Array(values) => values.iter().find_map(first_url),
+        serde_json::Value::Object(_) => json_string(value.get("url").or_else(|| value.get("contentUrl"))),
+        _ => None,
+    }
+}
+
+fn json_string(value: Option<&serde_json::Value>) -> Option<String> {
+    non_empty(value?.as_str().map(|s| s.to_string()))
+}
+
+fn select_attr(document: &Html, selector: &str, attr: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    let value = document.select(&selector).find_map(|element| element.value().attr(attr))?;
+    non_empty(Some(value.to_string()))
+}
+
+fn select_text(document: &Html, selector: &str) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    let text: String = document.select(&selector).next()?.text().collect();
+    non_empty(Some(text))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// "2013-03-27 17:59:54", "2024-01-31T10:00:00Z" or "2024-01-31"
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// ISO 8601 durations as used by schema.org, e.g. "PT1H2M3S" or "P0DT0H3M33S"
+fn parse_iso8601_duration(value: &str) -> Option<f64> {
+    let rest = value.trim().strip_prefix('P')?;
+    let (days, time) = match rest.split_once('T') {
+        Some((days, time)) => (days, time),
+        None => (rest, ""),
+    };
+
+    let mut seconds = 0.0;
+    let mut parse_units = |part: &str, units: &[(char, f64)]| -> Option<()> {
+        let mut number = String::new();
+        for c in part.chars() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+            } else {
+                let (_, scale) = units.iter().find(|(unit, _)| *unit == c)?;
+                seconds += number.parse::<f64>().ok()? * scale;
+                number.clear();
+            }
+        }
+        number.is_empty().then_some(())
+    };
+    parse_units(days, &[('D', 86400.0)])?;
+    parse_units(time, &[('H', 3600.0), ('M', 60.0), ('S', 1.0)])?;
+
+    (seconds > 0.0).then_some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestResponse, TestServer};
+
+    fn page(fixture: &str, url: &str, platform: Platform) -> ParsedPage {
+        let url = url::Url::parse(url).unwrap();
+        parse_page(fixture, &url, &PlatformAdapter::for_platform(&platform))
+    }
+
+    #[test]
+    fn test_iso8601_duration() {
+        assert_eq!(parse_iso8601_duration("PT3M33S"), Some(213.0));
+        assert_eq!(parse_iso8601_duration("PT1H2M3S"), Some(3723.0));
+        assert_eq!(parse_iso8601_duration("P0DT0H3M33S"), Some(213.0));
+        assert_eq!(parse_iso8601_duration("PT0S"), None);
+        assert_eq!(parse_iso8601_duration("3:33"), None);
+    }
+
+    #[test]
+    fn test_youtube_fixture() {
+        let url = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+        let parsed = page(include_str!("../tests/fixtures/metadata/youtube_watch.html"), url, Platform::new("youtube"));
+
+        assert_eq!(parsed.open_graph.title.as_deref(), Some("Rick Astley - Never Gonna Give You Up (Official Music Video)"));
+        assert_eq!(parsed.open_graph.uploader.as_deref(), Some("Rick Astley"));
+        assert_eq!(parsed.open_graph.duration, Some(213.0));
+        assert_eq!(parsed.open_graph.upload_date, NaiveDate::from_ymd_opt(2009, 10, 24));
+        assert_eq!(parsed.open_graph.thumbnail.as_deref(), Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg"));
+        // The suffix is stripped from <title>
+        assert_eq!(parsed.fallback.title.as_deref(), Some("Rick Astley - Never Gonna Give You Up (Official Music Video)"));
+
+        let oembed = parse_oembed(r#"{"title": "Rick Astley - Never Gonna Give You Up (Official Music Video)",
+            "author_name": "Rick Astley", "author_url": "https://www.youtube.com/@RickAstleyYT", "type": "video",
+            "thumbnail_url": "https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg", "provider_name": "YouTube"}"#).unwrap();
+        assert_eq!(oembed.duration, None);
+
+        // oEmbed has no duration for YouTube, the page fills it in
+        let mut resolved = oembed;
+        resolved.fill_from(parsed.open_graph);
+        assert_eq!(resolved.thumbnail.as_deref(), Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/hqdefault.jpg"));
+        assert_eq!(resolved.duration, Some(213.0));
+        assert!(resolved.is_complete());
+    }
+
+    #[test]
+    fn test_vimeo_fixture() {
+        let url = "https://vimeo.com/76979871";
+        let parsed = page(include_str!("../tests/fixtures/metadata/vimeo_video.html"), url, Platform::new("vimeo"));
+
+        let json_ld = parsed.json_ld;
+        assert_eq!(json_ld.title.as_deref(), Some("The New Vimeo Player (You Know, For Videos)"));
+        assert_eq!(json_ld.uploader.as_deref(), Some("Vimeo Staff"));
+        assert_eq!(json_ld.uploader_url.as_deref(), Some("https://vimeo.com/staff"));
+        assert_eq!(json_ld.duration, Some(62.0));
+        assert_eq!(json_ld.upload_date, NaiveDate::from_ymd_opt(2013, 10, 15));
+        assert_eq!(json_ld.thumbnail.as_deref(), Some("https://i.vimeocdn.com/video/452001751-640.jpg"));
+
+        let oembed = parse_oembed(r#"{"type": "video", "provider_name": "Vimeo", "title": "The New Vimeo Player (You Know, For Videos)",
+            "author_name": "Vimeo Staff", "duration": 62, "upload_date": "2013-10-15 14:08:29", "video_id": 76979871,
+            "thumbnail_url": "https://i.vimeocdn.com/video/452001751-d_295x166"}"#).unwrap();
+        assert_eq!(oembed.id.as_deref(), Some("76979871"));
+        assert_eq!(oembed.duration, Some(62.0));
+        assert_eq!(oembed.upload_date, NaiveDate::from_ymd_opt(2013, 10, 15));
+    }
+
+    #[test]
+    fn test_tiktok_fixture() {
+        let url = "https://www.tiktok.com/@scout2015/video/6718335390845095173";
+        let parsed = page(include_str!("../tests/fixtures/metadata/tiktok_video.html"), url, Platform::new("tiktok"));
+
+        assert_eq!(parsed.open_graph.title.as_deref(), Some("Scramble up ur name & I'll try to guess it"));
+        assert_eq!(parsed.open_graph.thumbnail.as_deref(), Some("https://p16-sign-va.tiktokcdn.com/obj/cover.jpeg"));
+        assert_eq!(parsed.json_ld.uploader.as_deref(), Some("Scout, Suki & Stella"));
+        assert_eq!(parsed.json_ld.duration, Some(14.0));
+        assert_eq!(parsed.fallback.title.as_deref(), Some("Scramble up ur name & I'll try to guess it"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_generic_with_discovered_oembed() {
+        let server = TestServer::start(vec![
+            ("/talks/keynote", TestResponse::ok("text/html", include_str!("../tests/fixtures/metadata/generic_oembed.html"))),
+            ("/oembed", TestResponse::ok("application/json",
+                r#"{"type": "video", "title": "Conference Keynote", "author_name": "Example Events"}"#)),
+        ]).await;
+
+        // Pages on this machine are refused by default
+        let page_url = server.url("/talks/keynote");
+        assert!(MetadataResolver::new().unwrap().resolve(&page_url, &Platform::generic()).await.is_err());
+
+        let mut resolver = MetadataResolver::new().unwrap();
+        resolver.fetcher.exempt_addresses.push("127.0.0.1".parse().unwrap());
+        let metadata = resolver.resolve(&page_url, &Platform::generic()).await.unwrap();
+
+        // oEmbed wins for the title, the page fills in what oEmbed lacks
+        assert_eq!(metadata.title, "Conference Keynote");
+        assert_eq!(metadata.uploader.as_deref(), Some("Example Events"));
+        assert_eq!(metadata.duration, Some(2700.0));
+        assert_eq!(metadata.thumbnail, Some(server.url("/images/keynote.jpg")));
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::download_manager::VideoMetadata;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SidecarFormat {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "nfo")]
+    Nfo,
+    #[serde(rename = "xmp")]
+    Xmp,
+}
+
+impl SidecarFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SidecarFormat::Json => "info.json",
+            SidecarFormat::Nfo => "nfo",
+            SidecarFormat::Xmp => "xmp",
+        }
+    }
+}
+
+/// Where and when a file was downloaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    pub source_url: String,
+    pub downloaded_at: DateTime<Utc>,
+    pub downloaded_with: String,
+}
+
+impl Provenance {
+    pub fn new(source_url: &str) -> Self {
+        Self::with_timestamp(source_url, Utc::now())
+    }
+
+    pub fn with_timestamp(source_url: &str, downloaded_at: DateTime<Utc>) -> Self {
+        Self {
+            source_url: source_url.to_string(),
+            downloaded_at,
+            downloaded_with: format!("GrabZilla {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+/// Target of a sidecar regeneration: a file on disk and the URL it was downloaded from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SidecarTarget {
+    pub file_path: String,
+    pub url: String,
+}
+
+/// video.mp4 -> video.info.json / video.nfo / video.xmp
+pub fn sidecar_path(media_file: &Path, format: SidecarFormat) -> PathBuf {
+    media_file.with_extension(format.extension())
+}
+
+pub fn write_sidecars(
+    media_file: &Path,
+    metadata: &VideoMetadata,
+    provenance: &Provenance,
+    formats: &[SidecarFormat],
+) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for format in formats {
+        let contents = match format {
+            SidecarFormat::Json => render_json(metadata, provenance)?,
+            SidecarFormat::Nfo => render_nfo(metadata, provenance),
+            SidecarFormat::Xmp => render_xmp(metadata, provenance),
+        };
+        let path = sidecar_path(media_file, *format);
+        std::fs::write(&path, contents)?;
+        println!("=== METADATA SIDECAR: Wrote {:?} ===", path);
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// The complete yt-dlp info dict when we have it, with our provenance block added
+fn render_json(metadata: &VideoMetadata, provenance: &Provenance) -> Result<String> {
+    let mut info = match metadata.info_json.clone() {
+        Some(serde_json::Value::Object(info)) => info,
+        _ => match serde_json::to_value(metadata)? {
+            serde_json::Value::Object(info) => info,
+            _ => serde_json::Map::new(),
+        },
+    };
+    info.insert("provenance".to_string(), serde_json::to_value(provenance)?);
+    Ok(serde_json::to_string_pretty(&info)?)
+}
+
+// Kodi/Jellyfin movie NFO. Both ignore elements they don't know, so the provenance
+// fields without a Kodi equivalent are kept as extra elements
+fn render_nfo(metadata: &VideoMetadata, provenance: &Provenance) -> String {
+    let mut nfo = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<movie>\n");
+    push_element(&mut nfo, "title", Some(&metadata.title));
+    push_element(&mut nfo, "plot", metadata.description.as_deref());
+    push_element(&mut nfo, "studio", metadata.uploader.as_deref());
+    if let Some(date) = metadata.formatted_upload_date() {
+        push_element(&mut nfo, "premiered", Some(&date));
+        push_element(&mut nfo, "year", Some(&date[..4]));
+    }
+    if let Some(duration) = metadata.duration {
+        // Kodi runtime is in minutes
+        push_element(&mut nfo, "runtime", Some(&((duration / 60.0).round() as u64).to_string()));
+    }
+    if let Some(ref id) = metadata.id {
+        nfo.push_str(&format!("  <uniqueid type=\"grabzilla\" default=\"true\">{}</uniqueid>\n", escape_xml(id)));
+    }
+    for tag in &metadata.tags {
+        push_element(&mut nfo, "tag", Some(tag));
+    }
+    push_element(&mut nfo, "dateadded", Some(&provenance.downloaded_at.format("%Y-%m-%d %H:%M:%S").to_string()));
+    push_element(&mut nfo, "source_url", Some(&provenance.source_url));
+    push_element(&mut nfo, "license", metadata.license.as_deref());
+    nfo.push_str("</movie>\n");
+    nfo
+}
+
+fn render_xmp(metadata: &VideoMetadata, provenance: &Provenance) -> String {
+    let mut properties = String::new();
+    push_alt(&mut properties, "dc:title", Some(&metadata.title));
+    push_alt(&mut properties, "dc:description", metadata.description.as_deref());
+    if let Some(ref uploader) = metadata.uploader {
+        properties.push_str(&format!("   <dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n", escape_xml(uploader)));
+    }
+    if !metadata.tags.is_empty() {
+        let tags: String = metadata.tags.iter()
+            .map(|tag| format!("<rdf:li>{}</rdf:li>", escape_xml(tag)))
+            .collect();
+        properties.push_str(&format!("   <dc:subject><rdf:Bag>{}</rdf:Bag></dc:subject>\n", tags));
+    }
+    push_alt(&mut properties, "dc:rights", metadata.license.as_deref());
+    properties.push_str(&format!("   <dc:source>{}</dc:source>\n", escape_xml(&provenance.source_url)));
+    if let Some(date) = metadata.formatted_upload_date() {
+        properties.push_str(&format!("   <xmp:CreateDate>{}</xmp:CreateDate>\n", date));
+    }
+    properties.push_str(&format!(
+        "   <xmp:MetadataDate>{}</xmp:MetadataDate>\n   <xmp:CreatorTool>{}</xmp:CreatorTool>\n",
+        provenance.downloaded_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        escape_xml(&provenance.downloaded_with),
+    ));
+
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n\
+         \x20<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n\
+         \x20 <rdf:Description rdf:about=\"\"\n\
+         \x20   xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n\
+         \x20   xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n\
+         {}\
+         \x20 </rdf:Description>\n\
+         \x20</rdf:RDF>\n\
+         </x:xmpmeta>\n\
+         <?xpacket end=\"w\"?>\n",
+        properties
+    )
+}
+
+fn push_element(xml: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = value.filter(|v| !v.is_empty()) {
+        xml.push_str(&format!("  <{0}>{1}</{0}>\n", name, escape_xml(value)));
+    }
+}
+
+fn push_alt(xml: &mut String, name: &str, value: Option<&str>) {
+    if let Some(value) = value.filter(|v| !v.is_empty()) {
+        xml.push_str(&format!(
+            "   <{0}><rdf:Alt><rdf:li xml:lang=\"x-default\">{1}</rdf:li></rdf:Alt></{0}>\n",
+            name,
+            escape_xml(value)
+        ));
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value.chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .fold(String::with_capacity(value.len()), |mut escaped, c| {
+            match c {
+                '&' => escaped.push_str("&amp;"),
+                '<' => escaped.push_str("&lt;"),
+                '>' => escaped.push_str("&gt;"),
+                '"' => escaped.push_str("&quot;"),
+                '\'' => escaped.push_str("&apos;"),
+                c => escaped.push(c),
+            }
+            escaped
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample() -> (VideoMetadata, Provenance) {
+        let metadata = VideoMetadata {
+            id: Some("dQw4w9WgXcQ".to_string()),
+            title: "Tom & Jerry <Live>".to_string(),
+            uploader: Some("Studio".to_string()),
+            description: Some("Line one\nLine two".to_string()),
+            duration: Some(1830.0),
+            upload_date: chrono::NaiveDate::from_ymd_opt(2024, 1, 31),
+            license: Some("Creative Commons Attribution license (reuse allowed)".to_string()),
+            tags: vec!["cartoon".to_string()],
+            info_json: Some(serde_json::json!({"id": "dQw4w9WgXcQ", "formats": []})),
+            ..Default::default()
+        };
+        let provenance = Provenance::with_timestamp(
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            Utc.with_ymd_and_hms(2024, 2, 1, 12, 30, 0).unwrap(),
+        );
+        (metadata, provenance)
+    }
+
+    #[test]
+    fn test_sidecar_rendering() {
+        let (metadata, provenance) = sample();
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&metadata, &provenance).unwrap()).unwrap();
+        assert_eq!(json["id"], "dQw4w9WgXcQ");
+        assert!(json["formats"].is_array());
+        assert_eq!(json["provenance"]["source_url"], "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(json["provenance"]["downloaded_at"], "2024-02-01T12:30:00Z");
+
+        let nfo = render_nfo(&metadata, &provenance);
+        assert!(nfo.contains("<title>Tom &amp; Jerry &lt;Live&gt;</title>"));
+        assert!(nfo.contains("<premiered>2024-01-31</premiered>"));
+        assert!(nfo.contains("<year>2024</year>"));
+        assert!(nfo.contains("<runtime>31</runtime>"));
+        assert!(nfo.contains("<dateadded>2024-02-01 12:30:00</dateadded>"));
+
+        let xmp = render_xmp(&metadata, &provenance);
+        assert!(xmp.contains("<rdf:li xml:lang=\"x-default\">Tom &amp; Jerry &lt;Live&gt;</rdf:li>"));
+        assert!(xmp.contains("<dc:source>https://www.youtube.com/watch?v=dQw4w9WgXcQ</dc:source>"));
+        assert!(xmp.contains("<xmp:MetadataDate>2024-02-01T12:30:00Z</xmp:MetadataDate>"));
+        assert!(xmp.trim_end().ends_with("<?xpacket end=\"w\"?>"));
+    }
+
+    #[test]
+    fn test_sidecar_paths() {
+        let media = Path::new("/videos/talk.mp4");
+        assert_eq!(sidecar_path(media, SidecarFormat::Json), PathBuf::from("/videos/talk.info.json"));
+        assert_eq!(sidecar_path(media, SidecarFormat::Nfo), PathBuf::from("/videos/talk.nfo"));
+        assert_eq!(sidecar_path(media, SidecarFormat::Xmp), PathBuf::from("/videos/talk.xmp"));
+    }
+}
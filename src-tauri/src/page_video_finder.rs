@@ -0,0 +1,193 @@
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use scraper::{Html, Selector};
+use std::sync::OnceLock;
+use url::Url;
+use crate::public_fetch::PublicFetcher;
+use crate::url_parser::{URLExtractionResult, URLExtractor};
+
+// Article pages, not media; anything bigger isn't parsed
+const MAX_PAGE_BYTES: usize = 5 * 1024 * 1024;
+
+static MANIFEST_URL: OnceLock<Regex> = OnceLock::new();
+static PLAYER_PATH: OnceLock<Regex> = OnceLock::new();
+
+// HLS and DASH manifests anywhere in the page source, including inline player configs
+fn manifest_url_regex() -> &'static Regex {
+    MANIFEST_URL.get_or_init(|| {
+        Regex::new(r#"https?://[^\s"'<>\\]+?\.(?:m3u8|mpd)(?:\?[^\s"'<>\\]*)?"#).unwrap()
+    })
+}
+
+// Embeds from sites without a platform pattern still count when they look like a player
+fn player_path_regex() -> &'static Regex {
+    PLAYER_PATH.get_or_init(|| Regex::new(r"(?i)/(?:embed|player|video)s?(?:/|\.|$)").unwrap())
+}
+
+/// Finds the videos embedded in an article or other web page: player iframes, `<video>`
+/// sources, OpenGraph video tags and HLS/DASH manifest links
+pub struct PageVideoFinder {
+    fetcher: PublicFetcher,
+}
+
+impl PageVideoFinder {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            fetcher: PublicFetcher::new(MAX_PAGE_BYTES)?,
+        })
+    }
+
+    pub async fn find_videos(&self, page_url: &str, extractor: &URLExtractor) -> Result<URLExtractionResult> {
+        // Already a video link, nothing to look for
+        if extractor.video_id(page_url).is_some() {
+            return extractor.extract_urls(page_url);
+        }
+
+        let (final_url, html) = self.fetch_page(page_url).await?;
+        let videos = discover_videos(&html, &final_url, extractor);
+        println!("=== PAGE VIDEO FINDER: {} video(s) on {} ===", videos.len(), final_url);
+        extractor.extract_titled_urls(&videos)
+    }
+
+    async fn fetch_page(&self, page_url: &str) -> Result<(Url, String)> {
+        let (url, response) = self.fetcher.get(page_url).await?;
+        let content_type = PublicFetcher::content_type(&response);
+        if !content_type.is_empty() && !content_type.contains("html") {
+            return Err(anyhow!("{} is not a web page ({})", url, content_type));
+        }
+        let body = self.fetcher.read_body(response).await?;
+        Ok((url, String::from_utf8_lossy(&body).to_string()))
+    }
+}
+
+/// Video URLs found in a page, with the page title for its OpenGraph video
+pub fn discover_videos(html: &str, page_url: &Url, extractor: &URLExtractor) -> Vec<(String, Option<String>)> {
+    let document = Html::parse_document(html);
+    let mut videos: Vec<(String, Option<String>)> = Vec::new();
+    let mut add = |url: Option<Url>, title: Option<String>| {
+        let Some(url) = url.filter(|url| url.scheme() == "https" || url.scheme() == "http") else { return };
+        let url = url.to_string();
+        if !videos.iter().any(|(existing, _)| *existing == url) {
+            videos.push((url, title));
+        }
+    };
+    let resolve = |value: &str| page_url.join(value.trim()).ok();
+    let select = |selector: &str| Selector::parse(selector).unwrap();
+
+    let page_title = document.select(&select(r#"meta[property="og:title"]"#))
+        .find_map(|meta| meta.value().attr("content"))
+        .map(str::to_string)
+        .or_else(|| document.select(&select("title")).next().map(|title| title.text().collect::<String>()))
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty());
+
+    let video_meta = select(concat!(
+        r#"meta[property="og:video"], meta[property="og:video:url"], meta[property="og:video:secure_url"], "#,
+        r#"meta[name="twitter:player"], meta[name="twitter:player:stream"]"#,
+    ));
+    for meta in document.select(&video_meta) {
+        add(meta.value().attr("content").and_then(resolve), page_title.clone());
+    }
+
+    // Lazy-loading scripts keep the real address in data-src until the player scrolls into view
+    for frame in document.select(&select("iframe, embed")) {
+        let source = frame.value().attr("data-src").or_else(|| frame.value().attr("src"));
+        let Some(url) = source.and_then(resolve) else { continue };
+        // Skips like buttons, ads and comment widgets
+        if !extractor.detect_platform(url.as_str()).is_generic() || player_path_regex().is_match(url.path()) {
+            add(Some(url), None);
+        }
+    }
+
+    for video in document.select(&select("video[src], video[data-src], video source[src]")) {
+        let source = video.value().attr("data-src").or_else(|| video.value().attr("src"));
+        add(source.and_then(resolve), None);
+    }
+
+    for link in document.select(&select("a[href]")) {
+        let Some(url) = link.value().attr("href").and_then(resolve) else { continue };
+        let path = url.path().to_lowercase();
+        if path.ends_with(".m3u8") || path.ends_with(".mpd") {
+            add(Some(url), None);
+        }
+    }
+
+    // Player configs in scripts are JSON, with escaped slashes
+    let source = html.replace("\\/", "/").replace("&amp;", "&");
+    for manifest in manifest_url_regex().find_iter(&source) {
+        add(Url::parse(manifest.as_str()).ok(), None);
+    }
+
+    videos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestResponse, TestServer};
+    use crate::url_parser::Platform;
+
+    #[test]
+    fn test_discover_videos() {
+        let extractor = URLExtractor::new().unwrap();
+        let page_url = Url::parse("https://news.example.com/2024/05/launch").unwrap();
+        let videos = discover_videos(include_str!("../tests/fixtures/pages/article.html"), &page_url, &extractor);
+
+        let urls: Vec<&str> = videos.iter().map(|(url, _)| url.as_str()).collect();
+        assert_eq!(urls, vec![
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/embed/jNQXAC9IVRw?rel=0",
+            "https://player.vimeo.com/video/76979871?h=a1b2c3&badge=0",
+            "https://media.example.com/players/embed/4521",
+            "https://cdn.example.com/clips/launch-teaser.mp4",
+            "https://news.example.com/streams/launch/manifest.mpd",
+            "https://cdn.example.com/hls/launch/master.m3u8?token=abc&exp=1700000000",
+        ]);
+        assert_eq!(videos[0].1.as_deref(), Some("Launch day recap"));
+
+        let result = extractor.extract_titled_urls(&videos).unwrap();
+        let platforms: Vec<Platform> = result.urls.iter().map(|url| url.platform.clone()).collect();
+        assert_eq!(platforms, vec![
+            Platform::new("youtube"),
+            Platform::new("youtube"),
+            Platform::new("vimeo"),
+            Platform::generic(),
+            Platform::generic(),
+            Platform::generic(),
+            Platform::generic(),
+        ]);
+        // The unlisted Vimeo hash survives cleaning, player options don't
+        assert_eq!(result.urls[2].url, "https://player.vimeo.com/video/76979871?h=a1b2c3");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_is_gated() {
+        let server = TestServer::start(vec![
+            ("/article", TestResponse::ok("text/html; charset=utf-8",
+                r#"<html><head><title>Local</title></head><body><iframe src="https://www.youtube.com/embed/dQw4w9WgXcQ"></iframe></body></html>"#)),
+            ("/moved", TestResponse::redirect("301 Moved Permanently", "/article")),
+            ("/metadata", TestResponse::redirect("302 Found", "http://169.254.169.254/latest/meta-data/")),
+            ("/video.mp4", TestResponse::ok("video/mp4", "")),
+        ]).await;
+        let extractor = URLExtractor::new().unwrap();
+
+        // Pages on this machine are refused by default
+        let finder = PageVideoFinder::new().unwrap();
+        let error = finder.find_videos(&server.url("/article"), &extractor).await.unwrap_err();
+        assert!(error.to_string().contains("not allowed"));
+
+        let mut finder = PageVideoFinder::new().unwrap();
+        finder.fetcher.exempt_addresses.push("127.0.0.1".parse().unwrap());
+        let result = finder.find_videos(&server.url("/moved"), &extractor).await.unwrap();
+        assert_eq!(result.urls.len(), 1);
+        assert_eq!(result.urls[0].platform, Platform::new("youtube"));
+
+        // Redirects are checked like the first request, whatever the finder allows
+        assert!(finder.find_videos(&server.url("/metadata"), &extractor).await.unwrap_err().to_string().contains("not allowed"));
+        assert!(finder.find_videos(&server.url("/video.mp4"), &extractor).await.unwrap_err().to_string().contains("not a web page"));
+
+        // A video link is returned without fetching anything
+        let result = finder.find_videos("https://youtu.be/dQw4w9WgXcQ", &extractor).await.unwrap();
+        assert_eq!(result.urls[0].url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+    }
+}
@@ -0,0 +1,447 @@
+use anyhow::{Result, anyhow};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use url::Url;
+use crate::url_parser::{match_host_patterns, Platform, UrlKind};
+
+const BUILTIN_PLATFORMS: &str = include_str!("platforms.json");
+
+static REGISTRY: OnceLock<PlatformRegistry> = OnceLock::new();
+
+/// The registry in effect: the built-in platforms, plus the user's override file if `init`
+/// ran before anything looked a platform up
+pub fn registry() -> &'static PlatformRegistry {
+    REGISTRY.get_or_init(|| PlatformRegistry::builtin().expect("Built-in platform registry is invalid"))
+}
+
+/// Loads the registry with the overrides at `path` applied. A broken override file is
+/// reported and ignored, so a typo can't take URL detection down with it
+pub fn init(path: &Path) {
+    let loaded = match PlatformRegistry::with_overrides(path) {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("Ignoring platform overrides in {}: {}", path.display(), e);
+            return;
+        }
+    };
+    if REGISTRY.set(loaded).is_err() {
+        eprintln!("Platform registry was used before overrides were loaded");
+    }
+}
+
+/// One platform as written in platforms.json. Patterns are matched against the whole URL and
+/// capture the video id in group 1
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlatformDefinition {
+    pub id: String,
+    pub name: String,
+    /// Domains the platform is served from; subdomains are included
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub video_patterns: Vec<String>,
+    #[serde(default)]
+    pub playlist_patterns: Vec<String>,
+    #[serde(default)]
+    pub channel_patterns: Vec<String>,
+    /// Patterns for the other kinds of page, such as "short", "live" or "channel_tab"
+    #[serde(default)]
+    pub kinds: HashMap<UrlKind, Vec<String>>,
+    /// First path segments that are the site's own pages (twitch.tv/directory), not channel
+    /// names. Channel, channel tab and live patterns don't apply under them
+    #[serde(default)]
+    pub reserved_paths: Vec<String>,
+    /// Matched against host (without "www.") and path; the id is put into `canonical_url`
+    #[serde(default)]
+    pub short_forms: Vec<String>,
+    /// Watch URL with an `{id}` placeholder
+    #[serde(default)]
+    pub canonical_url: Option<String>,
+    /// Alternative domains and the host they are rewritten to
+    #[serde(default)]
+    pub host_aliases: HashMap<String, String>,
+    /// Redirecting hosts that need an HTTP request to expand
+    #[serde(default)]
+    pub short_hosts: Vec<String>,
+    /// Query parameters worth keeping. Without a list only tracking parameters are removed
+    #[serde(default)]
+    pub query_params: Option<Vec<String>>,
+    /// Parameters holding a start time, moved out of the URL
+    #[serde(default)]
+    pub start_time_params: Vec<String>,
+    #[serde(default)]
+    pub network_whitelist: Vec<String>,
+    #[serde(default)]
+    pub oembed_endpoint: Option<String>,
+    /// Appended to <title> by the site
+    #[serde(default)]
+    pub title_suffixes: Vec<String>,
+    /// Thumbnail URL with an `{id}` placeholder, for when nothing else provides one
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,
+}
+
+/// What the frontend is told about a supported platform
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PlatformDescriptor {
+    pub id: Platform,
+    pub name: String,
+    pub hosts: Vec<String>,
+    pub short_hosts: Vec<String>,
+    pub canonical_url: Option<String>,
+    pub supports_playlists: bool,
+    pub supports_channels: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    /// General-purpose link shorteners, not tied to any platform
+    #[serde(default)]
+    shorteners: Vec<String>,
+    #[serde(default)]
+    network_whitelist: Vec<String>,
+    #[serde(default)]
+    platforms: Vec<PlatformDefinition>,
+}
+
+struct CompiledPlatform {
+    platform: Platform,
+    definition: PlatformDefinition,
+    video_patterns: Vec<Regex>,
+    /// Playlist and channel patterns included
+    kind_patterns: Vec<(UrlKind, Regex)>,
+    short_forms: Vec<Regex>,
+}
+
+impl CompiledPlatform {
+    fn compile(definition: PlatformDefinition) -> Result<Self> {
+        let compile_all = |patterns: &[String]| -> Result<Vec<Regex>> {
+            patterns.iter()
+                .map(|pattern| Regex::new(pattern).map_err(|e| anyhow!("Invalid pattern for {}: {}", definition.id, e)))
+                .collect()
+        };
+
+        let kinds = definition.kinds.iter()
+            .chain([(&UrlKind::Playlist, &definition.playlist_patterns), (&UrlKind::Channel, &definition.channel_patterns)]);
+        let mut kind_patterns = Vec::new();
+        for (kind, patterns) in kinds {
+            kind_patterns.extend(compile_all(patterns)?.into_iter().map(|pattern| (*kind, pattern)));
+        }
+
+        Ok(Self {
+            platform: Platform::new(&definition.id),
+            video_patterns: compile_all(&definition.video_patterns)?,
+            kind_patterns,
+            short_forms: compile_all(&definition.short_forms)?,
+            definition,
+        })
+    }
+
+    fn all_patterns(&self) -> impl Iterator<Item = &Regex> {
+        self.video_patterns.iter().chain(self.kind_patterns.iter().map(|(_, pattern)| pattern))
+    }
+
+    fn classify(&self, url: &str) -> Option<UrlKind> {
+        let video = self.video_patterns.iter().any(|pattern| pattern.is_match(url)).then_some(UrlKind::Video);
+        let reserved = self.is_reserved_path(url);
+        self.kind_patterns.iter()
+            .filter(|(kind, _)| !(reserved && matches!(kind, UrlKind::Channel | UrlKind::ChannelTab | UrlKind::Live)))
+            .filter(|(_, pattern)| pattern.is_match(url))
+            .map(|(kind, _)| *kind)
+            .chain(video)
+            .min_by_key(|kind| kind.precedence())
+    }
+
+    // The regex crate has no lookahead, so reserved names are checked here instead of in the patterns
+    fn is_reserved_path(&self, url: &str) -> bool {
+        if self.definition.reserved_paths.is_empty() {
+            return false;
+        }
+        let first_segment = Url::parse(url).ok()
+            .and_then(|url| url.path_segments()?.next().map(str::to_lowercase));
+        first_segment.is_some_and(|segment| self.definition.reserved_paths.contains(&segment))
+    }
+
+    fn has_kind(&self, wanted: impl Fn(UrlKind) -> bool) -> bool {
+        self.kind_patterns.iter().any(|(kind, _)| wanted(*kind))
+    }
+
+    fn serves(&self, host: &str) -> bool {
+        match_host_patterns(&self.definition.hosts, host).is_some()
+    }
+}
+
+/// Every platform-specific rule: detection patterns, URL cleanup, short links and the hosts
+/// the app may contact. Earlier entries win when several match
+pub struct PlatformRegistry {
+    platforms: Vec<CompiledPlatform>,
+    shorteners: Vec<String>,
+    network_whitelist: Vec<String>,
+}
+
+impl PlatformRegistry {
+    pub fn builtin() -> Result<Self> {
+        Self::from_value(serde_json::from_str(BUILTIN_PLATFORMS)?)
+    }
+
+    /// The built-in registry with a user file merged in. Platforms are matched by id and
+    /// only the fields the file sets are replaced; unknown ids are added after the built-ins
+    pub fn with_overrides(path: &Path) -> Result<Self> {
+        let mut merged: Value = serde_json::from_str(BUILTIN_PLATFORMS)?;
+        match std::fs::read_to_string(path) {
+            Ok(contents) => merge_overrides(&mut merged, serde_json::from_str(&contents)?)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Self::from_value(merged)
+    }
+
+    fn from_value(value: Value) -> Result<Self> {
+        let file: RegistryFile = serde_json::from_value(value)?;
+        let platforms = file.platforms.into_iter()
+            .map(CompiledPlatform::compile)
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(generic) = platforms.iter().find(|p| p.platform.is_generic()) {
+            return Err(anyhow!("'{}' is reserved for unrecognised sites", generic.definition.id));
+        }
+
+        Ok(Self {
+            platforms,
+            shorteners: file.shorteners,
+            network_whitelist: file.network_whitelist,
+        })
+    }
+
+    pub fn get(&self, platform: &Platform) -> Option<&PlatformDefinition> {
+        self.compiled(platform).map(|p| &p.definition)
+    }
+
+    fn compiled(&self, platform: &Platform) -> Option<&CompiledPlatform> {
+        self.platforms.iter().find(|p| p.platform == *platform)
+    }
+
+    /// The platform serving a host, e.g. "m.youtube.com"
+    pub fn for_host(&self, host: &str) -> Option<&PlatformDefinition> {
+        let host = host.strip_prefix("www.").unwrap_or(host);
+        self.platforms.iter().find(|p| p.serves(host)).map(|p| &p.definition)
+    }
+
+    pub fn detect(&self, url: &str) -> Platform {
+        self.platforms.iter()
+            .find(|p| p.all_patterns().any(|pattern| pattern.is_match(url)))
+            .map(|p| p.platform.clone())
+            .unwrap_or_else(Platform::generic)
+    }
+
+    /// Platform and video id, for URLs matching a video pattern
+    pub fn video_id(&self, url: &str) -> Option<(Platform, String)> {
+        self.platforms.iter().find_map(|p| {
+            p.video_patterns.iter().find_map(|pattern| {
+                let id = pattern.captures(url)?.get(1)?;
+                Some((p.platform.clone(), id.as_str().to_string()))
+            })
+        })
+    }
+
+    /// Kind of page the URL points at, by the first platform recognising it. Anything else is
+    /// taken to be a single video
+    pub fn classify(&self, url: &str) -> UrlKind {
+        self.platforms.iter()
+            .find_map(|p| p.classify(url))
+            .unwrap_or(UrlKind::Video)
+    }
+
+    /// Rewrites short forms (youtu.be/ID, /shorts/ID) to the platform's canonical URL, keeping
+    /// the query, and alias domains to their main host
+    pub fn expand_short_form(&self, url: &mut Url) {
+        let host = url.host_str().unwrap_or("").to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+        let location = format!("{}{}", host, url.path());
+
+        for platform in &self.platforms {
+            let Some(ref template) = platform.definition.canonical_url else { continue };
+            let Some(id) = platform.short_forms.iter().find_map(|pattern| pattern.captures(&location)?.get(1)) else { continue };
+            let Ok(mut expanded) = Url::parse(&template.replace("{id}", id.as_str())) else { continue };
+
+            let taken: Vec<String> = expanded.query_pairs().map(|(name, _)| name.into_owned()).collect();
+            let query: Vec<(String, String)> = url.query_pairs()
+                .filter(|(name, _)| !taken.iter().any(|taken| taken == name))
+                .map(|(name, value)| (name.into_owned(), value.into_owned()))
+                .collect();
+            if !query.is_empty() {
+                expanded.query_pairs_mut().extend_pairs(query);
+            }
+            expanded.set_fragment(url.fragment());
+            *url = expanded;
+            return;
+        }
+
+        if let Some(alias) = self.platforms.iter().find_map(|p| p.definition.host_aliases.get(&host)) {
+            let _ = url.set_host(Some(alias));
+        }
+    }
+
+    /// Hosts whose links are redirects to be followed before extraction
+    pub fn short_hosts(&self) -> Vec<String> {
+        self.shorteners.iter()
+            .chain(self.platforms.iter().flat_map(|p| &p.definition.short_hosts))
+            .cloned()
+            .collect()
+    }
+
+    /// Hosts the app may contact by default; short hosts are included so they can be resolved
+    pub fn network_whitelist(&self) -> Vec<String> {
+        let mut hosts: Vec<String> = self.network_whitelist.iter()
+            .chain(self.platforms.iter().flat_map(|p| &p.definition.network_whitelist))
+            .cloned()
+            .collect();
+        hosts.extend(self.short_hosts());
+        hosts
+    }
+
+    /// Thumbnail from the platform's template, when the URL carries a video id
+    pub fn fallback_thumbnail(&self, platform: &Platform, url: &str) -> Option<String> {
+        let template = self.get(platform)?.thumbnail_url.as_ref()?;
+        let (found, id) = self.video_id(url)?;
+        (found == *platform).then(|| template.replace("{id}", &id))
+    }
+
+    pub fn descriptors(&self) -> Vec<PlatformDescriptor> {
+        let mut descriptors: Vec<PlatformDescriptor> = self.platforms.iter()
+            .map(|p| PlatformDescriptor {
+                id: p.platform.clone(),
+                name: p.definition.name.clone(),
+                hosts: p.definition.hosts.clone(),
+                short_hosts: p.definition.short_hosts.clone(),
+                canonical_url: p.definition.canonical_url.clone(),
+                supports_playlists: p.has_kind(|kind| matches!(kind, UrlKind::Playlist | UrlKind::Album | UrlKind::Collection)),
+                supports_channels: p.has_kind(|kind| matches!(kind, UrlKind::Channel | UrlKind::ChannelTab)),
+            })
+            .collect();
+        descriptors.push(PlatformDescriptor {
+            id: Platform::generic(),
+            name: "Other sites".to_string(),
+            hosts: Vec::new(),
+            short_hosts: Vec::new(),
+            canonical_url: None,
+            supports_playlists: false,
+            supports_channels: false,
+        });
+        descriptors
+    }
+}
+
+// Lists at the top level are extended; platforms are merged field by field on their id
+fn merge_overrides(merged: &mut Value, overrides: Value) -> Result<()> {
+    let Value::Object(overrides) = overrides else {
+        return Err(anyhow!("Platform overrides must be a JSON object"));
+    };
+
+    for (key, value) in overrides {
+        match (key.as_str(), value) {
+            ("shorteners" | "network_whitelist", Value::Array(hosts)) => {
+                if let Some(Value::Array(existing)) = merged.get_mut(&key) {
+                    existing.extend(hosts);
+                }
+            }
+            ("platforms", Value::Array(platforms)) => {
+                let Some(Value::Array(existing)) = merged.get_mut("platforms") else { continue };
+                for platform in platforms {
+                    let Value::Object(fields) = platform else {
+                        return Err(anyhow!("Platform entries must be JSON objects"));
+                    };
+                    let id = fields.get("id").cloned();
+                    match existing.iter_mut().find(|entry| id.is_some() && entry.get("id") == id.as_ref()) {
+                        Some(Value::Object(entry)) => entry.extend(fields),
+                        _ => existing.push(Value::Object(fields)),
+                    }
+                }
+            }
+            (key, _) => return Err(anyhow!("Unexpected '{}' in platform overrides", key)),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_registry() {
+        let registry = PlatformRegistry::builtin().unwrap();
+
+        assert_eq!(registry.detect("https://www.youtube.com/@channel"), Platform::new("youtube"));
+        assert_eq!(registry.detect("https://example.com/watch?v=dQw4w9WgXcQ"), Platform::generic());
+        assert_eq!(registry.video_id("https://x.com/user/status/123"), Some((Platform::new("twitter"), "123".to_string())));
+        assert_eq!(registry.classify("https://vimeo.com/showcase/123"), UrlKind::Playlist);
+        assert_eq!(registry.classify("https://www.tiktok.com/@user"), UrlKind::Channel);
+        assert_eq!(registry.classify("https://www.tiktok.com/@user/video/123"), UrlKind::Video);
+
+        let mut url = Url::parse("https://youtu.be/dQw4w9WgXcQ?t=42").unwrap();
+        registry.expand_short_form(&mut url);
+        assert_eq!(url.as_str(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42");
+        let mut url = Url::parse("https://instagr.am/p/abc/").unwrap();
+        registry.expand_short_form(&mut url);
+        assert_eq!(url.as_str(), "https://www.instagram.com/p/abc/");
+
+        assert!(registry.short_hosts().contains(&"fb.watch".to_string()));
+        let whitelist = registry.network_whitelist();
+        assert!(whitelist.contains(&"youtube.com".to_string()) && whitelist.contains(&"bit.ly".to_string()));
+        assert_eq!(
+            registry.fallback_thumbnail(&Platform::new("youtube"), "https://www.youtube.com/watch?v=dQw4w9WgXcQ"),
+            Some("https://img.youtube.com/vi/dQw4w9WgXcQ/hqdefault.jpg".to_string())
+        );
+
+        let descriptors = registry.descriptors();
+        assert_eq!(descriptors.last().unwrap().id, Platform::generic());
+        assert!(descriptors.iter().any(|d| d.id == Platform::new("youtube") && d.supports_playlists && d.supports_channels));
+    }
+
+    #[test]
+    fn test_user_overrides() {
+        let dir = std::env::temp_dir().join(format!("grabzilla-platforms-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("platforms.json");
+
+        // No file, no changes
+        assert_eq!(PlatformRegistry::with_overrides(&path).unwrap().platforms.len(), PlatformRegistry::builtin().unwrap().platforms.len());
+
+        std::fs::write(&path, r#"{
+            "shorteners": ["lnk.example"],
+            "platforms": [
+                { "id": "vimeo", "query_params": ["h", "share"] },
+                {
+                    "id": "peertube",
+                    "name": "PeerTube",
+                    "hosts": ["video.example.org"],
+                    "video_patterns": ["video\\.example\\.org/w/([a-zA-Z0-9]+)"],
+                    "network_whitelist": ["video.example.org"]
+                }
+            ]
+        }"#).unwrap();
+        let registry = PlatformRegistry::with_overrides(&path).unwrap();
+
+        // Overridden fields change, the rest of the entry stays
+        let vimeo = registry.get(&Platform::new("vimeo")).unwrap();
+        assert_eq!(vimeo.query_params, Some(vec!["h".to_string(), "share".to_string()]));
+        assert_eq!(vimeo.name, "Vimeo");
+        assert_eq!(registry.video_id("https://vimeo.com/76979871"), Some((Platform::new("vimeo"), "76979871".to_string())));
+
+        assert_eq!(registry.video_id("https://video.example.org/w/abc123"), Some((Platform::new("peertube"), "abc123".to_string())));
+        assert_eq!(registry.for_host("video.example.org").unwrap().id, "peertube");
+        assert!(registry.network_whitelist().contains(&"video.example.org".to_string()));
+        assert!(registry.short_hosts().contains(&"lnk.example".to_string()));
+
+        std::fs::write(&path, r#"{ "platforms": [{ "id": "broken", "name": "Broken", "video_patterns": ["("] }] }"#).unwrap();
+        assert!(PlatformRegistry::with_overrides(&path).is_err());
+        std::fs::write(&path, r#"{ "platforms": [{ "id": "generic", "name": "Mine" }] }"#).unwrap();
+        assert!(PlatformRegistry::with_overrides(&path).is_err());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,342 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command as AsyncCommand;
+use crate::credential_manager::ResolvedCredentials;
+use crate::download_manager::{
+    deserialize_ytdlp_date, null_as_default, Availability, LiveStatus,
+};
+use crate::playlist_filter::PlaylistFilter;
+use crate::ytdlp_invocation::YtDlpInvocation;
+
+// Entries are handed out in batches so a large channel doesn't flood the frontend with events
+const BATCH_SIZE: usize = 25;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistOptions {
+    /// yt-dlp --playlist-items spec, e.g. "1-25,30" or "-10:"
+    #[serde(default)]
+    pub items: Option<String>,
+    #[serde(default)]
+    pub reverse: bool,
+    #[serde(default)]
+    pub date_after: Option<NaiveDate>,
+    #[serde(default)]
+    pub date_before: Option<NaiveDate>,
+    /// Applied to each entry as it streams in
+    #[serde(default)]
+    pub filter: Option<PlaylistFilter>,
+}
+
+impl PlaylistOptions {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref items) = self.items {
+            let valid = !items.trim().is_empty()
+                && items.chars().all(|c| c.is_ascii_digit() || matches!(c, ':' | ',' | '-' | ' '));
+            if !valid {
+                return Err(anyhow!("Invalid playlist item range: {}", items));
+            }
+        }
+        if let (Some(after), Some(before)) = (self.date_after, self.date_before) {
+            if after > before {
+                return Err(anyhow!("Date range is empty: {} is after {}", after, before));
+            }
+        }
+        if let Some(ref filter) = self.filter {
+            filter.validate()?;
+        }
+        Ok(())
+    }
+
+    fn add_args(&self, cmd: &mut AsyncCommand) {
+        if let Some(ref items) = self.items {
+            cmd.arg("--playlist-items").arg(items.replace(' ', ""));
+        }
+        if self.reverse {
+            cmd.arg("--playlist-reverse");
+        }
+        if let Some(date) = self.date_after {
+            cmd.arg("--dateafter").arg(date.format("%Y%m%d").to_string());
+        }
+        if let Some(date) = self.date_before {
+            cmd.arg("--datebefore").arg(date.format("%Y%m%d").to_string());
+        }
+    }
+
+    // Flat entries often have no date, so yt-dlp lets them through; filter the ones that do
+    fn matches_date(&self, date: Option<NaiveDate>) -> bool {
+        match date {
+            Some(date) => self.date_after.map_or(true, |after| date >= after)
+                && self.date_before.map_or(true, |before| date <= before),
+            None => true,
+        }
+    }
+
+    fn matches(&self, entry: &PlaylistEntry) -> bool {
+        self.matches_date(entry.upload_date)
+            && self.filter.as_ref().map_or(true, |filter| filter.matches(entry))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub index: u64,
+    pub id: Option<String>,
+    pub url: String,
+    pub title: Option<String>,
+    /// Length in seconds
+    pub duration: Option<f64>,
+    pub availability: Option<Availability>,
+    pub live_status: Option<LiveStatus>,
+    pub upload_date: Option<NaiveDate>,
+    pub channel: Option<String>,
+    pub thumbnail: Option<String>,
+    pub playlist_title: Option<String>,
+}
+
+// One line of `yt-dlp --flat-playlist -j`
+#[derive(Debug, Deserialize)]
+struct FlatEntry {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    webpage_url: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    availability: Option<Availability>,
+    #[serde(default)]
+    live_status: Option<LiveStatus>,
+    #[serde(default, deserialize_with = "deserialize_ytdlp_date")]
+    upload_date: Option<NaiveDate>,
+    #[serde(default)]
+    channel: Option<String>,
+    #[serde(default)]
+    uploader: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    thumbnails: Vec<FlatThumbnail>,
+    #[serde(default)]
+    playlist_index: Option<u64>,
+    #[serde(default)]
+    playlist_title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FlatThumbnail {
+    url: String,
+}
+
+/// Parses one JSON line into an entry; lines without a usable URL are skipped
+fn parse_entry(line: &str, position: u64) -> Option<PlaylistEntry> {
+    let entry: FlatEntry = serde_json::from_str(line).ok()?;
+    let url = entry.webpage_url
+        .or(entry.url)
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))?;
+
+    Some(PlaylistEntry {
+        index: entry.playlist_index.unwrap_or(position),
+        id: entry.id,
+        url,
+        title: entry.title,
+        duration: entry.duration,
+        availability: entry.availability,
+        live_status: entry.live_status,
+        upload_date: entry.upload_date,
+        channel: entry.channel.or(entry.uploader),
+        // yt-dlp orders thumbnails from worst to best
+        thumbnail: entry.thumbnails.into_iter().last().map(|t| t.url),
+        playlist_title: entry.playlist_title,
+    })
+}
+
+/// Streams the entries of a playlist or channel, calling `on_batch` as they arrive.
+/// Returns the number of entries found
+pub async fn expand_playlist<F>(
+    ytdlp_path: &Path,
+    url: &str,
+    options: &PlaylistOptions,
+    credentials: Option<&ResolvedCredentials>,
+    mut on_batch: F,
+) -> Result<usize>
+where
+    F: FnMut(Vec<PlaylistEntry>),
+{
+    options.validate()?;
+
+    let mut invocation = YtDlpInvocation::new(ytdlp_path);
+    invocation.arg("--flat-playlist")
+       .arg("--dump-json");
+    options.add_args(invocation.command());
+    // Private and members-only lists need the same login as their videos
+    invocation.credentials(credentials)?.url(url)?;
+    invocation.command()
+       .stdout(Stdio::piped())
+       .stderr(Stdio::piped());
+
+    println!("=== PLAYLIST EXPANDER: Executing command: {:?} ===", invocation);
+    let mut child = invocation.spawn()?;
+
+    // Drain stderr alongside stdout so a chatty extractor can't block on a full pipe
+    let stderr_task = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut errors = String::new();
+            let _ = stderr.read_to_string(&mut errors).await;
+            errors
+        })
+    });
+
+    let mut count = 0usize;
+    // Position in yt-dlp's output, for entries without a playlist_index. Filtered out
+    // entries still take up their place
+    let mut position = 0u64;
+    let mut batch = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Some(entry) = parse_entry(&line, position + 1) else {
+                continue;
+            };
+            position += 1;
+            if !options.matches(&entry) {
+                continue;
+            }
+
+            count += 1;
+            batch.push(entry);
+            if batch.len() >= BATCH_SIZE {
+                on_batch(std::mem::take(&mut batch));
+            }
+        }
+    }
+    if !batch.is_empty() {
+        on_batch(batch);
+    }
+
+    let status = child.wait().await?;
+    let errors = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => String::new(),
+    };
+
+    // Unavailable entries make yt-dlp exit non-zero even though the rest of the list came through
+    if !status.success() && count == 0 {
+        return Err(anyhow!("Failed to expand playlist: {}", errors.trim()));
+    }
+
+    println!("=== PLAYLIST EXPANDER: Found {} entries ===", count);
+    Ok(count)
+}
+
+/// Expands the playlist without queueing anything or applying the filter, so a preview can
+/// say how much the filter excludes (see `PlaylistPreview::new`)
+pub async fn preview_entries(
+    ytdlp_path: &Path,
+    url: &str,
+    options: &PlaylistOptions,
+    credentials: Option<&ResolvedCredentials>,
+) -> Result<Vec<PlaylistEntry>> {
+    options.validate()?;
+
+    let unfiltered = PlaylistOptions { filter: None, ..options.clone() };
+    let mut entries = Vec::new();
+    expand_playlist(ytdlp_path, url, &unfiltered, credentials, |batch| entries.extend(batch)).await?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flat_entry() {
+        let line = r#"{"_type": "url", "ie_key": "Youtube", "id": "dQw4w9WgXcQ",
+            "url": "https://www.youtube.com/watch?v=dQw4w9WgXcQ", "title": "Never Gonna Give You Up",
+            "duration": 212.0, "channel": "Rick Astley", "live_status": null, "availability": "public",
+            "thumbnails": [{"url": "https://i.ytimg.com/small.jpg"}, {"url": "https://i.ytimg.com/large.jpg"}],
+            "playlist_index": 7, "playlist_title": "Favourites"}"#;
+
+        let entry = parse_entry(line, 1).unwrap();
+        assert_eq!(entry.index, 7);
+        assert_eq!(entry.id.as_deref(), Some("dQw4w9WgXcQ"));
+        assert_eq!(entry.duration, Some(212.0));
+        assert_eq!(entry.availability, Some(Availability::Public));
+        assert_eq!(entry.thumbnail.as_deref(), Some("https://i.ytimg.com/large.jpg"));
+        assert_eq!(entry.playlist_title.as_deref(), Some("Favourites"));
+
+        // Bare ids without a URL can't be queued
+        assert!(parse_entry(r#"{"id": "abc", "url": "abc"}"#, 1).is_none());
+        assert!(parse_entry("WARNING: something", 1).is_none());
+    }
+
+    #[test]
+    fn test_playlist_options() {
+        let options = PlaylistOptions {
+            items: Some("1-25, 30".to_string()),
+            date_after: NaiveDate::from_ymd_opt(2024, 1, 1),
+            ..Default::default()
+        };
+        assert!(options.validate().is_ok());
+        assert!(options.matches_date(NaiveDate::from_ymd_opt(2024, 6, 1)));
+        assert!(!options.matches_date(NaiveDate::from_ymd_opt(2023, 12, 31)));
+        assert!(options.matches_date(None));
+
+        let invalid = PlaylistOptions { items: Some("1; rm -rf /".to_string()), ..Default::default() };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_expand_playlist_streams_batches() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("grabzilla-playlist-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("yt-dlp");
+        std::fs::write(&script, r#"#!/bin/sh
+echo "$*" > "$(dirname "$0")/last_run"
+i=1
+while [ $i -le 30 ]; do
+  echo "{\"id\": \"v$i\", \"url\": \"https://example.com/watch/$i\", \"title\": \"Video $i\"}"
+  i=$((i + 1))
+done
+echo "ERROR: one video is private" >&2
+exit 1
+"#).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let options = PlaylistOptions { reverse: true, items: Some("1-30".to_string()), ..Default::default() };
+        let mut batches = Vec::new();
+        let count = expand_playlist(&script, "https://example.com/playlist", &options, None, |batch| batches.push(batch))
+            .await
+            .unwrap();
+
+        assert_eq!(count, 30);
+        assert_eq!(batches.iter().map(|b| b.len()).collect::<Vec<_>>(), vec![25, 5]);
+        assert_eq!(batches[1][4].title.as_deref(), Some("Video 30"));
+        assert_eq!(batches[1][4].index, 30);
+
+        let args = std::fs::read_to_string(dir.join("last_run")).unwrap();
+        assert!(args.contains("--flat-playlist --dump-json"));
+        assert!(args.contains("--playlist-items 1-30 --playlist-reverse"));
+
+        // Filtered out entries don't shift the position of the ones after them
+        let options = PlaylistOptions {
+            filter: Some(PlaylistFilter::Keyword { keyword: "Video 3".to_string() }),
+            ..Default::default()
+        };
+        let mut entries = Vec::new();
+        expand_playlist(&script, "https://example.com/playlist", &options, None, |batch| entries.extend(batch))
+            .await
+            .unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.index).collect::<Vec<_>>(), vec![3, 30]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
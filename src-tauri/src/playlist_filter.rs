@@ -0,0 +1,218 @@
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use crate::download_manager::LiveStatus;
+use crate::playlist_expander::PlaylistEntry;
+
+// YouTube allows Shorts up to three minutes, anything longer is a regular video
+const MAX_SHORT_SECONDS: f64 = 180.0;
+
+/// Filter expression applied to playlist entries before they are queued.
+/// Serializable so the same expression can be stored with a subscription
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum PlaylistFilter {
+    #[serde(rename = "and")]
+    And { filters: Vec<PlaylistFilter> },
+    #[serde(rename = "or")]
+    Or { filters: Vec<PlaylistFilter> },
+    #[serde(rename = "not")]
+    Not { filter: Box<PlaylistFilter> },
+    #[serde(rename = "uploaded_after")]
+    UploadedAfter { date: NaiveDate },
+    #[serde(rename = "uploaded_before")]
+    UploadedBefore { date: NaiveDate },
+    #[serde(rename = "min_duration")]
+    MinDuration { seconds: f64 },
+    #[serde(rename = "max_duration")]
+    MaxDuration { seconds: f64 },
+    /// Case-insensitive match against the title
+    #[serde(rename = "keyword")]
+    Keyword { keyword: String },
+    #[serde(rename = "shorts")]
+    Shorts,
+    /// Live, upcoming and past live streams
+    #[serde(rename = "live")]
+    Live,
+}
+
+impl PlaylistFilter {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            PlaylistFilter::And { filters } | PlaylistFilter::Or { filters } => {
+                filters.iter().try_for_each(|filter| filter.validate())
+            }
+            PlaylistFilter::Not { filter } => filter.validate(),
+            PlaylistFilter::MinDuration { seconds } | PlaylistFilter::MaxDuration { seconds } => {
+                if seconds.is_finite() && *seconds >= 0.0 {
+                    Ok(())
+                } else {
+                    Err(anyhow!("Invalid duration filter: {}", seconds))
+                }
+            }
+            PlaylistFilter::Keyword { keyword } if keyword.trim().is_empty() => {
+                Err(anyhow!("Keyword filter is empty"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Some(true)/Some(false) when the entry's metadata decides the filter, None when
+    /// the flat playlist data doesn't include the field it needs
+    pub fn evaluate(&self, entry: &PlaylistEntry) -> Option<bool> {
+        match self {
+            // Three-valued logic: a definite answer wins over an unknown one
+            PlaylistFilter::And { filters } => {
+                let mut result = Some(true);
+                for filter in filters {
+                    match filter.evaluate(entry) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                result
+            }
+            PlaylistFilter::Or { filters } => {
+                let mut result = Some(false);
+                for filter in filters {
+                    match filter.evaluate(entry) {
+                        Some(true) => return Some(true),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                result
+            }
+            PlaylistFilter::Not { filter } => filter.evaluate(entry).map(|matched| !matched),
+            PlaylistFilter::UploadedAfter { date } => entry.upload_date.map(|uploaded| uploaded >= *date),
+            PlaylistFilter::UploadedBefore { date } => entry.upload_date.map(|uploaded| uploaded <= *date),
+            PlaylistFilter::MinDuration { seconds } => entry.duration.map(|duration| duration >= *seconds),
+            PlaylistFilter::MaxDuration { seconds } => entry.duration.map(|duration| duration <= *seconds),
+            PlaylistFilter::Keyword { keyword } => entry.title.as_ref()
+                .map(|title| title.to_lowercase().contains(&keyword.trim().to_lowercase())),
+            PlaylistFilter::Shorts => is_short(entry),
+            PlaylistFilter::Live => match entry.live_status {
+                Some(LiveStatus::NotLive) => Some(false),
+                Some(LiveStatus::Unknown) | None => None,
+                Some(_) => Some(true),
+            },
+        }
+    }
+
+    /// Entries are kept unless the filter definitely rejects them, matching how
+    /// yt-dlp treats missing fields in its own date filters
+    pub fn matches(&self, entry: &PlaylistEntry) -> bool {
+        self.evaluate(entry) != Some(false)
+    }
+}
+
+fn is_short(entry: &PlaylistEntry) -> Option<bool> {
+    if entry.url.contains("/shorts/") {
+        return Some(true);
+    }
+    match entry.duration {
+        Some(duration) if duration > MAX_SHORT_SECONDS => Some(false),
+        _ => None,
+    }
+}
+
+/// What a filter would keep from a playlist, shown before anything is queued
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistPreview {
+    pub entries: Vec<PlaylistEntry>,
+    /// Kept entries whose metadata wasn't enough to decide the filter
+    pub uncertain: usize,
+    pub excluded: usize,
+    pub total: usize,
+}
+
+impl PlaylistPreview {
+    pub fn new(entries: Vec<PlaylistEntry>, filter: Option<&PlaylistFilter>) -> Self {
+        let mut preview = PlaylistPreview {
+            total: entries.len(),
+            ..Default::default()
+        };
+        for entry in entries {
+            match filter.map_or(Some(true), |filter| filter.evaluate(&entry)) {
+                Some(false) => preview.excluded += 1,
+                Some(true) => preview.entries.push(entry),
+                None => {
+                    preview.uncertain += 1;
+                    preview.entries.push(entry);
+                }
+            }
+        }
+        preview
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str, title: &str, duration: Option<f64>, upload_date: Option<NaiveDate>) -> PlaylistEntry {
+        PlaylistEntry {
+            index: 1,
+            id: None,
+            url: url.to_string(),
+            title: Some(title.to_string()),
+            duration,
+            availability: None,
+            live_status: Some(LiveStatus::NotLive),
+            upload_date,
+            channel: None,
+            thumbnail: None,
+            playlist_title: None,
+        }
+    }
+
+    #[test]
+    fn test_filter_evaluation() {
+        let filter: PlaylistFilter = serde_json::from_str(r#"{"type": "and", "filters": [
+            {"type": "uploaded_after", "date": "2024-01-01"},
+            {"type": "min_duration", "seconds": 600},
+            {"type": "keyword", "keyword": "Rust"},
+            {"type": "not", "filter": {"type": "or", "filters": [{"type": "shorts"}, {"type": "live"}]}}
+        ]}"#).unwrap();
+        assert!(filter.validate().is_ok());
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1);
+        let talk = entry("https://www.youtube.com/watch?v=a", "Learning rust in 2024", Some(1800.0), date);
+        assert_eq!(filter.evaluate(&talk), Some(true));
+
+        let old = entry("https://www.youtube.com/watch?v=b", "Rust talk", Some(1800.0), NaiveDate::from_ymd_opt(2023, 1, 1));
+        assert_eq!(filter.evaluate(&old), Some(false));
+
+        let short = entry("https://www.youtube.com/shorts/c", "Rust tip", Some(1800.0), date);
+        assert_eq!(filter.evaluate(&short), Some(false));
+
+        let mut stream = talk.clone();
+        stream.live_status = Some(LiveStatus::WasLive);
+        assert_eq!(filter.evaluate(&stream), Some(false));
+
+        // Flat entries without a date can't be ruled out
+        let undated = entry("https://www.youtube.com/watch?v=d", "Rust", Some(1800.0), None);
+        assert_eq!(filter.evaluate(&undated), None);
+        assert!(filter.matches(&undated));
+    }
+
+    #[test]
+    fn test_playlist_preview() {
+        let filter = PlaylistFilter::Not { filter: Box::new(PlaylistFilter::Shorts) };
+        let entries = vec![
+            entry("https://www.youtube.com/shorts/a", "Short", Some(30.0), None),
+            entry("https://www.youtube.com/watch?v=b", "Long", Some(900.0), None),
+            entry("https://www.youtube.com/watch?v=c", "Unknown", Some(45.0), None),
+        ];
+
+        let preview = PlaylistPreview::new(entries, Some(&filter));
+        assert_eq!(preview.total, 3);
+        assert_eq!(preview.excluded, 1);
+        assert_eq!(preview.uncertain, 1);
+        assert_eq!(preview.entries.len(), 2);
+
+        assert!(PlaylistFilter::Keyword { keyword: "  ".to_string() }.validate().is_err());
+        assert!(PlaylistFilter::MinDuration { seconds: -1.0 }.validate().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{Result, anyhow};
+use reqwest::redirect;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+use crate::proxy_manager::with_proxy_routing;
+use crate::security_manager::SecurityManager;
+
+const MAX_REDIRECTS: usize = 5;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// HTTP GETs for URLs that come from pages, feeds and other sites, which must not reach
+/// the user's own machine or network
+pub struct PublicFetcher {
+    security_manager: Arc<SecurityManager>,
+    max_bytes: usize,
+    // Only set by tests, which serve pages from 127.0.0.1
+    pub(crate) exempt_addresses: Vec<IpAddr>,
+}
+
+impl PublicFetcher {
+    pub fn new(max_bytes: usize) -> Result<Self> {
+        Ok(Self {
+            security_manager: Arc::new(SecurityManager::new()?),
+            max_bytes,
+            exempt_addresses: Vec::new(),
+        })
+    }
+
+    /// Sends the request and follows redirects, returning the final URL and its successful response.
+    /// Redirects are followed by hand so every hop is checked, and each request is pinned to
+    /// the address that was checked, so a second DNS answer can't point it somewhere else
+    pub async fn get(&self, url: &str) -> Result<(Url, reqwest::Response)> {
+        let mut current = url.to_string();
+        for _ in 0..=MAX_REDIRECTS {
+            let url = Url::parse(&current)?;
+            let exempt = url.host_str()
+                .and_then(|host| host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>().ok())
+                .is_some_and(|ip| self.exempt_addresses.contains(&ip));
+            if !exempt {
+                self.security_manager.validate_public_url(&current)?;
+            }
+            let address = self.resolve_address(&url).await?;
+
+            let mut builder = with_proxy_routing(reqwest::Client::builder())
+                .redirect(redirect::Policy::none())
+                .timeout(FETCH_TIMEOUT)
+                .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36");
+            if let Some(domain) = url.domain() {
+                builder = builder.resolve(domain, address);
+            }
+            let response = builder.build()?.get(url.clone()).send().await
+                .map_err(|e| anyhow!("Failed to fetch {}: {}", url, e))?;
+
+            if response.status().is_redirection() {
+                let location = response.headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or_else(|| anyhow!("Redirect from {} has no location", url))?;
+                current = url.join(location)?.to_string();
+                continue;
+            }
+            if !response.status().is_success() {
+                return Err(anyhow!("{} returned HTTP {}", url, response.status()));
+            }
+            return Ok((url, response));
+        }
+        Err(anyhow!("Too many redirects fetching {}", url))
+    }
+
+    /// Reads the body as it arrives, giving up as soon as it goes over the size limit
+    pub async fn read_body(&self, mut response: reqwest::Response) -> Result<Vec<u8>> {
+        if response.content_length().is_some_and(|length| length as usize > self.max_bytes) {
+            return Err(anyhow!("Response from {} is too large", response.url()));
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > self.max_bytes {
+                return Err(anyhow!("Response from {} is too large", response.url()));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// Content type of a response, lowercased, empty when the server didn't send one
+    pub fn content_type(response: &reqwest::Response) -> String {
+        response.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .to_lowercase()
+    }
+
+    async fn resolve_address(&self, url: &Url) -> Result<SocketAddr> {
+        let host = url.host_str().ok_or_else(|| anyhow!("URL has no host"))?;
+        let port = url.port_or_known_default().unwrap_or(443);
+        // Brackets come off IPv6 literals for the lookup
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await
+            .map_err(|e| anyhow!("Cannot resolve {}: {}", host, e))?
+            .collect();
+        // Every answer has to be public, otherwise the pick could be the private one
+        let blocked = addresses.iter().find(|address| {
+            !self.security_manager.is_public_address(address.ip()) && !self.exempt_addresses.contains(&address.ip())
+        });
+        if let Some(blocked) = blocked {
+            return Err(anyhow!("Access to {} ({}) is not allowed", host, blocked.ip()));
+        }
+        addresses.into_iter().next().ok_or_else(|| anyhow!("Cannot resolve {}", host))
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Result, anyhow};
+use reqwest::redirect;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use url::Url;
+use crate::platform_registry::registry;
+use crate::proxy_manager::with_proxy_routing;
+use crate::security_manager::SecurityManager;
+use crate::url_parser::match_host_patterns;
+
+pub const MAX_HOPS: usize = 5;
+pub const HOP_TIMEOUT: Duration = Duration::from_secs(5);
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+const MAX_CACHE_ENTRIES: usize = 1000;
+
+/// Follows link-shortener redirects to the URL they point at. Only shortener hosts are ever
+/// requested: resolution stops at the first hop on any other host, so the destination site
+/// itself isn't contacted
+#[derive(Clone)]
+pub struct ShortUrlResolver {
+    client: reqwest::Client,
+    security_manager: Arc<SecurityManager>,
+    shortener_hosts: Arc<Vec<String>>,
+    max_hops: usize,
+    cache: Arc<Mutex<HashMap<String, (Instant, String)>>>,
+}
+
+impl ShortUrlResolver {
+    pub fn new() -> Result<Self> {
+        Self::with_hosts(
+            SecurityManager::new()?,
+            registry().short_hosts(),
+            MAX_HOPS,
+            HOP_TIMEOUT,
+        )
+    }
+
+    pub fn with_hosts(security_manager: SecurityManager, shortener_hosts: Vec<String>, max_hops: usize, timeout: Duration) -> Result<Self> {
+        // Redirects are followed by hand so every hop can be checked
+        let client = with_proxy_routing(reqwest::Client::builder())
+            .redirect(redirect::Policy::none())
+            .timeout(timeout)
+            .build()?;
+
+        Ok(Self {
+            client,
+            security_manager: Arc::new(security_manager),
+            shortener_hosts: Arc::new(shortener_hosts),
+            max_hops,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn is_short_url(&self, url: &str) -> bool {
+        Url::parse(url).ok()
+            .and_then(|url| url.host_str().map(|host| match_host_patterns(&self.shortener_hosts, host).is_some()))
+            .unwrap_or(false)
+    }
+
+    /// The URL a short link redirects to. Anything that isn't a short link comes back as is
+    pub async fn resolve(&self, url: &str) -> Result<String> {
+        if !self.is_short_url(url) {
+            return Ok(url.to_string());
+        }
+        if let Some(resolved) = self.cached(url) {
+            return Ok(resolved);
+        }
+
+        let mut current = Url::parse(url)?;
+        let mut hops = 0;
+        while self.is_short_url(current.as_str()) {
+            if hops == self.max_hops {
+                return Err(anyhow!("Too many redirects resolving {}", url));
+            }
+            if !self.security_manager.validate_network_access(current.as_str()) {
+                return Err(anyhow!("Network access to '{}' is not allowed", current));
+            }
+
+            match self.next_hop(&current).await? {
+                Some(next) => current = next,
+                // Not a redirect, so the short link was the final URL after all
+                None => break,
+            }
+            hops += 1;
+        }
+        // The destination is what gets extracted and downloaded, so it has to pass too
+        if !self.security_manager.validate_network_access(current.as_str()) {
+            return Err(anyhow!("Network access to '{}' is not allowed", current));
+        }
+
+        let resolved = current.to_string();
+        println!("=== SHORT URL RESOLVER: {} -> {} ({} hops) ===", url, resolved, hops);
+        self.store(url, &resolved);
+        Ok(resolved)
+    }
+
+    /// Resolves many URLs concurrently, in input order. Links that fail to resolve are kept
+    /// unchanged, since yt-dlp can still follow some of them itself
+    pub async fn resolve_all(&self, urls: Vec<String>) -> Vec<String> {
+        let mut tasks = JoinSet::new();
+        for (index, url) in urls.iter().enumerate().filter(|(_, url)| self.is_short_url(url)) {
+            let resolver = self.clone();
+            let url = url.clone();
+            tasks.spawn(async move { (index, resolver.resolve(&url).await) });
+        }
+
+        let mut resolved = urls;
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, Ok(url))) => resolved[index] = url,
+                Ok((index, Err(e))) => eprintln!("Failed to resolve {}: {}", resolved[index], e),
+                Err(e) => eprintln!("Short URL task failed: {}", e),
+            }
+        }
+        resolved
+    }
+
+    // HEAD first; some shorteners only redirect GET requests
+    async fn next_hop(&self, url: &Url) -> Result<Option<Url>> {
+        if let Ok(response) = self.client.head(url.clone()).send().await {
+            if response.status().is_redirection() {
+                return redirect_target(url, &response);
+            }
+            if response.status().is_success() {
+                return Ok(None);
+            }
+        }
+
+        let response = self.client.get(url.clone()).send().await
+            .map_err(|e| anyhow!("Failed to resolve {}: {}", url, e))?;
+        if response.status().is_redirection() {
+            return redirect_target(url, &response);
+        }
+        if !response.status().is_success() {
+            return Err(anyhow!("{} returned HTTP {}", url, response.status()));
+        }
+        Ok(None)
+    }
+
+    fn cached(&self, url: &str) -> Option<String> {
+        let cache = self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.get(url)
+            .filter(|(resolved_at, _)| resolved_at.elapsed() < CACHE_TTL)
+            .map(|(_, resolved)| resolved.clone())
+    }
+
+    fn store(&self, url: &str, resolved: &str) {
+        let mut cache = self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.retain(|_, (resolved_at, _)| resolved_at.elapsed() < CACHE_TTL);
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+        }
+        cache.insert(url.to_string(), (Instant::now(), resolved.to_string()));
+    }
+}
+
+fn redirect_target(from: &Url, response: &reqwest::Response) -> Result<Option<Url>> {
+    let location = response.headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|location| location.to_str().ok())
+        .ok_or_else(|| anyhow!("Redirect from {} has no location", from))?;
+    // Locations may be relative
+    let next = from.join(location)?;
+    if next.scheme() != "https" && next.scheme() != "http" {
+        return Err(anyhow!("Redirect to unsupported URL: {}", next));
+    }
+    Ok(Some(next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{TestResponse, TestServer};
+
+    // Stand-in shortener
+    async fn start_server() -> TestServer {
+        TestServer::start(vec![
+            ("/a", TestResponse::redirect("301 Moved Permanently", "/b")),
+            ("/b", TestResponse::redirect("302 Found", "https://www.youtube.com/watch?v=dQw4w9WgXcQ")),
+            ("HEAD /get-only", TestResponse::status("405 Method Not Allowed")),
+            ("GET /get-only", TestResponse::redirect("302 Found", "https://vimeo.com/76979871")),
+            ("/loop", TestResponse::redirect("302 Found", "{BASE}/loop")),
+            ("/escape", TestResponse::redirect("302 Found", "http://localhost:1/internal")),
+            ("/offsite", TestResponse::redirect("302 Found", "https://example.org/video")),
+            ("/plain", TestResponse::status("200 OK")),
+        ]).await
+    }
+
+    fn resolver() -> ShortUrlResolver {
+        let security_manager = SecurityManager::new().unwrap()
+            .with_network_whitelist(vec!["127.0.0.1".to_string()]);
+        ShortUrlResolver::with_hosts(
+            security_manager,
+            vec!["127.0.0.1".to_string(), "localhost".to_string()],
+            3,
+            Duration::from_secs(2),
+        ).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_resolves_redirect_chain() {
+        let server = start_server().await;
+        let resolver = resolver();
+
+        let url = server.url("/a");
+        assert_eq!(resolver.resolve(&url).await.unwrap(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        // Stops at youtube.com without requesting it
+        let seen = server.requests().len();
+        assert_eq!(seen, 2);
+
+        // Second lookup comes from the cache
+        assert_eq!(resolver.resolve(&url).await.unwrap(), "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(server.requests().len(), seen);
+
+        // Falls back to GET when HEAD isn't allowed
+        assert_eq!(resolver.resolve(&server.url("/get-only")).await.unwrap(), "https://vimeo.com/76979871");
+
+        // A shortener that doesn't redirect is its own final URL
+        let plain = server.url("/plain");
+        assert_eq!(resolver.resolve(&plain).await.unwrap(), plain);
+
+        // Not a shortener, nothing to do
+        assert_eq!(resolver.resolve("https://vimeo.com/1").await.unwrap(), "https://vimeo.com/1");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_loops_and_disallowed_hops() {
+        let server = start_server().await;
+        let resolver = resolver();
+
+        assert!(resolver.resolve(&server.url("/loop")).await.is_err());
+        // localhost is treated as a shortener here but isn't whitelisted
+        let error = resolver.resolve(&server.url("/escape")).await.unwrap_err();
+        assert!(error.to_string().contains("not allowed"));
+        // Neither a shortener nor whitelisted, so it can't be the destination either
+        let error = resolver.resolve(&server.url("/offsite")).await.unwrap_err();
+        assert!(error.to_string().contains("not allowed"));
+        assert!(resolver.resolve(&server.url("/missing")).await.is_err());
+
+        // Failures leave the URL as it was
+        let urls = vec![
+            server.url("/a"),
+            server.url("/escape"),
+            server.url("/offsite"),
+            "https://vimeo.com/1".to_string(),
+        ];
+        let resolved = resolver.resolve_all(urls.clone()).await;
+        assert_eq!(resolved, vec![
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string(),
+            urls[1].clone(),
+            urls[2].clone(),
+            urls[3].clone(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_extraction_uses_resolved_urls() {
+        let server = start_server().await;
+        let extractor = crate::url_parser::URLExtractor::new().unwrap();
+        let text = format!("short: {} and long: https://www.youtube.com/watch?v=dQw4w9WgXcQ", server.url("/a"));
+
+        let result = extractor.extract_urls_resolved(&text, &resolver()).await.unwrap();
+        // Both point at the same video
+        assert_eq!(result.urls.len(), 1);
+        assert_eq!(result.duplicates_removed, 1);
+        assert_eq!(result.urls[0].platform, crate::url_parser::Platform::new("youtube"));
+        assert_eq!(result.urls[0].original_text, server.url("/a"));
+    }
+}
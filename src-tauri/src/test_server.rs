@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Canned answer for one route of a `TestServer`
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    status: &'static str,
+    content_type: Option<&'static str>,
+    location: Option<String>,
+    body: String,
+}
+
+impl TestResponse {
+    pub fn ok(content_type: &'static str, body: impl Into<String>) -> Self {
+        Self { status: "200 OK", content_type: Some(content_type), location: None, body: body.into() }
+    }
+
+    pub fn redirect(status: &'static str, location: impl Into<String>) -> Self {
+        Self { status, content_type: None, location: Some(location.into()), body: String::new() }
+    }
+
+    pub fn status(status: &'static str) -> Self {
+        Self { status, content_type: None, location: None, body: String::new() }
+    }
+}
+
+/// Stand-in HTTP server on 127.0.0.1 for tests. Routes are keyed by path, without the query,
+/// or by "METHOD path" when a method needs its own answer; anything else gets a 404.
+/// "{BASE}" in a body or location is replaced with the server's own base URL
+pub struct TestServer {
+    pub base: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl TestServer {
+    pub async fn start(routes: Vec<(&str, TestResponse)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let routes: Arc<HashMap<String, TestResponse>> = Arc::new(routes.into_iter()
+            .map(|(route, response)| (route.to_string(), response))
+            .collect());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let server_base = base.clone();
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let (base, routes, seen) = (server_base.clone(), routes.clone(), seen.clone());
+                tokio::spawn(async move {
+                    let mut buffer = vec![0u8; 4096];
+                    let read = socket.read(&mut buffer).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+                    seen.lock().unwrap().push(request.clone());
+
+                    let mut parts = request.split_whitespace();
+                    let method = parts.next().unwrap_or("");
+                    let target = parts.next().unwrap_or("");
+                    let path = target.split('?').next().unwrap_or("");
+                    let response = routes.get(&format!("{} {}", method, path))
+                        .or_else(|| routes.get(path))
+                        .cloned()
+                        .unwrap_or_else(|| TestResponse::status("404 Not Found"));
+
+                    let body = response.body.replace("{BASE}", &base);
+                    let mut head = format!("HTTP/1.1 {}\r\n", response.status);
+                    if let Some(content_type) = response.content_type {
+                        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+                    }
+                    if let Some(location) = response.location {
+                        head.push_str(&format!("Location: {}\r\n", location.replace("{BASE}", &base)));
+                    }
+                    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+                    let _ = socket.write_all(format!("{}{}", head, body).as_bytes()).await;
+                });
+            }
+        });
+
+        Self { base, requests }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    /// Every request received so far, as raw text
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
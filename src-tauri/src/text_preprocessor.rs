@@ -0,0 +1,512 @@
+use regex::Regex;
+use scraper::{ElementRef, Html, Node};
+use std::sync::OnceLock;
+use url::Url;
+
+static HTML_TAG: OnceLock<Regex> = OnceLock::new();
+static MARKDOWN_LINK: OnceLock<Regex> = OnceLock::new();
+
+fn html_tag_regex() -> &'static Regex {
+    HTML_TAG.get_or_init(|| {
+        Regex::new(r"(?i)<(?:html|body|head|meta|div|span|p|br|a|iframe|video|source|embed|object|table|td|li|ul|ol|font|b|i|u|strong|em|code)[\s>/]").unwrap()
+    })
+}
+
+fn markdown_link_regex() -> &'static Regex {
+    MARKDOWN_LINK.get_or_init(|| Regex::new(r"\[([^\]]*)\]\((https?://[^)\s]+)\)").unwrap())
+}
+
+/// Turns pasted text into plain text that URLs can be picked out of. RTF and HTML (including
+/// Windows CF_HTML clipboard data) are parsed, and link targets that aren't visible in the
+/// text (hrefs, embeds, hyperlink fields) are written next to their link text, so links keep
+/// their order. Percent-encoding is left alone: `%2F` in a path is not the same as `/`
+pub fn preprocess(text: &str) -> String {
+    let trimmed = text.trim_start();
+    let visible = if trimmed.starts_with("{\\rtf") {
+        rtf_to_text(trimmed)
+    } else if let Some(html) = html_source(trimmed) {
+        html_to_text(html)
+    } else {
+        html_escape::decode_html_entities(text).to_string()
+    };
+
+    // [label](url) becomes "label url" so the closing parenthesis isn't taken as part of the URL
+    markdown_link_regex().replace_all(&visible, "$1 $2 ").to_string()
+}
+
+/// Drops quotes and closing brackets that were picked up with a URL from the surrounding
+/// text, e.g. "(see https://example.com/a)". Balanced parentheses, as in Wikipedia URLs, stay
+pub fn trim_candidate(candidate: &str) -> &str {
+    let mut trimmed = candidate;
+    loop {
+        let before = trimmed.len();
+        trimmed = trimmed.trim_end_matches(['"', '\'', '”', '’', '»', '.', ',', ';', ':', '!', '?']);
+        for (open, close) in [('(', ')'), ('[', ']')] {
+            if trimmed.ends_with(close) && trimmed.matches(close).count() > trimmed.matches(open).count() {
+                trimmed = &trimmed[..trimmed.len() - 1];
+            }
+        }
+        if trimmed.len() == before {
+            return trimmed;
+        }
+    }
+}
+
+/// The destination of a link-protection or click-tracking redirect (Outlook Safe Links,
+/// Google, Facebook, YouTube and Slack redirects), decoded once from its query parameter
+pub fn unwrap_redirect(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_lowercase();
+    let path = parsed.path();
+
+    let parameter = if host.ends_with(".safelinks.protection.outlook.com") || host == "slack-redir.net" {
+        "url"
+    } else if (host == "www.google.com" || host == "google.com") && path == "/url" {
+        if parsed.query_pairs().any(|(name, _)| name == "q") { "q" } else { "url" }
+    } else if (host == "l.facebook.com" || host == "lm.facebook.com") && path == "/l.php" {
+        "u"
+    } else if host == "www.youtube.com" && path == "/redirect" {
+        "q"
+    } else {
+        return None;
+    };
+
+    parsed.query_pairs()
+        .find(|(name, _)| name == parameter)
+        .map(|(_, target)| target.into_owned())
+        .filter(|target| target.starts_with("https://") || target.starts_with("http://"))
+}
+
+// Windows puts a "Version:0.9 StartHTML:..." header (with the page's SourceURL, which isn't
+// a link the user copied) in front of clipboard HTML
+fn html_source(text: &str) -> Option<&str> {
+    if text.starts_with("Version:") && text.contains("StartHTML:") {
+        return text.find('<').map(|start| &text[start..]);
+    }
+    html_tag_regex().is_match(text).then_some(text)
+}
+
+fn html_to_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut text = String::new();
+
+    for node in document.tree.root().descendants() {
+        match node.value() {
+            Node::Text(node_text) => {
+                let in_code = node.parent()
+                    .and_then(|parent| parent.value().as_element())
+                    .is_some_and(|parent| matches!(parent.name(), "script" | "style"));
+                if !in_code {
+                    // Separated, so text from neighbouring elements doesn't run into a URL
+                    text.push_str(node_text);
+                    text.push(' ');
+                }
+            }
+            Node::Element(element) => {
+                // Outlook keeps the address it replaced with a Safe Links one
+                let original = element.attr("originalsrc");
+                let mut targets: Vec<&str> = original.into_iter().collect();
+                if original.is_none() {
+                    match element.name() {
+                        "a" | "area" => targets.extend(element.attr("href")),
+                        "iframe" | "embed" | "video" | "audio" | "source" => targets.extend(element.attr("src")),
+                        "object" => targets.extend(element.attr("data")),
+                        _ => {}
+                    }
+                }
+                // data-stringify-link (Slack), data-src (lazy embeds), data-url, ...
+                targets.extend(element.attrs().filter(|(name, _)| name.starts_with("data-")).map(|(_, value)| value));
+
+                let link_text: String = ElementRef::wrap(node).map(|element| element.text().collect()).unwrap_or_default();
+                let mut written: Vec<&str> = Vec::new();
+                for target in targets.into_iter().map(str::trim) {
+                    let is_web = target.starts_with("https://") || target.starts_with("http://");
+                    // A link showing its own address is already in the text
+                    if is_web && !link_text.contains(target) && !written.contains(&target) {
+                        text.push_str(target);
+                        text.push(' ');
+                        written.push(target);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Destination {
+    Text,
+    FieldInstruction,
+    Skipped,
+}
+
+#[derive(Clone, Copy)]
+struct RtfGroup {
+    destination: Destination,
+    // Fallback characters following each \uN
+    unicode_skip: usize,
+    // Set on the group holding a \field, and on the one that opened its \fldinst
+    opens_field: bool,
+    opens_instruction: bool,
+}
+
+// A field being read: its hyperlink target once the instruction is complete, and where its
+// displayed result starts in the text
+struct RtfField {
+    link: Option<String>,
+    result_start: usize,
+}
+
+// Groups that hold fonts, styles, metadata or binary data rather than document text
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl", "colortbl", "stylesheet", "info", "pict", "object", "themedata", "colorschememapping",
+    "latentstyles", "datastore", "listtable", "listoverridetable", "rsidtbl", "xmlnstbl", "generator",
+    "header", "footer", "headerl", "headerr", "footerl", "footerr", "bkmkstart", "bkmkend",
+];
+
+/// Visible text of an RTF document, with the target of each HYPERLINK field written before
+/// its displayed text (unless that text is the address itself). Groups are tracked properly,
+/// so instructions split over nested groups (as Word writes them) and \u / \'hh escapes
+/// come out right
+fn rtf_to_text(rtf: &str) -> String {
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut stack = vec![RtfGroup { destination: Destination::Text, unicode_skip: 1, opens_field: false, opens_instruction: false }];
+    let mut output = RtfOutput::default();
+    let mut ignorable = false;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let character = chars[index];
+        index += 1;
+        let group = *stack.last().unwrap();
+
+        match character {
+            '{' => {
+                stack.push(RtfGroup { opens_field: false, opens_instruction: false, ..group });
+                ignorable = false;
+            }
+            '}' => {
+                if stack.len() > 1 {
+                    output.close_group(stack.pop().unwrap());
+                }
+                output.pending_skip = 0;
+            }
+            '\r' | '\n' => {}
+            '\\' => {
+                let Some(&next) = chars.get(index) else { break };
+                if next.is_ascii_alphabetic() {
+                    let start = index;
+                    while chars.get(index).is_some_and(|c| c.is_ascii_alphabetic()) {
+                        index += 1;
+                    }
+                    let word: String = chars[start..index].iter().collect();
+                    let parameter_start = index;
+                    if chars.get(index) == Some(&'-') {
+                        index += 1;
+                    }
+                    while chars.get(index).is_some_and(|c| c.is_ascii_digit()) {
+                        index += 1;
+                    }
+                    let parameter: Option<i64> = chars[parameter_start..index].iter().collect::<String>().parse().ok();
+                    // A single space ends a control word and belongs to it
+                    if chars.get(index) == Some(&' ') {
+                        index += 1;
+                    }
+
+                    let top = stack.last_mut().unwrap();
+                    if std::mem::take(&mut ignorable) && word != "fldinst" {
+                        top.destination = Destination::Skipped;
+                        continue;
+                    }
+                    match word.as_str() {
+                        "field" if top.destination == Destination::Text => {
+                            top.opens_field = true;
+                            output.fields.push(RtfField { link: None, result_start: output.text.len() });
+                        }
+                        "fldinst" if top.destination != Destination::Skipped => {
+                            top.destination = Destination::FieldInstruction;
+                            top.opens_instruction = true;
+                            output.instructions.push(String::new());
+                        }
+                        "u" => {
+                            if let Some(code) = parameter {
+                                // Values above 32767 are written as negative numbers
+                                let code = if code < 0 { code + 65536 } else { code };
+                                output.pending_skip = 0;
+                                output.emit(char::from_u32(code as u32).unwrap_or('\u{fffd}'), top);
+                                output.pending_skip = top.unicode_skip;
+                            }
+                        }
+                        "uc" => top.unicode_skip = parameter.unwrap_or(1).max(0) as usize,
+                        "par" | "line" | "sect" | "page" | "row" => output.emit('\n', top),
+                        "tab" | "cell" => output.emit(' ', top),
+                        word if SKIPPED_DESTINATIONS.contains(&word) => top.destination = Destination::Skipped,
+                        _ => {}
+                    }
+                    continue;
+                }
+
+                index += 1;
+                match next {
+                    '\'' => {
+                        let hex: String = chars.iter().skip(index).take(2)
+                            .take_while(|c| c.is_ascii_hexdigit())
+                            .collect();
+                        index += hex.chars().count();
+                        if hex.len() == 2 {
+                            if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                                output.emit(windows_1252(byte), &group);
+                            }
+                        }
+                    }
+                    '*' => ignorable = true,
+                    '\\' | '{' | '}' => output.emit(next, &group),
+                    '~' => output.emit(' ', &group),
+                    '_' => output.emit('-', &group),
+                    '\r' | '\n' => output.emit('\n', &group),
+                    _ => {}
+                }
+            }
+            _ => output.emit(character, &group),
+        }
+    }
+
+    // Truncated documents leave groups open
+    while stack.len() > 1 {
+        output.close_group(stack.pop().unwrap());
+    }
+    output.text
+}
+
+#[derive(Default)]
+struct RtfOutput {
+    text: String,
+    instructions: Vec<String>,
+    fields: Vec<RtfField>,
+    pending_skip: usize,
+}
+
+impl RtfOutput {
+    fn emit(&mut self, character: char, group: &RtfGroup) {
+        if self.pending_skip > 0 {
+            self.pending_skip -= 1;
+            return;
+        }
+        match group.destination {
+            Destination::Text => self.text.push(character),
+            Destination::FieldInstruction => {
+                if let Some(instruction) = self.instructions.last_mut() {
+                    instruction.push(character);
+                }
+            }
+            Destination::Skipped => {}
+        }
+    }
+
+    fn close_group(&mut self, group: RtfGroup) {
+        if group.opens_instruction {
+            let link = self.instructions.pop().as_deref().and_then(field_hyperlink);
+            if let Some(field) = self.fields.last_mut() {
+                field.link = link;
+            }
+        }
+        if group.opens_field {
+            let Some(field) = self.fields.pop() else { return };
+            let Some(link) = field.link else { return };
+            let result = self.text[field.result_start..].to_string();
+            if !result.contains(&link) {
+                self.text.insert_str(field.result_start, &format!(" {} ", link));
+            }
+        }
+    }
+}
+
+// HYPERLINK "url" \o "tooltip"; \l "bookmark" links within the document and is skipped
+fn field_hyperlink(instruction: &str) -> Option<String> {
+    let instruction = instruction.trim_start();
+    if !instruction.get(..9).is_some_and(|keyword| keyword.eq_ignore_ascii_case("HYPERLINK")) {
+        return None;
+    }
+
+    let mut rest = &instruction[9..];
+    let mut tokens = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            tokens.push(&quoted[..end]);
+            rest = quoted.get(end + 1..).unwrap_or("");
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            tokens.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        match token {
+            "\\l" => return None,
+            "\\o" | "\\t" => {
+                tokens.next();
+            }
+            switch if switch.starts_with('\\') => {}
+            target => return Some(target.to_string()),
+        }
+    }
+    None
+}
+
+// \'hh escapes use the document code page, which is almost always Windows-1252
+fn windows_1252(byte: u8) -> char {
+    match byte {
+        0x80 => '€',
+        0x85 => '…',
+        0x91 => '‘',
+        0x92 => '’',
+        0x93 => '“',
+        0x94 => '”',
+        0x96 => '–',
+        0x97 => '—',
+        _ => char::from(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::url_parser::URLExtractor;
+
+    fn urls(text: &str) -> Vec<String> {
+        URLExtractor::new().unwrap()
+            .extract_urls(text).unwrap()
+            .urls.into_iter().map(|url| url.url).collect()
+    }
+
+    #[test]
+    fn test_word_rtf() {
+        let rtf = include_str!("../tests/fixtures/clipboard/word.rtf");
+        let text = preprocess(rtf);
+        assert!(text.starts_with("Café talk – see  https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf the keynote and"));
+        assert!(!text.contains("Times New Roman"));
+
+        let result = URLExtractor::new().unwrap().extract_urls(rtf).unwrap();
+        let found: Vec<&str> = result.urls.iter().map(|url| url.url.as_str()).collect();
+        assert_eq!(found, vec![
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf",
+            "https://vimeo.com/76979871?h=a1b2c3",
+            "https://www.youtube.com/watch?v=jNQXAC9IVRw",
+        ]);
+        assert_eq!(result.duplicates_removed, 0);
+    }
+
+    #[test]
+    fn test_outlook_html() {
+        let found = urls(include_str!("../tests/fixtures/clipboard/outlook.html"));
+        assert_eq!(found, vec![
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            // Encoded slash stays encoded
+            "https://contoso.sharepoint.com/sites/Team/Shared%20Documents/Slides%2FQ3.pptx?web=1",
+            "https://vimeo.com/76979871",
+        ]);
+    }
+
+    #[test]
+    fn test_slack_html() {
+        let result = URLExtractor::new().unwrap()
+            .extract_urls(include_str!("../tests/fixtures/clipboard/slack.html")).unwrap();
+        let found: Vec<&str> = result.urls.iter().map(|url| url.url.as_str()).collect();
+        // Not the SourceURL of the clipboard header
+        assert_eq!(found, vec![
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ",
+            "https://www.tiktok.com/@scout2015/video/6718335390845095173",
+            "https://vimeo.com/76979871",
+        ]);
+        assert_eq!(result.duplicates_removed, 0);
+    }
+
+    #[test]
+    fn test_plain_text_regressions() {
+        assert_eq!(
+            urls("Docs at https://example.com/files/a%2Fb.pdf and https://example.com/search?q=rock%20%26%20roll"),
+            vec!["https://example.com/files/a%2Fb.pdf", "https://example.com/search?q=rock%20%26%20roll"]
+        );
+        assert_eq!(urls("(see https://en.wikipedia.org/wiki/Rick_Astley_(singer)), or [this](https://vimeo.com/1)."), vec![
+            "https://en.wikipedia.org/wiki/Rick_Astley_(singer)",
+            "https://vimeo.com/1",
+        ]);
+        assert_eq!(urls("&lt;https://vimeo.com/2&gt; &quot;https://vimeo.com/3&quot;"), vec!["https://vimeo.com/2", "https://vimeo.com/3"]);
+        assert_eq!(
+            urls("https://www.google.com/url?sa=t&url=https%3A%2F%2Fvimeo.com%2F76979871&usg=AOv"),
+            vec!["https://vimeo.com/76979871"]
+        );
+        assert_eq!(
+            urls(r#"<iframe width="560" src="https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ?si=x" allowfullscreen></iframe>"#),
+            vec!["https://www.youtube-nocookie.com/embed/dQw4w9WgXcQ"]
+        );
+    }
+
+    #[test]
+    fn test_rtf_escapes_and_fields() {
+        let text = rtf_to_text(r#"{\rtf1\uc2 a\u8364\'80\'80b{\*\unknown https://hidden.example}\'e9\\\{{\field{\*\fldinst{HYPERLINK }{"https://example.com/x"}}{\fldrslt y}}}"#);
+        assert_eq!(text, "a€b\u{e9}\\{ https://example.com/x y");
+        // A broken hex escape before non-ASCII text doesn't swallow the characters after it
+        assert_eq!(rtf_to_text(r"{\rtf1 a\'ébc\'4éd}"), "aébcéd");
+        // Unterminated field in a truncated document
+        assert_eq!(rtf_to_text(r#"{\rtf1 {\field{\*\fldinst HYPERLINK "https://example.com/z""#), " https://example.com/z ");
+
+        assert_eq!(field_hyperlink(r#" HYPERLINK \l "_Toc1" "#), None);
+        assert_eq!(field_hyperlink(r#"HYPERLINK \o "tip" https://example.com/y"#).as_deref(), Some("https://example.com/y"));
+        assert_eq!(field_hyperlink("PAGEREF _Toc1"), None);
+    }
+
+    // Random mixes of markup characters and fragments of the samples must never panic or make
+    // extraction fail, and everything extracted must be a valid URL
+    #[test]
+    fn test_fuzz_preprocess() {
+        let samples = [
+            include_str!("../tests/fixtures/clipboard/word.rtf"),
+            include_str!("../tests/fixtures/clipboard/outlook.html"),
+            include_str!("../tests/fixtures/clipboard/slack.html"),
+        ];
+        let pieces = [
+            "{", "}", "\\", "\\'", "\\u", "-1", "\\*", "\\fldinst", "HYPERLINK", "\"", "<a href=\"", "<iframe src=",
+            ">", "</a>", "&amp;", "&#x", "&", "%2F", "%", "(", ")", "[", "](", "https://", "http://[", "vimeo.com/1",
+            "youtu.be/", "é", "\u{fffd}", " ", "\n", "\r\n", "Version:0.9 StartHTML:", "{\\rtf1 ", "?", "=", "#t=",
+        ];
+
+        let extractor = URLExtractor::new().unwrap();
+        let mut state: u64 = 0x9e3779b97f4a7c15;
+        let mut next = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+
+        for _ in 0..2000 {
+            let mut input = String::new();
+            if next(3) == 0 {
+                let sample = samples[next(samples.len())];
+                let mut end = next(sample.len());
+                while !sample.is_char_boundary(end) {
+                    end -= 1;
+                }
+                input.push_str(&sample[..end]);
+            }
+            for _ in 0..next(40) {
+                input.push_str(pieces[next(pieces.len())]);
+            }
+
+            let result = extractor.extract_urls(&input).unwrap();
+            for url in result.urls {
+                assert!(Url::parse(&url.url).is_ok(), "{:?} from {:?}", url.url, input);
+            }
+        }
+    }
+}
@@ -0,0 +1,372 @@
+use anyhow::{Result, anyhow};
+use image::imageops::FilterType;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use crate::public_fetch::PublicFetcher;
+
+pub const THUMBNAIL_WIDTH: u32 = 120;
+pub const THUMBNAIL_HEIGHT: u32 = 68;
+
+const MAX_CACHE_ENTRIES: usize = 256;
+const MAX_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_SOURCE_BYTES: usize = 10 * 1024 * 1024;
+const JPEG_QUALITY: u8 = 85;
+// Recency survives restarts through this file; sizes are always read from disk
+const INDEX_FILE: &str = "index.json";
+// Inserts and evictions save the index straight away, cache hits at most this often
+const RECENCY_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    size: u64,
+    last_used: u64,
+}
+
+// In-memory view of the cache directory. Recency is a counter rather than a timestamp so
+// two lookups within the same clock tick still order correctly
+#[derive(Debug, Default)]
+struct CacheIndex {
+    entries: HashMap<String, CacheEntry>,
+    total_bytes: u64,
+    clock: u64,
+    // Changed since the index file was last written
+    dirty: bool,
+    saved_at: Option<Instant>,
+}
+
+impl CacheIndex {
+    fn touch(&mut self, key: &str) -> bool {
+        self.clock += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.clock;
+                self.dirty = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, key: String, size: u64) {
+        self.clock += 1;
+        let entry = CacheEntry { size, last_used: self.clock };
+        if let Some(previous) = self.entries.insert(key, entry) {
+            self.total_bytes -= previous.size;
+        }
+        self.total_bytes += size;
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_bytes -= entry.size;
+        }
+    }
+
+    // Pops least recently used keys until the cache fits within both limits
+    fn evict(&mut self, max_entries: usize, max_bytes: u64) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.entries.len() > max_entries || self.total_bytes > max_bytes {
+            let oldest = self.entries.iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(key) => {
+                    self.remove(&key);
+                    evicted.push(key);
+                }
+                None => break,
+            }
+        }
+        evicted
+    }
+}
+
+/// Fetches remote thumbnails, shrinks them to queue size and keeps them in an on-disk LRU cache
+pub struct ThumbnailManager {
+    cache_dir: PathBuf,
+    fetcher: PublicFetcher,
+    index: Mutex<CacheIndex>,
+    // Keeps concurrent saves from interleaving their writes
+    save_lock: tokio::sync::Mutex<()>,
+    max_entries: usize,
+    max_bytes: u64,
+}
+
+impl ThumbnailManager {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let cache_dir = app_handle
+            .path()
+            .app_cache_dir()
+            .map_err(|e| anyhow!("Failed to get app cache directory: {}", e))?
+            .join("thumbnails");
+
+        Self::with_cache_dir(cache_dir, MAX_CACHE_ENTRIES, MAX_CACHE_BYTES)
+    }
+
+    pub fn with_cache_dir(cache_dir: PathBuf, max_entries: usize, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&cache_dir)?;
+
+        let manager = Self {
+            index: Mutex::new(load_index(&cache_dir)),
+            cache_dir,
+            fetcher: PublicFetcher::new(MAX_SOURCE_BYTES)?,
+            save_lock: tokio::sync::Mutex::new(()),
+            max_entries,
+            max_bytes,
+        };
+        // Nothing else can use the cache yet, so blocking here is fine
+        for path in manager.evict() {
+            let _ = std::fs::remove_file(path);
+        }
+        if let Some(json) = manager.index_json() {
+            let _ = write_replacing(&manager.cache_dir.join(INDEX_FILE), &json);
+        }
+
+        Ok(manager)
+    }
+
+    /// Returns the resized JPEG for a thumbnail URL, downloading it on a cache miss
+    pub async fn get_thumbnail(&self, url: &str) -> Result<Vec<u8>> {
+        let key = cache_key(url);
+
+        if self.mark_used(&key) {
+            match tokio::fs::read(self.entry_path(&key)).await {
+                Ok(bytes) => {
+                    self.save_index_if_due().await;
+                    return Ok(bytes);
+                }
+                // Deleted behind our back, fetch it again
+                Err(_) => self.lock_index().remove(&key),
+            }
+        }
+
+        let source = self.fetch(url).await?;
+        let thumbnail = resize_thumbnail(&source)?;
+        self.store(&key, &thumbnail).await?;
+
+        Ok(thumbnail)
+    }
+
+    pub async fn clear(&self) -> Result<()> {
+        let keys: Vec<String> = self.lock_index().entries.keys().cloned().collect();
+        for key in keys {
+            let _ = tokio::fs::remove_file(self.entry_path(&key)).await;
+        }
+        *self.lock_index() = CacheIndex::default();
+        self.save_index().await;
+        Ok(())
+    }
+
+    // Thumbnail URLs come from page and feed metadata, so they get the same checks as pages
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let (_, response) = self.fetcher.get(url).await?;
+        self.fetcher.read_body(response).await
+    }
+
+    fn mark_used(&self, key: &str) -> bool {
+        self.lock_index().touch(key)
+    }
+
+    async fn store(&self, key: &str, thumbnail: &[u8]) -> Result<()> {
+        tokio::fs::write(self.entry_path(key), thumbnail).await?;
+        self.lock_index().insert(key.to_string(), thumbnail.len() as u64);
+        for path in self.evict() {
+            let _ = tokio::fs::remove_file(path).await;
+        }
+        self.save_index().await;
+        Ok(())
+    }
+
+    // Drops least recently used entries over the limits, returning the files to delete
+    fn evict(&self) -> Vec<PathBuf> {
+        let evicted = self.lock_index().evict(self.max_entries, self.max_bytes);
+        evicted.iter().map(|key| self.entry_path(key)).collect()
+    }
+
+    async fn save_index_if_due(&self) {
+        let due = {
+            let index = self.lock_index();
+            index.dirty && index.saved_at.map_or(true, |saved_at| saved_at.elapsed() >= RECENCY_SAVE_INTERVAL)
+        };
+        if due {
+            self.save_index().await;
+        }
+    }
+
+    // Best effort: a lost index only means recency falls back to modification times
+    async fn save_index(&self) {
+        let _saving = self.save_lock.lock().await;
+        let Some(json) = self.index_json() else { return };
+        let path = self.cache_dir.join(INDEX_FILE);
+        let _ = tokio::task::spawn_blocking(move || write_replacing(&path, &json)).await;
+    }
+
+    // Recency of every entry, marking the index as saved
+    fn index_json(&self) -> Option<Vec<u8>> {
+        let mut index = self.lock_index();
+        index.dirty = false;
+        index.saved_at = Some(Instant::now());
+        let recency: HashMap<&String, u64> = index.entries.iter()
+            .map(|(key, entry)| (key, entry.last_used))
+            .collect();
+        serde_json::to_vec(&recency).ok()
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.jpg", key))
+    }
+
+    fn lock_index(&self) -> std::sync::MutexGuard<'_, CacheIndex> {
+        self.index.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn cache_key(url: &str) -> String {
+    hex::encode(Sha256::digest(url.as_bytes()))
+}
+
+// Write then rename so a reader never sees half a file
+fn write_replacing(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, contents)?;
+    std::fs::rename(&temp_path, path)
+}
+
+// Rebuilds the index from the files left by a previous run, in the saved order of use.
+// Files the saved index doesn't know about count as oldest, by modification time
+fn load_index(cache_dir: &Path) -> CacheIndex {
+    let recency: HashMap<String, u64> = std::fs::read(cache_dir.join(INDEX_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .unwrap_or_default();
+    let mut files: Vec<(String, u64, SystemTime)> = std::fs::read_dir(cache_dir)
+        .map(|entries| {
+            entries.flatten()
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    if path.extension().and_then(|e| e.to_str()) != Some("jpg") {
+                        return None;
+                    }
+                    let key = path.file_stem()?.to_str()?.to_string();
+                    let metadata = entry.metadata().ok()?;
+                    Some((key, metadata.len(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH)))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort_by_key(|(key, _, modified)| (recency.get(key).copied().unwrap_or(0), *modified));
+
+    let mut index = CacheIndex::default();
+    for (key, size, _) in files {
+        index.insert(key, size);
+    }
+    index
+}
+
+/// Scales and center-crops an image to the queue thumbnail size, encoded as JPEG
+pub fn resize_thumbnail(source: &[u8]) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(source)
+        .map_err(|e| anyhow!("Failed to decode thumbnail: {}", e))?;
+    let resized = image.resize_to_fill(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT, FilterType::Triangle).to_rgb8();
+
+    let mut output = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, JPEG_QUALITY)
+        .encode_image(&resized)
+        .map_err(|e| anyhow!("Failed to encode thumbnail: {}", e))?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("grabzilla-thumbnails-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_resize_thumbnail() {
+        let source = image::RgbImage::from_pixel(640, 480, image::Rgb([200, 30, 30]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(source)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let thumbnail = resize_thumbnail(&png).unwrap();
+        let decoded = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT));
+        assert!(resize_thumbnail(b"not an image").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction() {
+        let dir = test_cache_dir("lru");
+        let manager = ThumbnailManager::with_cache_dir(dir.clone(), 2, 1024).unwrap();
+
+        manager.store("a", &[0; 100]).await.unwrap();
+        manager.store("b", &[0; 100]).await.unwrap();
+        // Using "a" makes "b" the least recently used entry
+        assert!(manager.mark_used("a"));
+        manager.store("c", &[0; 100]).await.unwrap();
+
+        assert!(dir.join("a.jpg").exists());
+        assert!(!dir.join("b.jpg").exists());
+        assert!(dir.join("c.jpg").exists());
+
+        // Size limit applies as well as the entry limit
+        manager.store("d", &[0; 1000]).await.unwrap();
+        assert_eq!(manager.lock_index().entries.len(), 1);
+        assert!(dir.join("d.jpg").exists());
+
+        // A fresh manager picks up what is already on disk
+        let reloaded = ThumbnailManager::with_cache_dir(dir.clone(), 2, 1024).unwrap();
+        assert_eq!(reloaded.lock_index().total_bytes, 1000);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_recency_survives_restart() {
+        let dir = test_cache_dir("recency");
+        let manager = ThumbnailManager::with_cache_dir(dir.clone(), 3, 1024).unwrap();
+        manager.store("a", &[0; 10]).await.unwrap();
+        manager.store("b", &[0; 10]).await.unwrap();
+        manager.store("c", &[0; 10]).await.unwrap();
+        assert!(manager.mark_used("a"));
+
+        // A hit right after a save doesn't write the index again
+        let saved = std::fs::read(dir.join(INDEX_FILE)).unwrap();
+        manager.save_index_if_due().await;
+        assert_eq!(std::fs::read(dir.join(INDEX_FILE)).unwrap(), saved);
+        // Once the interval has passed it does
+        manager.lock_index().saved_at = None;
+        manager.save_index_if_due().await;
+        assert_ne!(std::fs::read(dir.join(INDEX_FILE)).unwrap(), saved);
+        drop(manager);
+
+        // "a" was written first but used last, so "b" goes when the limit shrinks
+        let reloaded = ThumbnailManager::with_cache_dir(dir.clone(), 2, 1024).unwrap();
+        assert!(dir.join("a.jpg").exists());
+        assert!(!dir.join("b.jpg").exists());
+        assert!(reloaded.mark_used("c"));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_is_gated() {
+        let dir = test_cache_dir("gated");
+        let manager = ThumbnailManager::with_cache_dir(dir.clone(), 2, 1024).unwrap();
+        for url in ["http://127.0.0.1:9/thumb.jpg", "http://169.254.169.254/latest/meta-data/", "file:///etc/passwd"] {
+            assert!(manager.get_thumbnail(url).await.is_err(), "{} was fetched", url);
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri_plugin_updater::UpdaterExt;
 use crate::security_manager::SecurityManager;
+use crate::proxy_manager::{current_routes, http_client};
 use std::fs;
 use tauri::AppHandle;
 
@@ -70,7 +71,12 @@ impl UpdateManager {
 
     /// Check for available updates
     pub async fn check_for_updates(&self) -> Result<Vec<UpdateInfo>> {
-        let updater = self.app_handle.updater_builder().build()?;
+        // The updater takes a single proxy, so host rules don't apply to it
+        let mut builder = self.app_handle.updater_builder();
+        if let Some(proxy) = current_routes().default_proxy() {
+            builder = builder.proxy(proxy.url().clone());
+        }
+        let updater = builder.build()?;
         let updates = updater.check().await?;
         
         let mut update_infos = Vec::new();
@@ -173,7 +179,7 @@ impl UpdateManager {
         }
 
         // Download the update data
-        let response = reqwest::get(&update.download_url).await?;
+        let response = http_client()?.get(&update.download_url).send().await?;
         let bytes = response.bytes().await?;
         Ok(bytes.to_vec())
     }
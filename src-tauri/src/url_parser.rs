@@ -1,27 +1,112 @@
 use anyhow::Result;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use url::Url;
+use crate::download_manager::{LiveStatus, VideoMetadata};
+use crate::platform_registry::{registry, PlatformDescriptor, PlatformRegistry};
+use crate::short_url_resolver::ShortUrlResolver;
+use crate::text_preprocessor::{preprocess, trim_candidate, unwrap_redirect};
 
+/// Id of a platform in the registry (see `platform_registry`), or "generic" for other sites
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum Platform {
-    #[serde(rename = "youtube")]
-    YouTube,
-    #[serde(rename = "vimeo")]
-    Vimeo,
-    #[serde(rename = "twitch")]
-    Twitch,
-    #[serde(rename = "tiktok")]
-    TikTok,
-    #[serde(rename = "instagram")]
-    Instagram,
-    #[serde(rename = "twitter")]
-    Twitter,
-    #[serde(rename = "facebook")]
-    Facebook,
-    #[serde(rename = "generic")]
-    Generic,
+#[serde(transparent)]
+pub struct Platform(String);
+
+impl Platform {
+    pub fn new(id: &str) -> Self {
+        Platform(id.to_lowercase())
+    }
+
+    pub fn generic() -> Self {
+        Platform("generic".to_string())
+    }
+
+    pub fn is_generic(&self) -> bool {
+        self.0 == "generic"
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What a URL points at, judged from its shape and refined once metadata is in
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlKind {
+    #[default]
+    Video,
+    Short,
+    Live,
+    /// Scheduled premieres and streams that haven't started
+    Premiere,
+    Playlist,
+    Channel,
+    /// One tab of a channel, e.g. /videos, /shorts or /streams
+    ChannelTab,
+    Album,
+    Clip,
+    Collection,
+}
+
+impl UrlKind {
+    /// Playlists, channels and other pages with several videos
+    pub fn is_multi_video(self) -> bool {
+        matches!(self, UrlKind::Playlist | UrlKind::Channel | UrlKind::ChannelTab | UrlKind::Album | UrlKind::Collection)
+    }
+
+    // When a URL matches several kinds, e.g. a channel's /live page, the most specific one wins
+    pub(crate) fn precedence(self) -> u8 {
+        match self {
+            UrlKind::Short => 0,
+            UrlKind::Clip => 1,
+            UrlKind::Live => 2,
+            UrlKind::Premiere => 3,
+            UrlKind::ChannelTab => 4,
+            UrlKind::Album => 5,
+            UrlKind::Collection => 6,
+            UrlKind::Playlist => 7,
+            UrlKind::Channel => 8,
+            UrlKind::Video => 9,
+        }
+    }
+
+    /// yt-dlp flags for reading this kind's metadata
+    pub fn metadata_args(self) -> &'static [&'static str] {
+        match self {
+            kind if kind.is_multi_video() => &["--flat-playlist"],
+            // Streams that haven't started have no formats yet, but do have a title and start time
+            UrlKind::Live | UrlKind::Premiere => &["--no-playlist", "--ignore-no-formats-error"],
+            _ => &["--no-playlist"],
+        }
+    }
+
+    /// Corrects the kind guessed from the URL with what yt-dlp reported
+    pub fn refine(self, metadata: &VideoMetadata) -> UrlKind {
+        match metadata.live_status {
+            Some(LiveStatus::IsLive) => return UrlKind::Live,
+            Some(LiveStatus::IsUpcoming) => return UrlKind::Premiere,
+            // A stream that has ended is a recording now
+            Some(LiveStatus::WasLive | LiveStatus::PostLive | LiveStatus::NotLive)
+                if matches!(self, UrlKind::Live | UrlKind::Premiere) => return UrlKind::Video,
+            _ => {}
+        }
+
+        let is_single_video = metadata.duration.is_some() || !metadata.formats.is_empty();
+        match (self.is_multi_video(), metadata.playlist_count.is_some()) {
+            (false, true) => UrlKind::Playlist,
+            (true, false) if is_single_video => UrlKind::Video,
+            _ => self,
+        }
+    }
+}
+
+/// Identifies a video independently of the URL variant it was linked with
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct VideoKey {
+    pub platform: Platform,
+    pub video_id: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,8 +116,17 @@ pub struct ExtractedUrl {
     pub title: Option<String>,
     pub is_valid: bool,
     pub original_text: String,
+    /// Same as `kind.is_multi_video()`
     pub is_playlist: bool,
+    #[serde(default)]
+    pub kind: UrlKind,
     pub playlist_count: Option<u32>,
+    /// Single videos only; used to drop the same video linked in different ways
+    #[serde(default)]
+    pub video_key: Option<VideoKey>,
+    /// Seconds, from a `t=`/`start=` parameter or `#t=` fragment, which is removed from `url`
+    #[serde(default)]
+    pub start_time: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,97 +137,96 @@ pub struct URLExtractionResult {
     pub duplicates_removed: usize,
 }
 
-pub struct PlatformPattern {
-    pub platform: Platform,
-    pub regex: Regex,
-}
-
 pub struct URLExtractor {
-    platform_patterns: Vec<PlatformPattern>,
+    registry: &'static PlatformRegistry,
     generic_url_regex: Regex,
 }
 
 impl URLExtractor {
     pub fn new() -> Result<Self> {
-        let platform_patterns = vec![
-            // YouTube patterns
-            PlatformPattern {
-                platform: Platform::YouTube,
-                regex: Regex::new(r"(?:youtube\.com/watch\?v=|youtu\.be/|youtube\.com/embed/|youtube\.com/v/)([a-zA-Z0-9_-]{11})")?,
-            },
-            PlatformPattern {
-                platform: Platform::YouTube,
-                regex: Regex::new(r"youtube\.com/playlist\?list=([a-zA-Z0-9_-]+)")?,
-            },
-            // Vimeo patterns
-            PlatformPattern {
-                platform: Platform::Vimeo,
-                regex: Regex::new(r"vimeo\.com/(?:channels/[^/]+/)?(?:groups/[^/]+/videos/)?(\d+)")?,
-            },
-            // Twitch patterns
-            PlatformPattern {
-                platform: Platform::Twitch,
-                regex: Regex::new(r"twitch\.tv/videos/(\d+)")?,
-            },
-            PlatformPattern {
-                platform: Platform::Twitch,
-                regex: Regex::new(r"twitch\.tv/[^/]+/clip/([a-zA-Z0-9_-]+)")?,
-            },
-            // TikTok patterns
-            PlatformPattern {
-                platform: Platform::TikTok,
-                regex: Regex::new(r"tiktok\.com/@[\w.-]+/video/(\d+)")?,
-            },
-            PlatformPattern {
-                platform: Platform::TikTok,
-                regex: Regex::new(r"vm\.tiktok\.com/([a-zA-Z0-9]+)")?,
-            },
-            // Instagram patterns
-            PlatformPattern {
-                platform: Platform::Instagram,
-                regex: Regex::new(r"instagram\.com/(?:p|reel|tv)/([a-zA-Z0-9_-]+)")?,
-            },
-            // Twitter patterns
-            PlatformPattern {
-                platform: Platform::Twitter,
-                regex: Regex::new(r"(?:twitter\.com|x\.com)/[^/]+/status/(\d+)")?,
-            },
-            // Facebook patterns
-            PlatformPattern {
-                platform: Platform::Facebook,
-                regex: Regex::new(r"facebook\.com/.*?/videos/(\d+)")?,
-            },
-        ];
-
         let generic_url_regex = Regex::new(r"https?://[^\s<>]+[^\s<>.,;:]")?;
 
         Ok(URLExtractor {
-            platform_patterns,
+            registry: registry(),
             generic_url_regex,
         })
     }
 
     pub fn extract_urls(&self, text: &str) -> Result<URLExtractionResult> {
+        let candidates = self.find_urls(text)?;
+        Ok(self.build_result(candidates))
+    }
+
+    /// Like `extract_urls`, but short links are resolved first so detection, dedup and
+    /// playlist flags apply to the URL they point at
+    pub async fn extract_urls_resolved(&self, text: &str, resolver: &ShortUrlResolver) -> Result<URLExtractionResult> {
+        let candidates = self.find_urls(text)?;
+        let resolved = resolver.resolve_all(candidates.iter().map(|(_, url)| url.clone()).collect()).await;
+
+        let candidates = candidates.into_iter()
+            .zip(resolved)
+            .map(|((original, _), url)| Ok((original, self.clean_url(&url)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.build_result(candidates))
+    }
+
+    /// Runs `extract_urls` over links that come with a title, such as bookmarks; the title
+    /// of the first occurrence of each URL is kept
+    pub fn extract_titled_urls(&self, links: &[(String, Option<String>)]) -> Result<URLExtractionResult> {
+        let mut candidates = Vec::new();
+        let mut titles = HashMap::new();
+        for (link, title) in links {
+            for candidate in self.find_urls(link)? {
+                if let Some(title) = title {
+                    titles.entry(candidate.0.clone()).or_insert_with(|| title.clone());
+                }
+                candidates.push(candidate);
+            }
+        }
+
+        let mut result = self.build_result(candidates);
+        for url in &mut result.urls {
+            url.title = titles.get(&url.original_text).cloned();
+        }
+        Ok(result)
+    }
+
+    // Every URL in the text as (original text, cleaned URL), duplicates included
+    fn find_urls(&self, text: &str) -> Result<Vec<(String, String)>> {
+        // RTF, HTML, entities and markdown become plain text with every link target in it
+        let preprocessed_text = preprocess(text);
+
+        // Extract all potential URLs using generic regex, then clean them
+        // (remove tracking parameters, etc.). Matches that don't parse, e.g. "http://[", aren't URLs
+        Ok(self.generic_url_regex.find_iter(&preprocessed_text)
+            .filter_map(|found| {
+                let candidate = trim_candidate(found.as_str());
+                let target = unwrap_redirect(candidate).unwrap_or_else(|| candidate.to_string());
+                self.clean_url(&target).ok().map(|cleaned| (candidate.to_string(), cleaned))
+            })
+            .collect())
+    }
+
+    fn build_result(&self, candidates: Vec<(String, String)>) -> URLExtractionResult {
         let mut found_urls = Vec::new();
-        let mut seen_urls = HashSet::new();
+        let mut seen_keys = HashSet::new();
         let mut duplicates_removed = 0;
 
-        // Preprocess text to handle different formats
-        let preprocessed_text = self.preprocess_text(text);
-
-        // First pass: Extract all potential URLs using generic regex
-        for cap in self.generic_url_regex.find_iter(&preprocessed_text) {
-            let url_str = cap.as_str();
-            
-            // Clean the URL (remove tracking parameters, etc.)
-            let cleaned_url = self.clean_url(url_str)?;
+        for (url_str, cleaned_url) in candidates {
+            let video_key = self.video_id(&cleaned_url)
+                .map(|(platform, video_id)| VideoKey { platform, video_id });
+            let (cleaned_url, start_time) = self.split_start_time(&cleaned_url, video_key.as_ref());
             
-            // Check for duplicates
-            if seen_urls.contains(&cleaned_url) {
+            // Check for duplicates. The same video from a mobile, embed or music link, or with
+            // a different start time, is still the same video
+            let dedup_key = match video_key {
+                Some(ref key) => format!("{}:{}", key.platform.as_str(), key.video_id),
+                None => cleaned_url.clone(),
+            };
+            if !seen_keys.insert(dedup_key) {
                 duplicates_removed += 1;
                 continue;
             }
-            seen_urls.insert(cleaned_url.clone());
 
             // Determine platform
             let platform = self.detect_platform(&cleaned_url);
@@ -141,204 +234,76 @@ impl URLExtractor {
             // Validate URL
             let is_valid = self.validate_url(&cleaned_url);
 
-            let is_playlist = self.detect_playlist(&cleaned_url);
+            let kind = self.url_kind(&url_str, &cleaned_url);
             
             found_urls.push(ExtractedUrl {
                 url: cleaned_url,
                 platform,
                 title: None, // Will be populated by metadata fetching later
                 is_valid,
-                original_text: url_str.to_string(),
-                is_playlist,
+                original_text: url_str,
+                is_playlist: kind.is_multi_video(),
+                kind,
                 playlist_count: None, // Will be populated later if it's a playlist
+                video_key,
+                start_time,
             });
         }
 
         let total_found = found_urls.len() + duplicates_removed;
         let valid_urls = found_urls.iter().filter(|u| u.is_valid).count();
 
-        Ok(URLExtractionResult {
+        URLExtractionResult {
             urls: found_urls,
             total_found,
             valid_urls,
             duplicates_removed,
-        })
-    }
-
-    fn preprocess_text(&self, text: &str) -> String {
-        let mut processed = text.to_string();
-
-        // Handle HTML-encoded URLs
-        processed = processed
-            .replace("&amp;", "&")
-            .replace("&lt;", "<")
-            .replace("&gt;", ">")
-            .replace("&quot;", "\"")
-            .replace("&#39;", "'")
-            .replace("&#x2F;", "/")
-            .replace("&#x3D;", "=");
-
-        // Handle RTF-style URLs (remove RTF formatting)
-        if processed.contains("\\field") {
-            // RTF hyperlink pattern: {\field{\*\fldinst{HYPERLINK "URL"}}{\fldrslt{Text}}}
-            let rtf_regex = regex::Regex::new(r#"\\field\{[^}]*HYPERLINK\s+"([^"]+)"[^}]*\}[^}]*\}"#).unwrap();
-            for cap in rtf_regex.captures_iter(&processed.clone()) {
-                if let Some(url) = cap.get(1) {
-                    processed.push('\n');
-                    processed.push_str(url.as_str());
-                }
-            }
         }
-
-        // Handle markdown-style links: [text](URL)
-        let markdown_regex = regex::Regex::new(r"\[([^\]]*)\]\(([^)]+)\)").unwrap();
-        for cap in markdown_regex.captures_iter(&processed.clone()) {
-            if let Some(url) = cap.get(2) {
-                processed.push('\n');
-                processed.push_str(url.as_str());
-            }
-        }
-
-        // Handle HTML anchor tags: <a href="URL">text</a>
-        let html_regex = regex::Regex::new(r#"<a[^>]+href\s*=\s*["']([^"']+)["'][^>]*>"#).unwrap();
-        for cap in html_regex.captures_iter(&processed.clone()) {
-            if let Some(url) = cap.get(1) {
-                processed.push('\n');
-                processed.push_str(url.as_str());
-            }
-        }
-
-        // Handle URL-encoded characters
-        processed = urlencoding::decode(&processed).unwrap_or(std::borrow::Cow::Borrowed(&processed)).to_string();
-
-        processed
     }
 
-    fn clean_url(&self, url: &str) -> Result<String> {
+    /// Normalises a URL: short forms such as youtu.be are expanded, and the query is cut down
+    /// to the parameters the platform actually uses. Unrecognised sites only lose well-known
+    /// tracking parameters. Shortener links need HTTP and are followed by ShortUrlResolver
+    /// (see `extract_urls_resolved`)
+    pub fn clean_url(&self, url: &str) -> Result<String> {
         let mut parsed_url = Url::parse(url)?;
-        
-        // Remove common tracking parameters
-        let tracking_params = [
-            "utm_source", "utm_medium", "utm_campaign", "utm_content", "utm_term",
-            "fbclid", "gclid", "ref", "referrer", "source", "campaign",
-        ];
-        
-        // Collect pairs to keep
-        let pairs_to_keep: Vec<_> = parsed_url
-            .query_pairs()
-            .filter(|pair| !tracking_params.contains(&pair.0.as_ref()))
-            .map(|(k, v)| (k.to_string(), v.to_string()))
-            .collect();
-        
-        // Clear and rebuild query
-        parsed_url.set_query(None);
-        if !pairs_to_keep.is_empty() {
-            let query_string = pairs_to_keep
-                .iter()
-                .map(|(k, v)| format!("{}={}", k, v))
-                .collect::<Vec<_>>()
-                .join("&");
-            parsed_url.set_query(Some(&query_string));
-        }
-
-        // Expand shortened URLs (basic cases)
-        let cleaned = parsed_url.to_string();
-        Ok(self.expand_shortened_url(&cleaned))
+        self.registry.expand_short_form(&mut parsed_url);
+
+        let host = parsed_url.host_str().unwrap_or("").to_lowercase();
+        let definition = self.registry.for_host(&host);
+        // Start times are kept for split_start_time to read
+        let start_params = definition.map(|platform| platform.start_time_params.as_slice()).unwrap_or_default();
+        let allowed = definition.and_then(|platform| platform.query_params.as_ref());
+        let keep = |name: &str| match allowed {
+            Some(allowed) => allowed.iter().chain(start_params).any(|param| param == name),
+            None => !is_tracking_param(name),
+        };
+        retain_query_pairs(&mut parsed_url, keep);
+
+        Ok(parsed_url.to_string())
     }
 
-    fn expand_shortened_url(&self, url: &str) -> String {
-        // Handle common YouTube short URLs
-        if url.contains("youtu.be/") {
-            if let Some(video_id) = url.split("youtu.be/").nth(1) {
-                let video_id = video_id.split('?').next().unwrap_or(video_id).split('&').next().unwrap_or(video_id);
-                return format!("https://www.youtube.com/watch?v={}", video_id);
-            }
-        }
-        
-        // Handle YouTube Shorts
-        if url.contains("youtube.com/shorts/") {
-            if let Some(video_id) = url.split("shorts/").nth(1) {
-                let video_id = video_id.split('?').next().unwrap_or(video_id).split('&').next().unwrap_or(video_id);
-                return format!("https://www.youtube.com/watch?v={}", video_id);
-            }
-        }
-
-        // Handle Vimeo short URLs
-        if url.contains("vimeo.com/") && !url.contains("/video/") {
-            // Already in correct format
-            return url.to_string();
-        }
-
-        // Handle TikTok short URLs
-        if url.contains("vm.tiktok.com/") || url.contains("vt.tiktok.com/") {
-            // These need HTTP resolution in real implementation
-            // For now, return as-is since yt-dlp can handle them
-            return url.to_string();
-        }
-
-        // Handle Twitter/X short URLs
-        if url.contains("t.co/") {
-            // These need HTTP resolution in real implementation
-            // For now, return as-is
-            return url.to_string();
-        }
-
-        // Handle Instagram short URLs
-        if url.contains("instagr.am/") {
-            return url.replace("instagr.am/", "instagram.com/");
-        }
-
-        // Handle other common URL shorteners
-        let shorteners = ["bit.ly", "tinyurl.com", "goo.gl", "ow.ly", "is.gd", "buff.ly"];
-        for shortener in &shorteners {
-            if url.contains(shortener) {
-                // In a real implementation, we'd make HTTP HEAD requests to resolve these
-                // For now, return as-is since yt-dlp might handle some of them
-                return url.to_string();
-            }
-        }
-        
-        url.to_string()
+    pub fn detect_platform(&self, url: &str) -> Platform {
+        self.registry.detect(url)
     }
 
-    fn detect_platform(&self, url: &str) -> Platform {
-        // Sort patterns by priority and check each one
-        for pattern in &self.platform_patterns {
-            if pattern.regex.is_match(url) {
-                return pattern.platform.clone();
-            }
+    /// Platform and video id for a single-video URL. Playlists and unrecognised URLs have none
+    pub fn video_id(&self, url: &str) -> Option<(Platform, String)> {
+        if classify_url(url).is_multi_video() {
+            return None;
         }
-        
-        Platform::Generic
+
+        // Cleaning turns youtu.be and Shorts links into watch URLs the patterns recognise
+        let cleaned = self.clean_url(url).unwrap_or_else(|_| url.to_string());
+        self.registry.video_id(&cleaned)
     }
 
-    fn detect_playlist(&self, url: &str) -> bool {
-        // YouTube playlist patterns
-        if url.contains("youtube.com") && (url.contains("list=") || url.contains("playlist")) {
-            return true;
-        }
-        
-        // YouTube channel URLs (can be treated as playlists)
-        if url.contains("youtube.com") && (url.contains("/channel/") || url.contains("/c/") || url.contains("/@")) {
-            return true;
-        }
-        
-        // Vimeo showcases/albums
-        if url.contains("vimeo.com") && (url.contains("/showcase/") || url.contains("/album/")) {
-            return true;
-        }
-        
-        // TikTok user profiles (can be treated as playlists)
-        if url.contains("tiktok.com") && url.contains("/@") && !url.contains("/video/") {
-            return true;
-        }
-        
-        // Twitch collections
-        if url.contains("twitch.tv") && url.contains("/collection/") {
-            return true;
+    // Cleaning turns Shorts into watch URLs, so a Short is recognised by the original link
+    fn url_kind(&self, original: &str, cleaned: &str) -> UrlKind {
+        match self.registry.classify(cleaned) {
+            UrlKind::Video if self.registry.classify(original) == UrlKind::Short => UrlKind::Short,
+            kind => kind,
         }
-        
-        false
     }
 
     fn validate_url(&self, url: &str) -> bool {
@@ -356,20 +321,108 @@ impl URLExtractor {
         }
     }
 
-    pub fn get_supported_platforms(&self) -> Vec<Platform> {
-        let mut platforms: HashSet<_> = self.platform_patterns
-            .iter()
-            .map(|p| p.platform.clone())
-            .collect();
-        
-        platforms.insert(Platform::Generic);
-        platforms.into_iter().collect()
+    pub fn get_supported_platforms(&self) -> Vec<PlatformDescriptor> {
+        self.registry.descriptors()
+    }
+
+    // Moves a start time out of a video URL. Only recognised videos are touched, since on other
+    // sites `t` may mean something else entirely
+    fn split_start_time(&self, url: &str, video_key: Option<&VideoKey>) -> (String, Option<f64>) {
+        let Some(key) = video_key else {
+            return (url.to_string(), None);
+        };
+        let Ok(mut parsed) = Url::parse(url) else {
+            return (url.to_string(), None);
+        };
+
+        let mut start_time = None;
+        let start_params = self.registry.get(&key.platform).map(|p| p.start_time_params.as_slice()).unwrap_or_default();
+        if !start_params.is_empty() {
+            let is_start = |name: &str| start_params.iter().any(|param| param == name);
+            start_time = parsed.query_pairs()
+                .filter(|(name, _)| is_start(name))
+                .find_map(|(_, value)| parse_timestamp(&value));
+            retain_query_pairs(&mut parsed, |name| !is_start(name));
+        }
+        // Vimeo and YouTube also accept #t=1m30s
+        if let Some(fragment) = parsed.fragment().and_then(|f| f.strip_prefix("t=")).map(str::to_string) {
+            start_time = start_time.or_else(|| parse_timestamp(&fragment));
+            parsed.set_fragment(None);
+        }
+
+        (parsed.to_string(), start_time)
+    }
+}
+
+/// "90", "90s", "1m30s" or "1h2m3s" as seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds >= 0.0).then_some(seconds);
     }
+
+    let mut total = 0.0;
+    let mut number = String::new();
+    for c in value.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'h' | 'm' | 's' => {
+                let amount: f64 = number.parse().ok()?;
+                total += amount * match c { 'h' => 3600.0, 'm' => 60.0, _ => 1.0 };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    number.is_empty().then_some(total)
+}
+
+// Drops the query parameters `keep` rejects. The query is only rebuilt (and re-encoded by
+// the url crate) when something was actually removed, so untouched URLs keep their encoding
+fn retain_query_pairs(url: &mut Url, keep: impl Fn(&str) -> bool) {
+    let pairs: Vec<(String, String)> = url.query_pairs()
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    let kept: Vec<&(String, String)> = pairs.iter().filter(|(name, _)| keep(name)).collect();
+    if kept.len() == pairs.len() {
+        // "?" on its own carries nothing
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        return;
+    }
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_")
+        || matches!(name, "fbclid" | "gclid" | "dclid" | "msclkid" | "mc_cid" | "mc_eid" | "igshid" | "_hsenc" | "_hsmi")
+}
+
+/// Kind of a URL judged from its shape alone
+pub fn classify_url(url: &str) -> UrlKind {
+    registry().classify(url)
+}
+
+/// Matches a host against user-entered patterns, where "youtube.com" (or "*.youtube.com") also
+/// covers its subdomains. Returns the length of the most specific matching pattern
+pub fn match_host_patterns(patterns: &[String], host: &str) -> Option<usize> {
+    let host = host.to_lowercase();
+    patterns.iter()
+        .map(|pattern| pattern.trim().trim_start_matches("*.").to_lowercase())
+        .filter(|pattern| !pattern.is_empty())
+        .filter(|pattern| host == *pattern || host.ends_with(&format!(".{}", pattern)))
+        .map(|pattern| pattern.len())
+        .max()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::security_manager::SecurityManager;
 
     #[test]
     fn test_youtube_url_extraction() {
@@ -379,7 +432,7 @@ mod tests {
         let result = extractor.extract_urls(text).unwrap();
         
         assert_eq!(result.urls.len(), 2);
-        assert!(result.urls.iter().all(|u| matches!(u.platform, Platform::YouTube)));
+        assert!(result.urls.iter().all(|u| u.platform == Platform::new("youtube")));
         assert!(result.urls.iter().all(|u| u.is_valid));
     }
 
@@ -409,8 +462,53 @@ mod tests {
         
         let result = extractor.extract_urls(text).unwrap();
         
-        // Should have 2 unique URLs (the youtu.be gets expanded to different format)
-        assert!(result.duplicates_removed > 0 || result.urls.len() == 2);
+        // youtu.be links are the same video as the watch URL
+        assert_eq!(result.urls.len(), 1);
+        assert_eq!(result.duplicates_removed, 2);
+    }
+
+    #[test]
+    fn test_canonical_video_key() {
+        let extractor = URLExtractor::new().unwrap();
+        let text = "
+            https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=30
+            https://m.youtube.com/watch?v=dQw4w9WgXcQ
+            https://www.youtube.com/embed/dQw4w9WgXcQ
+            https://music.youtube.com/watch?v=dQw4w9WgXcQ
+            https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ
+            https://vimeo.com/76979871#t=1m30s
+            https://vimeo.com/76979871
+            https://example.com/page?t=30
+        ";
+        
+        let result = extractor.extract_urls(text).unwrap();
+        
+        assert_eq!(result.urls.len(), 3);
+        assert_eq!(result.duplicates_removed, 5);
+        
+        let youtube = &result.urls[0];
+        assert_eq!(youtube.video_key, Some(VideoKey { platform: Platform::new("youtube"), video_id: "dQw4w9WgXcQ".to_string() }));
+        assert_eq!(youtube.start_time, Some(30.0));
+        assert_eq!(youtube.url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        
+        let vimeo = &result.urls[1];
+        assert_eq!(vimeo.start_time, Some(90.0));
+        assert_eq!(vimeo.url, "https://vimeo.com/76979871");
+        
+        // Unknown sites keep their parameters and have no key
+        assert_eq!(result.urls[2].url, "https://example.com/page?t=30");
+        assert_eq!(result.urls[2].video_key, None);
+        assert_eq!(result.urls[2].start_time, None);
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("42"), Some(42.0));
+        assert_eq!(parse_timestamp("42s"), Some(42.0));
+        assert_eq!(parse_timestamp("1h2m3s"), Some(3723.0));
+        assert_eq!(parse_timestamp("2m"), Some(120.0));
+        assert_eq!(parse_timestamp("1m30"), None);
+        assert_eq!(parse_timestamp("soon"), None);
     }
 
     #[test]
@@ -424,4 +522,210 @@ mod tests {
         assert!(!result.urls[0].url.contains("utm_source"));
         assert!(!result.urls[0].url.contains("fbclid"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_platform_query_rules() {
+        let extractor = URLExtractor::new().unwrap();
+
+        assert_eq!(
+            extractor.clean_url("https://www.youtube.com/watch?feature=share&v=dQw4w9WgXcQ&list=PL1&si=abc&pp=x").unwrap(),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1"
+        );
+        assert_eq!(
+            extractor.clean_url("https://youtu.be/dQw4w9WgXcQ?si=abc&t=42").unwrap(),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=42"
+        );
+        assert_eq!(
+            extractor.clean_url("https://www.youtube.com/shorts/dQw4w9WgXcQ?feature=share").unwrap(),
+            "https://www.youtube.com/watch?v=dQw4w9WgXcQ"
+        );
+        assert_eq!(
+            extractor.clean_url("https://vimeo.com/76979871?h=a1b2c3&share=copy").unwrap(),
+            "https://vimeo.com/76979871?h=a1b2c3"
+        );
+        assert_eq!(
+            extractor.clean_url("https://www.tiktok.com/@user/video/123?is_from_webapp=1&sender_device=pc").unwrap(),
+            "https://www.tiktok.com/@user/video/123"
+        );
+
+        // Unknown sites keep parameters like ref and source, and encoded values stay encoded
+        assert_eq!(
+            extractor.clean_url("https://example.com/v?ref=home&source=feed&q=a%26b%3Dc&utm_source=x").unwrap(),
+            "https://example.com/v?ref=home&source=feed&q=a%26b%3Dc"
+        );
+        // Nothing removed, nothing re-encoded
+        assert_eq!(extractor.clean_url("https://example.com/v?q=a%20b~").unwrap(), "https://example.com/v?q=a%20b~");
+
+        // Facebook posts are addressed by their query
+        assert_eq!(
+            extractor.clean_url("https://www.facebook.com/permalink.php?story_fbid=123456&id=789&mibextid=abc").unwrap(),
+            "https://www.facebook.com/permalink.php?story_fbid=123456&id=789"
+        );
+        assert_eq!(
+            extractor.clean_url("https://m.facebook.com/story.php?story_fbid=123456&id=789").unwrap(),
+            "https://m.facebook.com/story.php?story_fbid=123456&id=789"
+        );
+
+        // start survives cleaning so it can become the start time
+        let result = extractor.extract_urls("https://www.youtube.com/watch?v=dQw4w9WgXcQ&start=90&si=abc").unwrap();
+        assert_eq!(result.urls[0].url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(result.urls[0].start_time, Some(90.0));
+    }
+
+    #[test]
+    fn test_platform_url_shapes() {
+        let extractor = URLExtractor::new().unwrap();
+        // URL, platform, video id (None for pages with several videos), playlist or channel
+        let cases: &[(&str, &str, Option<&str>, bool)] = &[
+            ("https://www.dailymotion.com/video/x8m8jle", "dailymotion", Some("x8m8jle"), false),
+            ("https://dai.ly/x8m8jle", "dailymotion", Some("x8m8jle"), false),
+            ("https://www.dailymotion.com/embed/video/x8m8jle?autoplay=1", "dailymotion", Some("x8m8jle"), false),
+            ("https://geo.dailymotion.com/player.html?video=x8m8jle", "dailymotion", Some("x8m8jle"), false),
+            ("https://www.dailymotion.com/playlist/x6hynp", "dailymotion", None, true),
+            ("https://www.dailymotion.com/arte", "dailymotion", None, true),
+            ("https://www.reddit.com/r/videos/comments/1abc2de/some_title/", "reddit", Some("1abc2de"), false),
+            ("https://old.reddit.com/r/videos/comments/1abc2de/", "reddit", Some("1abc2de"), false),
+            ("https://redd.it/1abc2de", "reddit", Some("1abc2de"), false),
+            ("https://v.redd.it/h3kx9a8b7c6d", "reddit", Some("h3kx9a8b7c6d"), false),
+            ("https://www.bilibili.com/video/BV1GJ411x7h7/?spm_id_from=333.788", "bilibili", Some("BV1GJ411x7h7"), false),
+            ("https://m.bilibili.com/video/av170001", "bilibili", Some("av170001"), false),
+            ("https://player.bilibili.com/player.html?bvid=BV1GJ411x7h7&page=1", "bilibili", Some("BV1GJ411x7h7"), false),
+            ("https://www.bilibili.com/bangumi/play/ep508404", "bilibili", Some("ep508404"), false),
+            ("https://www.bilibili.com/bangumi/play/ss33073", "bilibili", None, true),
+            ("https://space.bilibili.com/946974", "bilibili", None, true),
+            ("https://space.bilibili.com/946974/channel/collectiondetail?sid=1", "bilibili", None, true),
+            ("https://soundcloud.com/forss/flickermood", "soundcloud", Some("forss/flickermood"), false),
+            ("https://m.soundcloud.com/forss/flickermood?in=forss/sets/soulhack", "soundcloud", Some("forss/flickermood"), false),
+            ("https://soundcloud.com/forss/sets/soulhack", "soundcloud", None, true),
+            ("https://soundcloud.com/forss", "soundcloud", None, true),
+            ("https://soundcloud.com/forss/tracks", "soundcloud", None, true),
+            ("https://radiohead.bandcamp.com/track/creep", "bandcamp", Some("radiohead.bandcamp.com/track/creep"), false),
+            ("https://radiohead.bandcamp.com/album/kid-a-mnesia", "bandcamp", None, true),
+            ("https://radiohead.bandcamp.com/music", "bandcamp", None, true),
+            ("https://rumble.com/v4k2r7m-some-video-title.html", "rumble", Some("v4k2r7m"), false),
+            ("https://rumble.com/embed/v3xyz12/?pub=4", "rumble", Some("v3xyz12"), false),
+            ("https://rumble.com/c/SomeChannel", "rumble", None, true),
+            ("https://rumble.com/user/someone", "rumble", None, true),
+            ("https://kick.com/xqc/videos/0f5f1c5e-8f5a-4a3e-9c39-1c2d3e4f5a6b", "kick", Some("0f5f1c5e-8f5a-4a3e-9c39-1c2d3e4f5a6b"), false),
+            ("https://kick.com/xqc/clips/clip_01HZ3ABCDEF", "kick", Some("clip_01HZ3ABCDEF"), false),
+            ("https://kick.com/xqc?clip=clip_01HZ3ABCDEF", "kick", Some("clip_01HZ3ABCDEF"), false),
+            // A channel page plays the live stream, if any; it isn't a video
+            ("https://kick.com/xqc", "kick", None, false),
+            ("https://bsky.app/profile/bsky.app/post/3l6oveex3ii2l", "bluesky", Some("bsky.app/post/3l6oveex3ii2l"), false),
+            ("https://www.threads.net/@zuck/post/C8rYbDnSIRq?xmt=abc", "threads", Some("C8rYbDnSIRq"), false),
+            ("https://www.threads.com/@zuck/post/C8rYbDnSIRq", "threads", Some("C8rYbDnSIRq"), false),
+        ];
+
+        let security_manager = SecurityManager::new().unwrap();
+        for (url, platform, video_id, is_playlist) in cases {
+            // Downloads are refused for hosts outside the whitelist
+            assert!(security_manager.validate_network_access(url), "{}", url);
+            let result = extractor.extract_urls(url).unwrap();
+            assert_eq!(result.urls.len(), 1, "{}", url);
+            let extracted = &result.urls[0];
+            assert_eq!(extracted.platform.as_str(), *platform, "{}", url);
+            assert_eq!(extracted.video_key.as_ref().map(|key| key.video_id.as_str()), *video_id, "{}", url);
+            assert_eq!(extracted.is_playlist, *is_playlist, "{}", url);
+        }
+
+        // Short forms and per-platform query rules
+        assert_eq!(extractor.clean_url("https://dai.ly/x8m8jle").unwrap(), "https://www.dailymotion.com/video/x8m8jle");
+        assert_eq!(extractor.clean_url("https://redd.it/1abc2de").unwrap(), "https://www.reddit.com/comments/1abc2de");
+        assert_eq!(
+            extractor.clean_url("https://www.bilibili.com/video/BV1GJ411x7h7/?p=2&spm_id_from=333.788&vd_source=x").unwrap(),
+            "https://www.bilibili.com/video/BV1GJ411x7h7/?p=2"
+        );
+        assert_eq!(
+            extractor.clean_url("https://www.threads.net/@zuck/post/C8rYbDnSIRq?xmt=abc&slof=1").unwrap(),
+            "https://www.threads.net/@zuck/post/C8rYbDnSIRq"
+        );
+    }
+
+    #[test]
+    fn test_url_kinds() {
+        let cases = [
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ", UrlKind::Video),
+            ("https://www.youtube.com/shorts/dQw4w9WgXcQ", UrlKind::Short),
+            ("https://www.youtube.com/live/jfKfPfyJRdk", UrlKind::Live),
+            ("https://www.youtube.com/@lofigirl/live", UrlKind::Live),
+            ("https://www.youtube.com/clip/UgkxU2HSeGL_NvmDJ-nQJrlLwllwMDBdGZFs", UrlKind::Clip),
+            ("https://www.youtube.com/playlist?list=PLrAXtmErZgOeiKm4sgNOknGvNjby9efdf", UrlKind::Playlist),
+            ("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1", UrlKind::Playlist),
+            ("https://www.youtube.com/@mkbhd", UrlKind::Channel),
+            ("https://www.youtube.com/channel/UCBJycsmduvYEL83R_U4JriQ/videos", UrlKind::ChannelTab),
+            ("https://www.youtube.com/@mkbhd/shorts", UrlKind::ChannelTab),
+            ("https://www.youtube.com/@mkbhd/streams", UrlKind::ChannelTab),
+            ("https://vimeo.com/album/2222222", UrlKind::Album),
+            ("https://vimeo.com/showcase/7654321", UrlKind::Playlist),
+            ("https://vimeo.com/event/1234567", UrlKind::Live),
+            ("https://www.twitch.tv/shroud", UrlKind::Live),
+            ("https://www.twitch.tv/shroud/videos", UrlKind::ChannelTab),
+            ("https://www.twitch.tv/videos/2012345678", UrlKind::Video),
+            ("https://clips.twitch.tv/FunnyClipName-abc123", UrlKind::Clip),
+            ("https://www.twitch.tv/collections/abcDEF123", UrlKind::Collection),
+            ("https://www.tiktok.com/@user/live", UrlKind::Live),
+            ("https://www.instagram.com/reel/C1a2b3c4d5e/", UrlKind::Short),
+            ("https://radiohead.bandcamp.com/album/kid-a-mnesia", UrlKind::Album),
+            ("https://space.bilibili.com/946974/lists/1234", UrlKind::Collection),
+            ("https://live.bilibili.com/21452505", UrlKind::Live),
+            ("https://kick.com/xqc", UrlKind::Live),
+            ("https://kick.com/xqc?clip=clip_01HZ3ABCDEF", UrlKind::Clip),
+            ("https://kick.com/xqc/videos", UrlKind::ChannelTab),
+            ("https://example.com/video.mp4", UrlKind::Video),
+        ];
+        for (url, kind) in cases {
+            assert_eq!(classify_url(url), kind, "{}", url);
+        }
+
+        // Site pages share the shape of a channel name but aren't channels or streams
+        for url in ["https://www.twitch.tv/directory", "https://www.twitch.tv/videos", "https://www.twitch.tv/settings/", "https://kick.com/categories"] {
+            assert!(!matches!(classify_url(url), UrlKind::Live | UrlKind::Channel | UrlKind::ChannelTab), "{}", url);
+        }
+
+        // Shorts are cleaned into watch URLs but stay Shorts
+        let extractor = URLExtractor::new().unwrap();
+        let result = extractor.extract_urls("https://youtube.com/shorts/dQw4w9WgXcQ?feature=share").unwrap();
+        assert_eq!(result.urls[0].url, "https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        assert_eq!(result.urls[0].kind, UrlKind::Short);
+        assert!(!result.urls[0].is_playlist);
+        let result = extractor.extract_urls("https://www.youtube.com/@lofigirl/live").unwrap();
+        assert_eq!(result.urls[0].kind, UrlKind::Live);
+        assert!(!result.urls[0].is_playlist);
+    }
+
+    #[test]
+    fn test_kind_refined_by_metadata() {
+        let upcoming = VideoMetadata { live_status: Some(LiveStatus::IsUpcoming), ..Default::default() };
+        assert_eq!(UrlKind::Video.refine(&upcoming), UrlKind::Premiere);
+        assert_eq!(UrlKind::Premiere.metadata_args(), &["--no-playlist", "--ignore-no-formats-error"]);
+
+        let ended = VideoMetadata { live_status: Some(LiveStatus::WasLive), duration: Some(3600.0), ..Default::default() };
+        assert_eq!(UrlKind::Live.refine(&ended), UrlKind::Video);
+        assert_eq!(UrlKind::Short.refine(&ended), UrlKind::Short);
+
+        // A channel URL that yt-dlp resolved to a single video, and the other way round
+        let video = VideoMetadata { duration: Some(212.0), ..Default::default() };
+        assert_eq!(UrlKind::Channel.refine(&video), UrlKind::Video);
+        let playlist = VideoMetadata { playlist_count: Some(12), ..Default::default() };
+        assert_eq!(UrlKind::Video.refine(&playlist), UrlKind::Playlist);
+        assert_eq!(UrlKind::ChannelTab.refine(&playlist), UrlKind::ChannelTab);
+        assert_eq!(UrlKind::ChannelTab.metadata_args(), &["--flat-playlist"]);
+    }
+
+    #[test]
+    fn test_video_id() {
+        let extractor = URLExtractor::new().unwrap();
+
+        let expected = Some((Platform::new("youtube"), "dQw4w9WgXcQ".to_string()));
+        assert_eq!(extractor.video_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ&utm_source=x"), expected);
+        assert_eq!(extractor.video_id("https://youtu.be/dQw4w9WgXcQ?t=42"), expected);
+        assert_eq!(extractor.video_id("https://www.youtube.com/shorts/dQw4w9WgXcQ"), expected);
+        assert_eq!(
+            extractor.video_id("https://vimeo.com/123456789"),
+            Some((Platform::new("vimeo"), "123456789".to_string()))
+        );
+
+        assert_eq!(extractor.video_id("https://www.youtube.com/playlist?list=PL123"), None);
+        assert_eq!(extractor.video_id("https://example.com/video.mp4"), None);
+    }
+}
@@ -0,0 +1,390 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::sync::{OnceLock, RwLock};
+use tauri::{AppHandle, Manager};
+use tokio::process::{Child, Command as AsyncCommand};
+use crate::credential_manager::{PrivateConfigFile, ResolvedCredentials};
+use crate::proxy_manager::apply_ytdlp_proxy;
+
+// Options a profile may add. yt-dlp accepts any unambiguous prefix of a long option, so a
+// list of dangerous ones (--exec, --netrc-cmd, --load-info-json, --cache-dir...) can't be
+// complete; only these exact names are let through. The flag says whether the option takes
+// a value, which is then never read as an option itself
+const ALLOWED_ARGS: &[(&str, bool)] = &[
+    // Formats and post-processing
+    ("--format-sort", true), ("--format-sort-force", false), ("--no-format-sort-force", false),
+    ("--merge-output-format", true), ("--remux-video", true), ("--recode-video", true),
+    ("--audio-format", true), ("--audio-quality", true), ("--prefer-free-formats", false),
+    ("--no-prefer-free-formats", false), ("--check-formats", false), ("--no-check-formats", false),
+    ("--keep-video", false), ("--no-keep-video", false), ("--no-post-overwrites", false),
+    // Subtitles and embedded metadata
+    ("--write-subs", false), ("--no-write-subs", false), ("--write-auto-subs", false),
+    ("--no-write-auto-subs", false), ("--sub-langs", true), ("--sub-format", true),
+    ("--convert-subs", true), ("--embed-subs", false), ("--no-embed-subs", false),
+    ("--embed-metadata", false), ("--no-embed-metadata", false), ("--embed-chapters", false),
+    ("--no-embed-chapters", false), ("--parse-metadata", true), ("--replace-in-metadata", true),
+    ("--sponsorblock-mark", true), ("--sponsorblock-remove", true), ("--no-sponsorblock", false),
+    // Selection
+    ("--match-filters", true), ("--break-match-filters", true), ("--min-filesize", true),
+    ("--max-filesize", true), ("--date", true), ("--datebefore", true), ("--dateafter", true),
+    ("--age-limit", true), ("--live-from-start", false), ("--no-live-from-start", false),
+    ("--wait-for-video", true), ("--no-wait-for-video", false),
+    // Network and download behaviour
+    ("--concurrent-fragments", true), ("--fragment-retries", true), ("--file-access-retries", true),
+    ("--retry-sleep", true), ("--socket-timeout", true), ("--force-ipv4", false),
+    ("--force-ipv6", false), ("--throttled-rate", true), ("--http-chunk-size", true),
+    ("--buffer-size", true), ("--sleep-requests", true), ("--sleep-subtitles", true),
+    ("--impersonate", true), ("--extractor-args", true), ("--add-headers", true),
+    ("--referer", true), ("--legacy-server-connect", false), ("--hls-use-mpegts", false),
+    ("--no-hls-use-mpegts", false), ("--abort-on-unavailable-fragments", false),
+    ("--skip-unavailable-fragments", false), ("--compat-options", true),
+    // File names and timestamps
+    ("--restrict-filenames", false), ("--no-restrict-filenames", false),
+    ("--windows-filenames", false), ("--no-windows-filenames", false), ("--trim-filenames", true),
+    ("--mtime", false), ("--no-mtime", false), ("--no-part", false), ("--part", false),
+];
+
+/// User-editable defaults applied to every yt-dlp run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct YtDlpProfile {
+    /// None keeps yt-dlp's own, which it keeps current with real browsers
+    pub user_agent: Option<String>,
+    pub retries: u32,
+    pub extractor_retries: u32,
+    /// Seconds to wait before each download, randomised up to `max_sleep_interval`
+    pub sleep_interval: Option<f64>,
+    pub max_sleep_interval: Option<f64>,
+    pub verify_tls: bool,
+    /// Download speed cap in yt-dlp's notation, e.g. "500K" or "4.2M"
+    pub rate_limit: Option<String>,
+    /// Fakes an X-Forwarded-For header for geo-restricted videos
+    pub geo_bypass: bool,
+    /// Two-letter country code to appear to come from
+    pub geo_bypass_country: Option<String>,
+    pub extra_args: Vec<String>,
+}
+
+impl Default for YtDlpProfile {
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            retries: 10,
+            extractor_retries: 5,
+            sleep_interval: Some(1.0),
+            max_sleep_interval: Some(5.0),
+            verify_tls: true,
+            rate_limit: None,
+            geo_bypass: true,
+            geo_bypass_country: None,
+            extra_args: Vec::new(),
+        }
+    }
+}
+
+impl YtDlpProfile {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(ref user_agent) = self.user_agent {
+            if user_agent.trim().is_empty() || user_agent.contains(['\r', '\n']) {
+                return Err(anyhow!("Invalid user agent"));
+            }
+        }
+        match (self.sleep_interval, self.max_sleep_interval) {
+            (Some(min), _) if !(0.0..=600.0).contains(&min) => {
+                return Err(anyhow!("Sleep interval must be between 0 and 600 seconds"));
+            }
+            (None, Some(_)) => return Err(anyhow!("A maximum sleep interval needs a minimum")),
+            (Some(min), Some(max)) if max < min || max > 600.0 => {
+                return Err(anyhow!("Maximum sleep interval must be between the minimum and 600 seconds"));
+            }
+            _ => {}
+        }
+        if let Some(ref rate_limit) = self.rate_limit {
+            if !is_valid_rate(rate_limit) {
+                return Err(anyhow!("Invalid rate limit '{}', expected e.g. 500K or 4.2M", rate_limit));
+            }
+        }
+        if let Some(ref country) = self.geo_bypass_country {
+            if country.len() != 2 || !country.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(anyhow!("Geo-bypass country must be a two-letter code"));
+            }
+        }
+        if let Some(refused) = refused_arg(&self.extra_args) {
+            return Err(anyhow!("'{}' can't be used as an extra argument", refused));
+        }
+        Ok(())
+    }
+
+    fn add_args(&self, cmd: &mut AsyncCommand) {
+        if let Some(ref user_agent) = self.user_agent {
+            cmd.arg("--user-agent").arg(user_agent);
+        }
+        cmd.arg("--retries").arg(self.retries.to_string())
+           .arg("--extractor-retries").arg(self.extractor_retries.to_string());
+        if let Some(min) = self.sleep_interval {
+            cmd.arg("--sleep-interval").arg(min.to_string());
+            if let Some(max) = self.max_sleep_interval {
+                cmd.arg("--max-sleep-interval").arg(max.to_string());
+            }
+        }
+        if !self.verify_tls {
+            cmd.arg("--no-check-certificates");
+        }
+        if let Some(ref rate_limit) = self.rate_limit {
+            cmd.arg("--limit-rate").arg(rate_limit);
+        }
+        match (self.geo_bypass, &self.geo_bypass_country) {
+            (false, _) => { cmd.arg("--xff").arg("never"); }
+            (true, Some(country)) => { cmd.arg("--xff").arg(country.to_uppercase()); }
+            (true, None) => {}
+        }
+    }
+}
+
+fn is_valid_rate(rate: &str) -> bool {
+    let number = rate.trim_end_matches(|c: char| matches!(c.to_ascii_uppercase(), 'K' | 'M' | 'G'));
+    // At most one unit suffix
+    rate.len() - number.len() <= 1
+        && !number.is_empty()
+        && number.parse::<f64>().is_ok_and(|value| value > 0.0)
+}
+
+// Checks a profile's extra args against the allowlist, returning the first one refused
+fn refused_arg(args: &[String]) -> Option<&str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, _)) => (name, true),
+            None => (arg.as_str(), false),
+        };
+        match ALLOWED_ARGS.iter().find(|(allowed, _)| *allowed == name) {
+            // The value is missing when nothing follows
+            Some((_, true)) if !inline_value => {
+                if args.next().is_none() {
+                    return Some(arg);
+                }
+            }
+            Some((_, false)) if inline_value => return Some(arg),
+            Some(_) => {}
+            // Short options, prefixes and stray values (which yt-dlp would read as URLs)
+            None => return Some(arg),
+        }
+    }
+    None
+}
+
+static PROFILE: OnceLock<RwLock<YtDlpProfile>> = OnceLock::new();
+
+fn profile_lock() -> &'static RwLock<YtDlpProfile> {
+    PROFILE.get_or_init(|| RwLock::new(YtDlpProfile::default()))
+}
+
+/// The profile in effect, shared by every invocation
+pub fn current_profile() -> YtDlpProfile {
+    profile_lock().read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+}
+
+/// One yt-dlp run: the profile's options, then the caller's, then credentials, proxy and
+/// extra args, with the URL last. Keep it alive until the process exits, since credentials
+/// and proxy passwords may live in temporary config files it owns
+pub struct YtDlpInvocation {
+    cmd: AsyncCommand,
+    extra_args: Vec<String>,
+    config_files: Vec<PrivateConfigFile>,
+}
+
+impl YtDlpInvocation {
+    pub fn new(ytdlp_path: &Path) -> Self {
+        Self::with_profile(ytdlp_path, &current_profile())
+    }
+
+    pub fn with_profile(ytdlp_path: &Path, profile: &YtDlpProfile) -> Self {
+        let mut cmd = AsyncCommand::new(ytdlp_path);
+        profile.add_args(&mut cmd);
+        Self {
+            cmd,
+            extra_args: profile.extra_args.clone(),
+            config_files: Vec::new(),
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.cmd.arg(arg);
+        self
+    }
+
+    pub fn credentials(&mut self, credentials: Option<&ResolvedCredentials>) -> Result<&mut Self> {
+        if let Some(credentials) = credentials {
+            self.config_files.extend(credentials.apply(&mut self.cmd)?);
+        }
+        Ok(self)
+    }
+
+    /// Finishes the argument list with the proxy route for `url`, the profile's extra
+    /// args and the URL itself
+    pub fn url(&mut self, url: &str) -> Result<&mut Self> {
+        self.config_files.extend(apply_ytdlp_proxy(&mut self.cmd, url)?);
+        self.cmd.args(&self.extra_args);
+        // Keeps a URL starting with '-' from being read as an option
+        self.cmd.arg("--").arg(url);
+        Ok(self)
+    }
+
+    /// For stdio and other process settings
+    pub fn command(&mut self) -> &mut AsyncCommand {
+        &mut self.cmd
+    }
+
+    pub fn spawn(&mut self) -> Result<Child> {
+        Ok(self.cmd.spawn()?)
+    }
+
+    pub async fn output(&mut self) -> Result<Output> {
+        Ok(self.cmd.output().await?)
+    }
+}
+
+impl std::fmt::Debug for YtDlpInvocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.cmd.fmt(f)
+    }
+}
+
+/// Persists the profile and keeps the shared copy in sync with it
+pub struct YtDlpProfileManager {
+    profile_path: PathBuf,
+}
+
+impl YtDlpProfileManager {
+    pub fn new(app_handle: &AppHandle) -> Result<Self> {
+        let config_dir = app_handle
+            .path()
+            .app_config_dir()
+            .map_err(|e| anyhow!("Failed to get app config directory: {}", e))?;
+        std::fs::create_dir_all(&config_dir)?;
+        let manager = Self { profile_path: config_dir.join("ytdlp_profile.json") };
+
+        let profile = match std::fs::read_to_string(&manager.profile_path) {
+            Ok(contents) => serde_json::from_str::<YtDlpProfile>(&contents)
+                .map_err(anyhow::Error::from)
+                .and_then(|profile| profile.validate().map(|_| profile))
+                .unwrap_or_else(|e| {
+                    eprintln!("Ignoring invalid yt-dlp profile: {}", e);
+                    YtDlpProfile::default()
+                }),
+            Err(_) => YtDlpProfile::default(),
+        };
+        *profile_lock().write().unwrap_or_else(|poisoned| poisoned.into_inner()) = profile;
+        Ok(manager)
+    }
+
+    pub fn get_profile(&self) -> YtDlpProfile {
+        current_profile()
+    }
+
+    pub fn save_profile(&self, mut profile: YtDlpProfile) -> Result<YtDlpProfile> {
+        profile.user_agent = profile.user_agent.map(|ua| ua.trim().to_string()).filter(|ua| !ua.is_empty());
+        profile.rate_limit = profile.rate_limit.map(|rate| rate.trim().to_string()).filter(|rate| !rate.is_empty());
+        profile.geo_bypass_country = profile.geo_bypass_country.map(|c| c.trim().to_uppercase()).filter(|c| !c.is_empty());
+        profile.extra_args.retain(|arg| !arg.trim().is_empty());
+        profile.validate()?;
+
+        std::fs::write(&self.profile_path, serde_json::to_string_pretty(&profile)?)?;
+        *profile_lock().write().unwrap_or_else(|poisoned| poisoned.into_inner()) = profile.clone();
+        println!("=== YT-DLP PROFILE: Saved {:?} ===", profile);
+        Ok(profile)
+    }
+
+    pub fn reset_profile(&self) -> Result<YtDlpProfile> {
+        self.save_profile(YtDlpProfile::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(invocation: &mut YtDlpInvocation) -> Vec<String> {
+        invocation.command().as_std().get_args()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_default_profile_args() {
+        let mut invocation = YtDlpInvocation::with_profile(Path::new("yt-dlp"), &YtDlpProfile::default());
+        invocation.arg("--dump-json").url("https://www.youtube.com/watch?v=dQw4w9WgXcQ").unwrap();
+        let args = args(&mut invocation);
+
+        assert!(!args.iter().any(|arg| arg.starts_with("--no-check-certificate")));
+        assert!(!args.iter().any(|arg| arg == "--user-agent"));
+        assert!(args.windows(2).any(|pair| pair == ["--extractor-retries", "5"]));
+        assert_eq!(&args[args.len() - 2..], ["--", "https://www.youtube.com/watch?v=dQw4w9WgXcQ"]);
+    }
+
+    #[test]
+    fn test_custom_profile_args() {
+        let profile = YtDlpProfile {
+            user_agent: Some("Grabzilla/1.0".to_string()),
+            verify_tls: false,
+            rate_limit: Some("4.2M".to_string()),
+            sleep_interval: None,
+            max_sleep_interval: None,
+            geo_bypass_country: Some("de".to_string()),
+            extra_args: vec!["--format-sort".to_string(), "res:1080".to_string()],
+            ..Default::default()
+        };
+        profile.validate().unwrap();
+
+        let mut invocation = YtDlpInvocation::with_profile(Path::new("yt-dlp"), &profile);
+        invocation.url("https://vimeo.com/76979871").unwrap();
+        let args = args(&mut invocation);
+
+        assert!(args.windows(2).any(|pair| pair == ["--user-agent", "Grabzilla/1.0"]));
+        assert!(args.windows(2).any(|pair| pair == ["--limit-rate", "4.2M"]));
+        assert!(args.windows(2).any(|pair| pair == ["--xff", "DE"]));
+        assert!(args.contains(&"--no-check-certificates".to_string()));
+        assert!(!args.contains(&"--sleep-interval".to_string()));
+        // Extra args come right before the URL
+        assert_eq!(&args[args.len() - 4..], ["--format-sort", "res:1080", "--", "https://vimeo.com/76979871"]);
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let with_args = |args: &[&str]| YtDlpProfile {
+            extra_args: args.iter().map(|arg| arg.to_string()).collect(),
+            ..Default::default()
+        };
+        assert!(with_args(&["--embed-subs", "--sub-langs", "en.*"]).validate().is_ok());
+        assert!(with_args(&["--exec", "rm -rf ~"]).validate().is_err());
+        assert!(with_args(&["--exec=touch x"]).validate().is_err());
+        assert!(with_args(&["-o", "/etc/passwd"]).validate().is_err());
+        assert!(with_args(&["-o/tmp/x"]).validate().is_err());
+        assert!(with_args(&["--ppa", "ffmpeg:-y"]).validate().is_err());
+        // Unique prefixes of refused options, which yt-dlp would expand
+        assert!(with_args(&["--netrc-c", "sh -c id"]).validate().is_err());
+        assert!(with_args(&["--print-to-f", "id", "/tmp/x"]).validate().is_err());
+        assert!(with_args(&["--load-info", "/tmp/info.json"]).validate().is_err());
+        assert!(with_args(&["--enable-file-urls"]).validate().is_err());
+        assert!(with_args(&["--download-archive", "/tmp/archive.txt"]).validate().is_err());
+        assert!(with_args(&["--cache-dir=/tmp"]).validate().is_err());
+        // Values are never read as options, stray values are refused as URLs would be
+        assert!(with_args(&["--sub-langs", "--exec"]).validate().is_ok());
+        assert!(with_args(&["--sub-langs=en,de", "--embed-chapters"]).validate().is_ok());
+        assert!(with_args(&["--embed-subs", "file:///etc/passwd"]).validate().is_err());
+        assert!(with_args(&["--embed-subs=yes"]).validate().is_err());
+
+        let with_rate = |rate: &str| YtDlpProfile { rate_limit: Some(rate.to_string()), ..Default::default() };
+        assert!(with_rate("500K").validate().is_ok());
+        assert!(with_rate("1.5m").validate().is_ok());
+        assert!(with_rate("fast").validate().is_err());
+        assert!(with_rate("5MM").validate().is_err());
+        assert!(with_rate("0").validate().is_err());
+
+        let sleeps = YtDlpProfile { sleep_interval: Some(5.0), max_sleep_interval: Some(2.0), ..Default::default() };
+        assert!(sleeps.validate().is_err());
+    }
+}
@@ -44,6 +44,15 @@ const formatInfo = {
     size: 'Small',
     compatibility: 'Universal',
   },
+  [ConversionFormat.HEVC]: {
+    name: 'H.265 / HEVC',
+    description: 'Modern delivery format - smaller files at the same quality, uses GPU encoding when available',
+    useCase: 'Archiving, 4K/HDR delivery, modern devices',
+    extension: 'mp4',
+    quality: 'High',
+    size: 'Very Small',
+    compatibility: 'Modern',
+  },
   [ConversionFormat.DNxHR]: {
     name: 'DNxHR SQ',
     description: 'Professional intermediate codec for Avid editing workflows',
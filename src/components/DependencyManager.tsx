@@ -37,6 +37,7 @@ interface DependencyInfo {
 interface DependencyStatus {
   yt_dlp: DependencyInfo;
   ffmpeg: DependencyInfo;
+  ffprobe: DependencyInfo;
   all_installed: boolean;
 }
 
@@ -26,6 +26,9 @@ import {
   VideoLibrary as VimeoIcon,
   SportsEsports as TwitchIcon,
   MusicVideo as TikTokIcon,
+  Reddit as RedditIcon,
+  Audiotrack as AudioIcon,
+  LiveTv as LiveIcon,
   Link as GenericIcon,
   GetApp as DownloadingIcon,
   Transform as ConvertingIcon,
@@ -36,7 +39,8 @@ import {
   CloudDownload as DownloadOnlyIcon,
   DoubleArrow as DownloadConvertIcon,
 } from '@mui/icons-material';
-import { DownloadItem, DownloadStatus, Platform } from '../types';
+import { DownloadItem, DownloadStatus, KnownPlatform } from '../types';
+import { TauriAPI } from '../services/tauri-api';
 
 // Utility function to format bytes in human readable format
 const formatBytes = (bytes: number): string => {
@@ -64,15 +68,25 @@ interface QueueItemProps {
   onConvertOnly: (id: string) => void;
 }
 
-const platformIcons = {
-  [Platform.YouTube]: YouTubeIcon,
-  [Platform.Vimeo]: VimeoIcon,
-  [Platform.Twitch]: TwitchIcon,
-  [Platform.TikTok]: TikTokIcon,
-  [Platform.Instagram]: GenericIcon,
-  [Platform.Twitter]: GenericIcon,
-  [Platform.Facebook]: GenericIcon,
-  [Platform.Generic]: GenericIcon,
+// Keyed by platform id; user-defined platforms fall back to GenericIcon
+const platformIcons: Record<string, React.ComponentType<any>> = {
+  [KnownPlatform.YouTube]: YouTubeIcon,
+  [KnownPlatform.Vimeo]: VimeoIcon,
+  [KnownPlatform.Twitch]: TwitchIcon,
+  [KnownPlatform.TikTok]: TikTokIcon,
+  [KnownPlatform.Instagram]: GenericIcon,
+  [KnownPlatform.Twitter]: GenericIcon,
+  [KnownPlatform.Facebook]: GenericIcon,
+  [KnownPlatform.Dailymotion]: VimeoIcon,
+  [KnownPlatform.Reddit]: RedditIcon,
+  [KnownPlatform.Bilibili]: VimeoIcon,
+  [KnownPlatform.SoundCloud]: AudioIcon,
+  [KnownPlatform.Bandcamp]: AudioIcon,
+  [KnownPlatform.Rumble]: VimeoIcon,
+  [KnownPlatform.Kick]: LiveIcon,
+  [KnownPlatform.Bluesky]: GenericIcon,
+  [KnownPlatform.Threads]: GenericIcon,
+  [KnownPlatform.Generic]: GenericIcon,
 };
 
 const statusIcons = {
@@ -146,7 +160,7 @@ export function QueueItem({
     }
   };
 
-  const PlatformIcon = platformIcons[item.platform];
+  const PlatformIcon = platformIcons[item.platform] || GenericIcon;
   const StatusIcon = statusIcons[item.status];
 
   const canStart = item.status === DownloadStatus.Queued || item.status === DownloadStatus.Paused;
@@ -191,7 +205,7 @@ export function QueueItem({
       {/* Thumbnail */}
       <Box sx={{ position: 'relative', mr: 1.5 }}>
         <Avatar
-          src={item.metadataLoading ? undefined : item.thumbnail}
+          src={item.metadataLoading ? undefined : TauriAPI.getThumbnailSrc(item.thumbnail)}
           sx={{
             width: 60,
             height: 42,
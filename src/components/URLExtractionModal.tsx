@@ -30,7 +30,7 @@ import {
   Delete as DeleteIcon,
   PlaylistPlay as PlaylistIcon,
 } from '@mui/icons-material';
-import { ExtractedUrl, Platform, URLExtractionResult } from '../types';
+import { ExtractedUrl, Platform, KnownPlatform, URLExtractionResult, UrlKind } from '../types';
 
 interface URLExtractionModalProps {
   open: boolean;
@@ -39,26 +39,48 @@ interface URLExtractionModalProps {
   onConfirm: (selectedUrls: ExtractedUrl[]) => void;
 }
 
-const platformIcons: Record<Platform, React.ComponentType<any>> = {
-  [Platform.YouTube]: YouTubeIcon,
-  [Platform.Vimeo]: VimeoIcon,
-  [Platform.Twitch]: TwitchIcon,
-  [Platform.TikTok]: TikTokIcon,
-  [Platform.Instagram]: GenericIcon,
-  [Platform.Twitter]: GenericIcon,
-  [Platform.Facebook]: GenericIcon,
-  [Platform.Generic]: GenericIcon,
+const platformIcons: Record<string, React.ComponentType<any>> = {
+  [KnownPlatform.YouTube]: YouTubeIcon,
+  [KnownPlatform.Vimeo]: VimeoIcon,
+  [KnownPlatform.Twitch]: TwitchIcon,
+  [KnownPlatform.TikTok]: TikTokIcon,
+  [KnownPlatform.Instagram]: GenericIcon,
+  [KnownPlatform.Twitter]: GenericIcon,
+  [KnownPlatform.Facebook]: GenericIcon,
+  [KnownPlatform.Generic]: GenericIcon,
 };
 
-const platformColors: Record<Platform, string> = {
-  [Platform.YouTube]: '#FF0000',
-  [Platform.Vimeo]: '#1AB7EA',
-  [Platform.Twitch]: '#9146FF',
-  [Platform.TikTok]: '#000000',
-  [Platform.Instagram]: '#E4405F',
-  [Platform.Twitter]: '#1DA1F2',
-  [Platform.Facebook]: '#1877F2',
-  [Platform.Generic]: '#757575',
+const kindLabels: Record<UrlKind, string> = {
+  video: 'Video',
+  short: 'Short',
+  live: 'Live',
+  premiere: 'Premiere',
+  playlist: 'Playlist',
+  channel: 'Channel',
+  channel_tab: 'Channel tab',
+  album: 'Album',
+  clip: 'Clip',
+  collection: 'Collection',
+};
+
+const platformColors: Record<string, string> = {
+  [KnownPlatform.YouTube]: '#FF0000',
+  [KnownPlatform.Vimeo]: '#1AB7EA',
+  [KnownPlatform.Twitch]: '#9146FF',
+  [KnownPlatform.TikTok]: '#000000',
+  [KnownPlatform.Instagram]: '#E4405F',
+  [KnownPlatform.Twitter]: '#1DA1F2',
+  [KnownPlatform.Facebook]: '#1877F2',
+  [KnownPlatform.Dailymotion]: '#0D0D0D',
+  [KnownPlatform.Reddit]: '#FF4500',
+  [KnownPlatform.Bilibili]: '#00A1D6',
+  [KnownPlatform.SoundCloud]: '#FF5500',
+  [KnownPlatform.Bandcamp]: '#1DA0C3',
+  [KnownPlatform.Rumble]: '#85C742',
+  [KnownPlatform.Kick]: '#53FC18',
+  [KnownPlatform.Bluesky]: '#1185FE',
+  [KnownPlatform.Threads]: '#000000',
+  [KnownPlatform.Generic]: '#757575',
 };
 
 export function URLExtractionModal({
@@ -286,7 +308,7 @@ export function URLExtractionModal({
                         {extractedUrl.is_playlist && (
                           <Chip
                             icon={<PlaylistIcon sx={{ fontSize: '12px !important' }} />}
-                            label={extractedUrl.playlist_count ? `${extractedUrl.playlist_count} videos` : 'Playlist'}
+                            label={extractedUrl.playlist_count ? `${extractedUrl.playlist_count} videos` : kindLabels[extractedUrl.kind] ?? 'Playlist'}
                             size="small"
                             variant="outlined"
                             color="secondary"
@@ -296,6 +318,18 @@ export function URLExtractionModal({
                             }}
                           />
                         )}
+                        {!extractedUrl.is_playlist && extractedUrl.kind && extractedUrl.kind !== 'video' && (
+                          <Chip
+                            label={kindLabels[extractedUrl.kind]}
+                            size="small"
+                            variant="outlined"
+                            color={extractedUrl.kind === 'live' ? 'error' : 'default'}
+                            sx={{ 
+                              fontSize: '0.7rem',
+                              height: 20,
+                            }}
+                          />
+                        )}
                       </Box>
                     </Box>
                   }
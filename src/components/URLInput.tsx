@@ -16,9 +16,13 @@ import {
   Clear as ClearIcon,
   Link as LinkIcon,
   FileUpload as FileUploadIcon,
+  ManageSearch as FindVideosIcon,
 } from '@mui/icons-material';
-import { ExtractedUrl, URLExtractionResult } from '../types';
+import { ExtractedUrl, MULTI_VIDEO_KINDS, URLExtractionResult, UrlKind } from '../types';
 import { TauriAPI, isTauriEnvironment } from '../services/tauri-api';
+import { getCurrentWebview } from '@tauri-apps/api/webview';
+
+const LINK_FILE_TYPES = '.txt,.url,.webloc,.html,.htm,.csv,.docx,.pdf';
 
 interface URLInputProps {
   onURLsExtracted: (result: URLExtractionResult) => void;
@@ -33,55 +37,65 @@ export function URLInput({ onURLsExtracted, disabled = false }: URLInputProps) {
   const textareaRef = useRef<HTMLTextAreaElement>(null);
   const fileInputRef = useRef<HTMLInputElement>(null);
 
+  // Playlists in a backend result are expanded into their videos, which stream straight to the queue
+  const expandPlaylists = useCallback(async (result: URLExtractionResult): Promise<URLExtractionResult> => {
+    // Check if any of the URLs are playlists and expand them
+    const expandedUrls: ExtractedUrl[] = [];
+    let totalFound = result.total_found;
+    let duplicatesRemoved = result.duplicates_removed;
+    let streamedCount = 0;
+    
+    for (const url of result.urls) {
+      if (url.is_playlist) {
+        console.log('=== URLInput: Found playlist, extracting individual videos ===');
+        try {
+          // Entries go straight to the queue as they stream in, so a large channel shows up progressively
+          const count = await TauriAPI.expandPlaylist(url.url, undefined, (entries) => {
+            const batch: ExtractedUrl[] = entries.map((entry) => ({
+              url: entry.url,
+              title: entry.title || `Video ${entry.index}${entry.id ? ` (${entry.id})` : ''}`,
+              platform: url.platform, // Same platform as playlist
+              is_valid: true,
+              original_text: `${url.original_text} (Video ${entry.index})`,
+              is_playlist: false,
+              kind: 'video',
+              playlist_count: undefined,
+            }));
+            onURLsExtracted({
+              urls: batch,
+              total_found: batch.length,
+              valid_urls: batch.length,
+              duplicates_removed: 0,
+            });
+          });
+          console.log(`=== URLInput: Expanded playlist into ${count} videos ===`);
+          streamedCount += count;
+          totalFound += count - 1; // Adjust count (replace 1 playlist with N videos)
+        } catch (error) {
+          console.warn('Failed to extract playlist videos, keeping playlist URL:', error);
+          expandedUrls.push(url); // Keep original playlist URL as fallback
+        }
+      } else {
+        expandedUrls.push(url);
+      }
+    }
+    
+    return {
+      urls: expandedUrls,
+      total_found: totalFound,
+      // Streamed playlist entries were already handed over but still count as found
+      valid_urls: expandedUrls.length + streamedCount,
+      duplicates_removed: duplicatesRemoved,
+    };
+  }, [onURLsExtracted]);
+
   // URL extraction with Rust backend fallback to JavaScript
   const extractURLs = useCallback(async (text: string): Promise<URLExtractionResult> => {
     // Try Rust backend first if available
     if (isTauriEnvironment()) {
       try {
         const result = await TauriAPI.extractUrlsFromText(text);
-        
-        // Check if any of the URLs are playlists and expand them
-        const expandedUrls: ExtractedUrl[] = [];
-        let totalFound = result.total_found;
-        let duplicatesRemoved = result.duplicates_removed;
-        
-        for (const url of result.urls) {
-          if (url.is_playlist) {
-            console.log('=== URLInput: Found playlist, extracting individual videos ===');
-            try {
-              const videoUrls = await TauriAPI.extractPlaylistVideos(url.url);
-              console.log(`=== URLInput: Extracted ${videoUrls.length} videos from playlist ===`);
-              
-              // Convert each video URL to an ExtractedUrl
-              for (let i = 0; i < videoUrls.length; i++) {
-                const videoUrl = videoUrls[i];
-                const videoId = videoUrl.match(/(?:v=|\/watch\?v=|youtu\.be\/)([a-zA-Z0-9_-]{11})/)?.[1];
-                expandedUrls.push({
-                  url: videoUrl,
-                  title: `Video ${i + 1}${videoId ? ` (${videoId})` : ''}`, // Add a proper title
-                  platform: url.platform, // Same platform as playlist
-                  is_valid: true,
-                  original_text: `${url.original_text} (Video ${i + 1})`,
-                  is_playlist: false,
-                  playlist_count: undefined,
-                });
-              }
-              totalFound += videoUrls.length - 1; // Adjust count (replace 1 playlist with N videos)
-            } catch (error) {
-              console.warn('Failed to extract playlist videos, keeping playlist URL:', error);
-              expandedUrls.push(url); // Keep original playlist URL as fallback
-            }
-          } else {
-            expandedUrls.push(url);
-          }
-        }
-        
-        return {
-          urls: expandedUrls,
-          total_found: totalFound,
-          valid_urls: expandedUrls.length,
-          duplicates_removed: duplicatesRemoved,
-        };
+        return await expandPlaylists(result);
       } catch (error) {
         console.warn('Rust backend failed, falling back to JavaScript:', error);
         // Fall through to JavaScript implementation
@@ -128,18 +142,33 @@ export function URLInput({ onURLsExtracted, disabled = false }: URLInputProps) {
         platform: platform as any,
         is_valid: true, // Will be validated by backend
         original_text: url,
-        is_playlist: cleanUrl.includes('list=') || cleanUrl.includes('playlist') || cleanUrl.includes('/channel/'),
+        is_playlist: false,
+        kind: 'video',
         playlist_count: undefined,
       });
     }
 
+    // Kinds come from the backend's platform registry, the same rules full extraction uses.
+    // Without the backend every URL is treated as a single video
+    if (isTauriEnvironment() && extractedUrls.length > 0) {
+      try {
+        const kinds: UrlKind[] = await TauriAPI.classifyUrls(extractedUrls.map(entry => entry.url));
+        extractedUrls.forEach((entry, index) => {
+          entry.kind = kinds[index] ?? 'video';
+          entry.is_playlist = MULTI_VIDEO_KINDS.includes(entry.kind);
+        });
+      } catch (error) {
+        console.warn('URL classification failed, treating URLs as videos:', error);
+      }
+    }
+
     return {
       urls: extractedUrls,
       total_found: matches.length,
       valid_urls: extractedUrls.length,
       duplicates_removed: duplicatesRemoved,
     };
-  }, []);
+  }, [expandPlaylists]);
 
   const handleExtractURLs = useCallback(async () => {
     if (!inputValue.trim()) {
@@ -198,10 +227,40 @@ export function URLInput({ onURLsExtracted, disabled = false }: URLInputProps) {
     }
   }, [handleExtractURLs]);
 
-  const handleFileUpload = useCallback((event: React.ChangeEvent<HTMLInputElement>) => {
+  // Links the backend read from a file or page go through the same path as extracted text
+  const importFrom = useCallback(async (name: string, load: () => Promise<URLExtractionResult>) => {
+    console.log(`=== URLInput: Importing URLs from ${name} ===`);
+    setIsExtracting(true);
+    setError(null);
+
+    try {
+      const result = await expandPlaylists(await load());
+      setLastExtraction(result);
+      onURLsExtracted(result);
+
+      if (result.valid_urls === 0) {
+        setError(`No valid URLs found in ${name}`);
+      }
+    } catch (err) {
+      setError(`Failed to import ${name}: ${err}`);
+      console.error('URL import error:', err);
+    } finally {
+      setIsExtracting(false);
+    }
+  }, [expandPlaylists, onURLsExtracted]);
+
+  const handleFileUpload = useCallback(async (event: React.ChangeEvent<HTMLInputElement>) => {
     const file = event.target.files?.[0];
+    // Allow picking the same file again
+    event.target.value = '';
     if (!file) return;
 
+    if (isTauriEnvironment()) {
+      const contents = new Uint8Array(await file.arrayBuffer());
+      await importFrom(file.name, () => TauriAPI.extractUrlsFromFileContents(file.name, contents));
+      return;
+    }
+
     if (!file.name.match(/\.(txt|url)$/i)) {
       setError('Please select a .txt or .url file');
       return;
@@ -214,7 +273,36 @@ export function URLInput({ onURLsExtracted, disabled = false }: URLInputProps) {
     };
     reader.onerror = () => setError('Failed to read file');
     reader.readAsText(file);
-  }, []);
+  }, [importFrom]);
+
+  // Files dropped on the window are read by the backend from their path
+  useEffect(() => {
+    if (!isTauriEnvironment() || disabled) return;
+
+    const unlisten = getCurrentWebview().onDragDropEvent(async (event) => {
+      if (event.payload.type !== 'drop') return;
+      for (const path of event.payload.paths) {
+        const name = path.split(/[\\/]/).pop() || path;
+        await importFrom(name, () => TauriAPI.extractUrlsFromFile(path));
+      }
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, [disabled, importFrom]);
+
+  // "Find videos on this page": each link in the input is fetched and searched for embedded videos
+  const handleFindVideos = useCallback(async () => {
+    const pages = inputValue.match(/https?:\/\/[^\s<>"]+/gi) || [];
+    if (pages.length === 0) {
+      setError('Please enter the address of a page to search for videos');
+      return;
+    }
+
+    for (const page of pages) {
+      await importFrom(page, () => TauriAPI.findVideosOnPage(page));
+    }
+  }, [inputValue, importFrom]);
 
   const handleClear = useCallback(() => {
     setInputValue('');
@@ -289,7 +377,20 @@ export function URLInput({ onURLsExtracted, disabled = false }: URLInputProps) {
               </IconButton>
             </Tooltip>
 
-            <Tooltip title="Upload text file">
+            {isTauriEnvironment() && (
+              <Tooltip title="Find videos on the pages entered">
+                <IconButton
+                  onClick={handleFindVideos}
+                  disabled={disabled || isExtracting || !inputValue.trim()}
+                  sx={{ color: 'primary.main' }}
+                  size="small"
+                >
+                  <FindVideosIcon />
+                </IconButton>
+              </Tooltip>
+            )}
+
+            <Tooltip title="Import links from a file">
               <IconButton
                 onClick={() => fileInputRef.current?.click()}
                 disabled={disabled || isExtracting}
@@ -354,7 +455,7 @@ export function URLInput({ onURLsExtracted, disabled = false }: URLInputProps) {
         <input
           ref={fileInputRef}
           type="file"
-          accept=".txt,.url"
+          accept={LINK_FILE_TYPES}
           style={{ display: 'none' }}
           onChange={handleFileUpload}
         />
@@ -289,6 +289,23 @@ const determineActualQuality = (metadata: VideoMetadata, requestedQuality: strin
   return fallbackHeight ? `${fallbackHeight}p` : requestedQuality;
 };
 
+// Display string for metadata duration: "1:02:03" for videos, "12 videos" for playlists
+const formatMetadataDuration = (metadata?: VideoMetadata): string | undefined => {
+  if (metadata?.playlist_count) {
+    return `${metadata.playlist_count} videos`;
+  }
+  if (metadata?.duration === undefined || metadata.duration === null) {
+    return undefined;
+  }
+  const totalSeconds = Math.round(metadata.duration);
+  const hours = Math.floor(totalSeconds / 3600);
+  const minutes = Math.floor((totalSeconds % 3600) / 60);
+  const seconds = String(totalSeconds % 60).padStart(2, '0');
+  return hours > 0
+    ? `${hours}:${String(minutes).padStart(2, '0')}:${seconds}`
+    : `${minutes}:${seconds}`;
+};
+
 // Create download item from extracted URL
 const createDownloadItem = (url: ExtractedUrl, index: number, defaultQuality: string, metadata?: VideoMetadata, shouldAutoConvert?: boolean): DownloadItem => {
   const normalizedQuality = normalizeQuality(defaultQuality);
@@ -298,7 +315,7 @@ const createDownloadItem = (url: ExtractedUrl, index: number, defaultQuality: st
     id: `download-${Date.now()}-${index}-${Math.random().toString(36).substr(2, 9)}`, // More unique ID for same URL different settings
     url: url.url,
     title: metadata?.title || url.title || (url.is_playlist ? `Playlist ${index + 1}` : `Video ${index + 1}`),
-    duration: metadata?.duration || (url.is_playlist && url.playlist_count ? `${url.playlist_count} videos` : '0:00'),
+    duration: formatMetadataDuration(metadata) || (url.is_playlist && url.playlist_count ? `${url.playlist_count} videos` : '0:00'),
     thumbnail: metadata?.thumbnail || `https://via.placeholder.com/120x68/333/fff?text=${url.platform.toUpperCase()}${url.is_playlist ? '+PL' : ''}`,
     platform: url.platform,
     status: DownloadStatus.Queued,
@@ -633,165 +650,124 @@ export function useDownloads() {
       return;
     }
     
-    // Process metadata with concurrency control for better performance
-    // Dynamically detect CPU cores and scale for I/O-bound tasks
-    const detectedCores = navigator.hardwareConcurrency || 4; // Fallback to 4 if detection fails
-    const ioBoundMultiplier = 2; // I/O-bound tasks can benefit from more concurrency than CPU cores
-    const optimalConcurrency = detectedCores * ioBoundMultiplier;
-    
-    // Apply reasonable bounds: min 3 (proven working), max 12 (prevent overwhelming backend)
-    const CONCURRENT_METADATA_FETCHES = Math.max(3, Math.min(12, optimalConcurrency));
+    // Metadata is loaded lazily once the items are in the queue. The backend bounds the
+    // concurrency, spaces out requests per host and streams each result back as it finishes
+    console.log(`=== FRONTEND: Prefetching metadata for ${processedUrls.length} videos ===`);
     
-    console.log(`=== FRONTEND: CPU Detection ===`);
-    console.log(`=== FRONTEND: Detected ${detectedCores} CPU cores ===`);
-    console.log(`=== FRONTEND: Optimal concurrency: ${detectedCores} cores × ${ioBoundMultiplier} = ${optimalConcurrency} ===`);
-    console.log(`=== FRONTEND: Using ${CONCURRENT_METADATA_FETCHES} concurrent metadata fetches (bounded 3-12) ===`);
-    console.log(`=== FRONTEND: Processing ${urls.length} videos with max ${CONCURRENT_METADATA_FETCHES} concurrent fetches ===`);
-    
-    // Helper function to process a single video's metadata
-    const processVideoMetadata = async (url: ExtractedUrl, index: number) => {
-      const downloadId = newDownloads[index].id;
-      console.log(`=== FRONTEND: Processing metadata for URL ${index + 1}/${urls.length}: ${url.url} ===`);
-      
+    // Helper function to fall back when the full metadata fetch failed
+    const handleMetadataFailure = async (url: ExtractedUrl, downloadId: string, errorMessage: string) => {
+      console.error(`=== FRONTEND: Failed to get metadata for ${url.url}:`, errorMessage);
       let metadata: VideoMetadata | undefined;
       
-      try {
-        console.log('=== FRONTEND: Calling getVideoMetadata ===');
-        metadata = await TauriAPI.getVideoMetadata(url.url);
-        console.log('=== FRONTEND: Metadata received:', metadata);
-        
-        // Determine actual quality/resolution from metadata
-        const currentDownload = newDownloads[index];
-        const actualQuality = determineActualQuality(metadata!, currentDownload.quality);
-        
-        // Update the specific download item with fetched metadata
-        setDownloads(prev => prev.map(download => 
-          download.id === downloadId 
-            ? {
-                ...download,
-                title: metadata!.title,
-                duration: metadata!.duration || '0:00',
-                thumbnail: metadata!.thumbnail || download.thumbnail,
-                quality: actualQuality,
-                metadataLoading: false,
-              }
-            : download
-        ));
+      // Check if it's a bot detection error
+      if (errorMessage.includes('Sign in to confirm you\'re not a bot') || 
+          errorMessage.includes('bot detection') ||
+          errorMessage.includes('cookies')) {
+        console.warn(`=== FRONTEND: Bot detection for ${url.url}, trying basic metadata ===`);
         
-      } catch (error) {
-        console.error(`=== FRONTEND: Failed to get metadata for ${url.url}:`, error);
-        // Check if it's a bot detection error
-        const errorMessage = error instanceof Error ? error.message : String(error);
-        if (errorMessage.includes('Sign in to confirm you\'re not a bot') || 
-            errorMessage.includes('bot detection') ||
-            errorMessage.includes('cookies')) {
-          console.warn(`=== FRONTEND: Bot detection for ${url.url}, trying basic metadata ===`);
+        // Try the lightweight metadata method first
+        try {
+          console.log('=== FRONTEND: Attempting basic metadata fetch ===');
+          metadata = await TauriAPI.getBasicVideoMetadata(url.url);
+          console.log('=== FRONTEND: Basic metadata successful:', metadata);
           
-          // Try the lightweight metadata method first
-          try {
-            console.log('=== FRONTEND: Attempting basic metadata fetch ===');
-            metadata = await TauriAPI.getBasicVideoMetadata(url.url);
-            console.log('=== FRONTEND: Basic metadata successful:', metadata);
-            
-            // Update with basic metadata
-            setDownloads(prev => prev.map(download => 
-              download.id === downloadId 
-                ? {
-                    ...download,
-                    title: metadata!.title,
-                    duration: metadata!.duration || '0:00',
-                    thumbnail: metadata!.thumbnail || download.thumbnail,
-                    metadataLoading: false,
-                  }
-                : download
-            ));
-            
-          } catch (basicError) {
-            console.warn('=== FRONTEND: Basic metadata also failed, using manual fallback ===');
-            // Create basic metadata from URL for bot-detected videos
-            const hostname = new URL(url.url).hostname;
-            const videoId = url.url.match(/(?:v=|\/watch\?v=|youtu\.be\/)([a-zA-Z0-9_-]{11})/)?.[1];
-            const fallbackMetadata = {
-              title: `Video from ${hostname}`,
-              duration: '0:00',
-              thumbnail: videoId ? `https://img.youtube.com/vi/${videoId}/hqdefault.jpg` : `https://via.placeholder.com/120x68/333/fff?text=${url.platform.toUpperCase()}`,
-            };
-            
-            // Update with fallback metadata
-            setDownloads(prev => prev.map(download => 
-              download.id === downloadId 
-                ? {
-                    ...download,
-                    title: fallbackMetadata.title,
-                    duration: fallbackMetadata.duration,
-                    thumbnail: fallbackMetadata.thumbnail,
-                    metadataLoading: false,
-                  }
-                : download
-            ));
-          }
-        } else {
-          // For other errors, still provide a fallback
+          // Update with basic metadata
+          setDownloads(prev => prev.map(download => 
+            download.id === downloadId 
+              ? {
+                  ...download,
+                  title: metadata!.title,
+                  duration: formatMetadataDuration(metadata!) || '0:00',
+                  thumbnail: metadata!.thumbnail || download.thumbnail,
+                  metadataLoading: false,
+                }
+              : download
+          ));
+          
+        } catch (basicError) {
+          console.warn('=== FRONTEND: Basic metadata also failed, using manual fallback ===');
+          // Create basic metadata from URL for bot-detected videos
           const hostname = new URL(url.url).hostname;
+          const videoId = url.url.match(/(?:v=|\/watch\?v=|youtu\.be\/)([a-zA-Z0-9_-]{11})/)?.[1];
+          const fallbackMetadata = {
+            title: `Video from ${hostname}`,
+            duration: '0:00',
+            thumbnail: videoId ? `https://img.youtube.com/vi/${videoId}/hqdefault.jpg` : `https://via.placeholder.com/120x68/333/fff?text=${url.platform.toUpperCase()}`,
+          };
+          
+          // Update with fallback metadata
           setDownloads(prev => prev.map(download => 
             download.id === downloadId 
               ? {
                   ...download,
-                  title: `Video from ${hostname}`,
-                  duration: '0:00',
+                  title: fallbackMetadata.title,
+                  duration: fallbackMetadata.duration,
+                  thumbnail: fallbackMetadata.thumbnail,
                   metadataLoading: false,
                 }
               : download
           ));
         }
+      } else {
+        // For other errors, still provide a fallback
+        const hostname = new URL(url.url).hostname;
+        setDownloads(prev => prev.map(download => 
+          download.id === downloadId 
+            ? {
+                ...download,
+                title: `Video from ${hostname}`,
+                duration: '0:00',
+                metadataLoading: false,
+              }
+            : download
+        ));
       }
     };
     
-    // Process videos in batches with concurrency control
-    const processInBatches = async () => {
-      const totalVideos = urls.length;
-      const totalBatches = Math.ceil(totalVideos / CONCURRENT_METADATA_FETCHES);
-      let processedCount = 0;
-      const startTime = Date.now();
+    const startTime = Date.now();
+    const handled = new Set<number>();
+    TauriAPI.prefetchMetadata(processedUrls.map(url => url.url), (result) => {
+      const currentDownload = newDownloads[result.index];
+      const url = processedUrls[result.index];
+      if (!currentDownload || !url) return;
+      handled.add(result.index);
       
-      console.log(`=== FRONTEND: Starting concurrent metadata processing ===`);
-      console.log(`=== FRONTEND: ${totalVideos} videos, ${totalBatches} batches, max ${CONCURRENT_METADATA_FETCHES} concurrent ===`);
-      
-      // Create batches of URLs to process concurrently
-      for (let i = 0; i < totalVideos; i += CONCURRENT_METADATA_FETCHES) {
-        const batch = urls.slice(i, i + CONCURRENT_METADATA_FETCHES);
-        const batchNumber = Math.floor(i / CONCURRENT_METADATA_FETCHES) + 1;
-        const batchStart = Date.now();
-        
-        const batchPromises = batch.map((url, batchIndex) => 
-          processVideoMetadata(url, i + batchIndex)
-        );
-        
-        console.log(`=== FRONTEND: Processing batch ${batchNumber}/${totalBatches} (videos ${i + 1}-${Math.min(i + CONCURRENT_METADATA_FETCHES, totalVideos)}) ===`);
-        
-        // Wait for all videos in this batch to complete before starting the next batch
-        await Promise.all(batchPromises);
-        
-        processedCount += batch.length;
-        const batchTime = Date.now() - batchStart;
-        console.log(`=== FRONTEND: Batch ${batchNumber} completed in ${batchTime}ms - Progress: ${processedCount}/${totalVideos} ===`);
+      if (!result.metadata) {
+        handleMetadataFailure(url, currentDownload.id, result.error || 'Unknown error');
+        return;
       }
       
-      const totalTime = Date.now() - startTime;
-      const averageTimePerVideo = totalTime / totalVideos;
-      const theoreticalSequentialTime = averageTimePerVideo * totalVideos;
-      const speedupFactor = (theoreticalSequentialTime / totalTime).toFixed(1);
+      // Determine actual quality/resolution from metadata
+      const metadata = result.metadata;
+      const actualQuality = determineActualQuality(metadata, currentDownload.quality);
       
-      console.log(`=== FRONTEND: All ${totalVideos} videos processed in ${totalTime}ms ===`);
-      console.log(`=== FRONTEND: Average time per video: ${averageTimePerVideo.toFixed(1)}ms ===`);
-      console.log(`=== FRONTEND: Performance: ${speedupFactor}x speedup with ${CONCURRENT_METADATA_FETCHES}-way concurrency (${detectedCores} CPU cores detected) ===`);
-      console.log(`=== FRONTEND: System utilization: ${((CONCURRENT_METADATA_FETCHES / detectedCores) * 100).toFixed(1)}% of available cores ===`);
-    };
-    
-    // Start processing batches (don't await, let it run in background)
-    processInBatches().catch(error => {
-      console.error('=== FRONTEND: Error processing video batches:', error);
-    });
+      // Update the specific download item with fetched metadata
+      setDownloads(prev => prev.map(download => 
+        download.id === currentDownload.id 
+          ? {
+              ...download,
+              title: metadata.title,
+              duration: formatMetadataDuration(metadata) || '0:00',
+              thumbnail: metadata.thumbnail || download.thumbnail,
+              quality: actualQuality,
+              metadataLoading: false,
+            }
+          : download
+      ));
+    })
+      .then(count => {
+        console.log(`=== FRONTEND: Metadata for ${count}/${processedUrls.length} videos loaded in ${Date.now() - startTime}ms ===`);
+      })
+      .catch(error => {
+        // The whole batch failed (e.g. yt-dlp missing), give the remaining items their fallback
+        console.error('=== FRONTEND: Error prefetching metadata:', error);
+        const errorMessage = error instanceof Error ? error.message : String(error);
+        processedUrls.forEach((url, index) => {
+          if (!handled.has(index)) {
+            handleMetadataFailure(url, newDownloads[index].id, errorMessage);
+          }
+        });
+      });
     
   }, [downloads.length, settings]);
 
@@ -1,6 +1,6 @@
-import { invoke } from '@tauri-apps/api/core';
+import { invoke, convertFileSrc } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
-import { URLExtractionResult, Platform, VideoMetadata, ConversionFormat, DownloadStatus } from '../types';
+import { URLExtractionResult, PlatformDescriptor, VideoMetadata, ConversionFormat, DownloadStatus, ClipOptions, Chapter, SidecarFormat, SidecarTarget, PlaylistOptions, PlaylistEntry, PlaylistPreview, PrefetchResult, CredentialProfile, ProxySettings, ProxyServer, ProxyTestResult, YtDlpProfile, BookmarkFolder, UrlKind } from '../types';
 
 // Progress update interface
 interface DownloadProgress {
@@ -29,12 +29,72 @@ export class TauriAPI {
     }
   }
 
+  /**
+   * Extract URLs from a file on disk (.txt, .url, .webloc, .html, .csv, .docx, .pdf)
+   */
+  static async extractUrlsFromFile(path: string): Promise<URLExtractionResult> {
+    try {
+      return await invoke<URLExtractionResult>('extract_urls_from_file', { path });
+    } catch (error) {
+      console.error('Failed to extract URLs from file:', error);
+      throw new Error(`URL extraction failed: ${error}`);
+    }
+  }
+
+  /**
+   * Extract URLs from the contents of a file picked in the browser
+   */
+  static async extractUrlsFromFileContents(fileName: string, contents: Uint8Array): Promise<URLExtractionResult> {
+    try {
+      return await invoke<URLExtractionResult>('extract_urls_from_file_contents', { fileName, contents: Array.from(contents) });
+    } catch (error) {
+      console.error('Failed to extract URLs from file:', error);
+      throw new Error(`URL extraction failed: ${error}`);
+    }
+  }
+
+  /**
+   * Fetch a web page and find the videos embedded in it (player iframes, <video> tags, og:video, HLS/DASH links)
+   */
+  static async findVideosOnPage(url: string): Promise<URLExtractionResult> {
+    try {
+      return await invoke<URLExtractionResult>('find_videos_on_page', { url });
+    } catch (error) {
+      console.error('Failed to find videos on page:', error);
+      throw new Error(`Video discovery failed: ${error}`);
+    }
+  }
+
+  /**
+   * List the bookmark folders of a Firefox or Chromium profile (directory, places.sqlite or Bookmarks file)
+   */
+  static async listBookmarkFolders(profilePath: string): Promise<BookmarkFolder[]> {
+    try {
+      return await invoke<BookmarkFolder[]>('list_bookmark_folders', { profilePath });
+    } catch (error) {
+      console.error('Failed to read bookmarks:', error);
+      throw new Error(`Bookmark import failed: ${error}`);
+    }
+  }
+
+  /**
+   * Import the bookmarks in a folder and its subfolders, or the whole profile; titles are kept
+   */
+  static async importBookmarks(profilePath: string, folderId?: string): Promise<URLExtractionResult> {
+    try {
+      return await invoke<URLExtractionResult>('import_bookmarks', { profilePath, folderId });
+    } catch (error) {
+      console.error('Failed to import bookmarks:', error);
+      throw new Error(`Bookmark import failed: ${error}`);
+    }
+  }
+
   /**
    * Get list of supported platforms
    */
-  static async getSupportedPlatforms(): Promise<Platform[]> {
+  static async getSupportedPlatforms(): Promise<PlatformDescriptor[]> {
     try {
-      const platforms = await invoke<Platform[]>('get_supported_platforms');
+      const platforms = await invoke<PlatformDescriptor[]>('get_supported_platforms');
       return platforms;
     } catch (error) {
       console.error('Failed to get supported platforms:', error);
@@ -55,6 +115,18 @@ export class TauriAPI {
     }
   }
 
+  /**
+   * Classify URLs (video, short, live, playlist, channel...) by their shape, without fetching them
+   */
+  static async classifyUrls(urls: string[]): Promise<UrlKind[]> {
+    try {
+      return await invoke<UrlKind[]>('classify_urls', { urls });
+    } catch (error) {
+      console.error('Failed to classify URLs:', error);
+      throw new Error(`URL classification failed: ${error}`);
+    }
+  }
+
   /**
    * Clean URL by removing tracking parameters
    */
@@ -82,12 +154,12 @@ export class TauriAPI {
   }
 
   /**
-   * Get video metadata for a URL
+   * Get video metadata for a URL, served from the metadata cache unless forceRefresh is set
    */
-  static async getVideoMetadata(url: string): Promise<VideoMetadata> {
+  static async getVideoMetadata(url: string, forceRefresh = false): Promise<VideoMetadata> {
     try {
       console.log('Frontend: Calling get_video_metadata with URL:', url);
-      const metadata = await invoke<VideoMetadata>('get_video_metadata', { url });
+      const metadata = await invoke<VideoMetadata>('get_video_metadata', { url, forceRefresh });
       console.log('Frontend: Received metadata:', metadata);
       return metadata;
     } catch (error) {
@@ -106,7 +178,14 @@ export class TauriAPI {
     format: string,
     outputDir: string,
     convertFormat?: ConversionFormat,
-    keepOriginal?: boolean
+    keepOriginal?: boolean,
+    clips?: ClipOptions,
+    splitChapters?: boolean,
+    writeThumbnail?: boolean,
+    embedThumbnail?: boolean,
+    sidecars?: SidecarFormat[],
+    credentialProfile?: string,
+    keepUnsplit?: boolean
   ): Promise<void> {
     try {
       const params = {
@@ -117,6 +196,13 @@ export class TauriAPI {
         outputDir: outputDir,
         convert_format: convertFormat,
         keep_original: keepOriginal,
+        clips,
+        split_chapters: splitChapters,
+        write_thumbnail: writeThumbnail,
+        embed_thumbnail: embedThumbnail,
+        sidecars,
+        credential_profile: credentialProfile,
+        keep_unsplit: keepUnsplit,
       };
       
       console.log('=== TAURI API: Starting download with params ===');
@@ -131,6 +217,18 @@ export class TauriAPI {
     }
   }
 
+  /**
+   * Rewrite JSON/NFO/XMP sidecars for files that were downloaded earlier
+   */
+  static async regenerateSidecars(targets: SidecarTarget[], formats: SidecarFormat[]): Promise<string[]> {
+    try {
+      return await invoke<string[]>('regenerate_sidecars', { targets, formats });
+    } catch (error) {
+      console.error('Failed to regenerate sidecars:', error);
+      throw new Error(`Sidecar regeneration failed: ${error}`);
+    }
+  }
+
   /**
    * Cancel a download
    */
@@ -221,17 +319,65 @@ export class TauriAPI {
   }
 
   /**
-   * Extract individual video URLs from a playlist
+   * Fetch metadata for a batch of URLs in the background; onResult is called for each URL as it finishes.
+   * Resolves with the number of URLs that got metadata
+   */
+  static async prefetchMetadata(urls: string[], onResult: (result: PrefetchResult) => void): Promise<number> {
+    const batchId = `${Date.now()}-${Math.random().toString(36).slice(2)}`;
+    const unlisten = await listen('metadata-prefetched', (event: any) => {
+      if (event.payload?.batch_id === batchId) {
+        onResult(event.payload as PrefetchResult);
+      }
+    });
+
+    try {
+      console.log(`Frontend: Calling prefetch_metadata with ${urls.length} URLs`);
+      return await invoke<number>('prefetch_metadata', { batchId, urls });
+    } catch (error) {
+      console.error('Failed to prefetch metadata:', error);
+      throw new Error(`Metadata prefetch failed: ${error}`);
+    } finally {
+      unlisten();
+    }
+  }
+
+  /**
+   * Stream the entries of a playlist or channel; onEntries is called with each batch as it arrives.
+   * Resolves with the total number of entries
    */
-  static async extractPlaylistVideos(playlistUrl: string): Promise<string[]> {
+  static async expandPlaylist(
+    url: string,
+    options: PlaylistOptions | undefined,
+    onEntries: (entries: PlaylistEntry[]) => void
+  ): Promise<number> {
+    const expansionId = `${Date.now()}-${Math.random().toString(36).slice(2)}`;
+    const unlisten = await listen('playlist-entries', (event: any) => {
+      if (event.payload?.expansion_id === expansionId) {
+        onEntries(event.payload.entries as PlaylistEntry[]);
+      }
+    });
+
     try {
-      console.log('Frontend: Calling extract_playlist_videos with URL:', playlistUrl);
-      const videoUrls = await invoke<string[]>('extract_playlist_videos', { playlistUrl: playlistUrl });
-      console.log('Frontend: Received video URLs:', videoUrls);
-      return videoUrls;
+      console.log('Frontend: Calling expand_playlist with URL:', url);
+      return await invoke<number>('expand_playlist', { expansionId, url, options });
     } catch (error) {
-      console.error('Failed to extract playlist videos:', error);
-      throw new Error(`Playlist extraction failed: ${error}`);
+      console.error('Failed to expand playlist:', error);
+      throw new Error(`Playlist expansion failed: ${error}`);
+    } finally {
+      unlisten();
+    }
+  }
+
+  /**
+   * Show which entries of a playlist match the filters, without queueing anything
+   */
+  static async previewPlaylist(url: string, options?: PlaylistOptions): Promise<PlaylistPreview> {
+    try {
+      console.log('Frontend: Calling preview_playlist with URL:', url);
+      return await invoke<PlaylistPreview>('preview_playlist', { url, options });
+    } catch (error) {
+      console.error('Failed to preview playlist:', error);
+      throw new Error(`Playlist preview failed: ${error}`);
     }
   }
 
@@ -369,34 +515,186 @@ export class TauriAPI {
   }
 
   /**
-   * Convert a video file to a different format
+   * Convert a video file to a different format. Returns every file written, which is
+   * one per clip range when clips are extracted to separate files
    */
   static async convertVideoFile(
     id: string,
     inputFile: string,
     outputFile: string,
     format: ConversionFormat,
-    keepOriginal?: boolean
-  ): Promise<void> {
+    keepOriginal?: boolean,
+    clips?: ClipOptions
+  ): Promise<string[]> {
     try {
       console.log('=== TAURI API: Starting video conversion ===');
-      console.log('Conversion params:', { id, inputFile, outputFile, format, keepOriginal });
+      console.log('Conversion params:', { id, inputFile, outputFile, format, keepOriginal, clips });
       
-      await invoke('convert_video_file', {
+      const outputFiles = await invoke<string[]>('convert_video_file', {
         id,
         inputFile,
         outputFile,
         format,
         keepOriginal,
+        clips,
       });
       
-      console.log('=== TAURI API: Video conversion started successfully ===');
+      console.log('=== TAURI API: Video conversion finished ===', outputFiles);
+      return outputFiles;
     } catch (error) {
       console.error('Failed to start video conversion:', error);
       throw new Error(`Video conversion failed: ${error}`);
     }
   }
 
+  /**
+   * Export chapters as an EDL or CSV file for editors
+   */
+  static async exportChapters(
+    chapters: Chapter[],
+    format: 'edl' | 'csv',
+    outputPath: string,
+    title?: string,
+    frameRate?: number
+  ): Promise<string> {
+    try {
+      return await invoke<string>('export_chapters', { chapters, format, outputPath, title, frameRate });
+    } catch (error) {
+      console.error('Failed to export chapters:', error);
+      throw new Error(`Chapter export failed: ${error}`);
+    }
+  }
+
+  /**
+   * Resolve a remote thumbnail URL to the backend's cached, resized copy
+   */
+  static getThumbnailSrc(url?: string): string | undefined {
+    if (!url || !/^https?:\/\//.test(url) || !isTauriEnvironment()) {
+      return url;
+    }
+    return convertFileSrc(url, 'thumbnail');
+  }
+
+  /**
+   * Clear the on-disk thumbnail cache
+   */
+  static async clearThumbnailCache(): Promise<void> {
+    try {
+      await invoke('clear_thumbnail_cache');
+    } catch (error) {
+      console.error('Failed to clear thumbnail cache:', error);
+      throw new Error(`Failed to clear thumbnail cache: ${error}`);
+    }
+  }
+
+  /**
+   * Clear the on-disk metadata cache, returning how many entries were removed
+   */
+  static async clearMetadataCache(): Promise<number> {
+    try {
+      return await invoke<number>('clear_metadata_cache');
+    } catch (error) {
+      console.error('Failed to clear metadata cache:', error);
+      throw new Error(`Failed to clear metadata cache: ${error}`);
+    }
+  }
+
+  /**
+   * Credential profiles for sites that need a login or cookies
+   */
+  static async listCredentialProfiles(): Promise<CredentialProfile[]> {
+    try {
+      return await invoke<CredentialProfile[]>('list_credential_profiles');
+    } catch (error) {
+      console.error('Failed to list credential profiles:', error);
+      throw new Error(`Failed to list credential profiles: ${error}`);
+    }
+  }
+
+  /**
+   * Create or update a profile. Leave secret undefined to keep a stored password
+   */
+  static async saveCredentialProfile(profile: CredentialProfile, secret?: string): Promise<CredentialProfile> {
+    try {
+      return await invoke<CredentialProfile>('save_credential_profile', { profile, secret });
+    } catch (error) {
+      console.error('Failed to save credential profile:', error);
+      throw new Error(`Failed to save credential profile: ${error}`);
+    }
+  }
+
+  static async deleteCredentialProfile(id: string): Promise<void> {
+    try {
+      await invoke('delete_credential_profile', { id });
+    } catch (error) {
+      console.error('Failed to delete credential profile:', error);
+      throw new Error(`Failed to delete credential profile: ${error}`);
+    }
+  }
+
+  /**
+   * Options applied to every yt-dlp run
+   */
+  static async getYtDlpProfile(): Promise<YtDlpProfile> {
+    try {
+      return await invoke<YtDlpProfile>('get_ytdlp_profile');
+    } catch (error) {
+      console.error('Failed to get yt-dlp profile:', error);
+      throw new Error(`Failed to get yt-dlp profile: ${error}`);
+    }
+  }
+
+  static async saveYtDlpProfile(profile: YtDlpProfile): Promise<YtDlpProfile> {
+    try {
+      return await invoke<YtDlpProfile>('save_ytdlp_profile', { profile });
+    } catch (error) {
+      console.error('Failed to save yt-dlp profile:', error);
+      throw new Error(`Failed to save yt-dlp profile: ${error}`);
+    }
+  }
+
+  static async resetYtDlpProfile(): Promise<YtDlpProfile> {
+    try {
+      return await invoke<YtDlpProfile>('reset_ytdlp_profile');
+    } catch (error) {
+      console.error('Failed to reset yt-dlp profile:', error);
+      throw new Error(`Failed to reset yt-dlp profile: ${error}`);
+    }
+  }
+
+  /**
+   * Proxy settings applied to yt-dlp, FFmpeg and the app's own requests
+   */
+  static async getProxySettings(): Promise<ProxySettings> {
+    try {
+      return await invoke<ProxySettings>('get_proxy_settings');
+    } catch (error) {
+      console.error('Failed to get proxy settings:', error);
+      throw new Error(`Failed to get proxy settings: ${error}`);
+    }
+  }
+
+  static async saveProxySettings(settings: ProxySettings): Promise<ProxySettings> {
+    try {
+      return await invoke<ProxySettings>('save_proxy_settings', { settings });
+    } catch (error) {
+      console.error('Failed to save proxy settings:', error);
+      throw new Error(`Failed to save proxy settings: ${error}`);
+    }
+  }
+
+  /**
+   * Fetch a test URL through one proxy, without saving it
+   */
+  static async testProxy(proxy: ProxyServer, testUrl?: string): Promise<ProxyTestResult> {
+    try {
+      return await invoke<ProxyTestResult>('test_proxy', { proxy, testUrl });
+    } catch (error) {
+      console.error('Proxy test failed:', error);
+      throw new Error(`Proxy test failed: ${error}`);
+    }
+  }
+
   /**
    * Open download folder for a specific download
    */
@@ -4,11 +4,32 @@ export interface ExtractedUrl {
   title?: string;
   is_valid: boolean;
   original_text: string;
-  is_playlist: boolean;
+  is_playlist: boolean; // any kind with several videos
+  kind: UrlKind;
   playlist_count?: number;
+  video_key?: VideoKey; // same for every URL variant of one video
+  start_time?: number; // seconds, taken out of the URL's t= parameter
 }
 
-export enum Platform {
+export interface VideoKey {
+  platform: Platform;
+  video_id: string;
+}
+
+// A folder in a browser profile's bookmarks, as offered for import
+export interface BookmarkFolder {
+  id: string;
+  name: string;
+  path: string; // e.g. "Bookmarks Toolbar/Research"
+  parent_id?: string;
+  bookmark_count: number; // direct children only
+}
+
+// Id of a platform in the backend's registry; 'generic' for sites without an entry
+export type Platform = string;
+
+// Built-in platforms with their own icon and colour
+export enum KnownPlatform {
   YouTube = 'youtube',
   Vimeo = 'vimeo',
   Twitch = 'twitch',
@@ -16,9 +37,28 @@ export enum Platform {
   Instagram = 'instagram',
   Twitter = 'twitter',
   Facebook = 'facebook',
+  Dailymotion = 'dailymotion',
+  Reddit = 'reddit',
+  Bilibili = 'bilibili',
+  SoundCloud = 'soundcloud',
+  Bandcamp = 'bandcamp',
+  Rumble = 'rumble',
+  Kick = 'kick',
+  Bluesky = 'bluesky',
+  Threads = 'threads',
   Generic = 'generic',
 }
 
+export interface PlatformDescriptor {
+  id: Platform;
+  name: string;
+  hosts: string[];
+  short_hosts: string[]; // redirecting hosts, expanded before extraction
+  canonical_url?: string; // watch URL with an {id} placeholder
+  supports_playlists: boolean;
+  supports_channels: boolean;
+}
+
 export interface DownloadItem {
   id: string;
   url: string;
@@ -58,11 +98,22 @@ export enum DownloadStatus {
 
 export enum ConversionFormat {
   H264 = 'h264',
+  HEVC = 'hevc',
   DNxHR = 'dnxhr',
   ProRes = 'prores',
   MP3 = 'mp3',
 }
 
+export type ClipSelection =
+  | { type: 'range'; start: number; end: number }
+  | { type: 'chapter'; title: string };
+
+export interface ClipOptions {
+  selections: ClipSelection[];
+  output?: 'separate' | 'concatenate';
+  seek_mode?: 'accurate' | 'keyframe_copy';
+}
+
 export interface DownloadSettings {
   quality: string;
   format: string;
@@ -96,15 +147,112 @@ export interface ValidationResult {
   };
 }
 
+export type UrlKind = 'video' | 'short' | 'live' | 'premiere' | 'playlist' | 'channel' | 'channel_tab' | 'album' | 'clip' | 'collection';
+
+// Kinds that list several videos, matching UrlKind::is_multi_video in the backend
+export const MULTI_VIDEO_KINDS: UrlKind[] = ['playlist', 'channel', 'channel_tab', 'album', 'collection'];
+
+export type LiveStatus = 'not_live' | 'is_live' | 'is_upcoming' | 'was_live' | 'post_live' | 'unknown';
+
+export type Availability = 'public' | 'unlisted' | 'private' | 'premium_only' | 'subscriber_only' | 'needs_auth' | 'unknown';
+
+export interface SubtitleTrack {
+  ext?: string;
+  url?: string;
+  name?: string;
+}
+
 export interface VideoMetadata {
+  id?: string;
   title: string;
-  duration?: string;
+  duration?: number; // seconds
   uploader?: string;
+  uploader_id?: string;
+  channel?: string;
+  channel_id?: string;
+  channel_url?: string;
   description?: string;
   thumbnail?: string;
   view_count?: number;
-  upload_date?: string;
+  like_count?: number;
+  upload_date?: string; // YYYY-MM-DD
+  release_date?: string; // YYYY-MM-DD
+  timestamp?: string; // RFC 3339
+  tags: string[];
+  categories: string[];
+  live_status?: LiveStatus;
+  availability?: Availability;
+  age_limit?: number;
+  license?: string;
+  webpage_url?: string;
+  extractor_key?: string;
+  playlist_count?: number;
+  url_kind?: UrlKind; // what the URL turned out to be once yt-dlp looked at it
   formats: VideoFormat[];
+  chapters: Chapter[];
+  subtitles: Record<string, SubtitleTrack[]>;
+  automatic_captions: Record<string, SubtitleTrack[]>;
+}
+
+export interface PlaylistOptions {
+  items?: string; // yt-dlp --playlist-items spec, e.g. "1-25,30"
+  reverse?: boolean;
+  date_after?: string; // YYYY-MM-DD
+  date_before?: string; // YYYY-MM-DD
+  filter?: PlaylistFilter;
+}
+
+export type PlaylistFilter =
+  | { type: 'and'; filters: PlaylistFilter[] }
+  | { type: 'or'; filters: PlaylistFilter[] }
+  | { type: 'not'; filter: PlaylistFilter }
+  | { type: 'uploaded_after'; date: string } // YYYY-MM-DD
+  | { type: 'uploaded_before'; date: string } // YYYY-MM-DD
+  | { type: 'min_duration'; seconds: number }
+  | { type: 'max_duration'; seconds: number }
+  | { type: 'keyword'; keyword: string }
+  | { type: 'shorts' }
+  | { type: 'live' };
+
+export interface PlaylistPreview {
+  entries: PlaylistEntry[];
+  uncertain: number; // kept without enough metadata to decide
+  excluded: number;
+  total: number;
+}
+
+export interface PlaylistEntry {
+  index: number;
+  id?: string;
+  url: string;
+  title?: string;
+  duration?: number; // seconds
+  availability?: Availability;
+  live_status?: LiveStatus;
+  upload_date?: string; // YYYY-MM-DD
+  channel?: string;
+  thumbnail?: string;
+  playlist_title?: string;
+}
+
+export interface PrefetchResult {
+  index: number; // position in the submitted batch
+  url: string;
+  metadata?: VideoMetadata;
+  error?: string;
+}
+
+export type SidecarFormat = 'json' | 'nfo' | 'xmp';
+
+export interface SidecarTarget {
+  file_path: string;
+  url: string;
+}
+
+export interface Chapter {
+  start_time: number;
+  end_time: number;
+  title?: string;
 }
 
 export interface VideoFormat {
@@ -138,6 +286,62 @@ export interface DownloadRequest {
   output_dir: string;
   convert_format?: ConversionFormat;
   keep_original: boolean;
+  clips?: ClipOptions;
+  split_chapters: boolean;
+  keep_unsplit?: boolean; // keep the whole file next to its chapter files
+  write_thumbnail: boolean;
+  embed_thumbnail: boolean;
+  sidecars: SidecarFormat[];
+  credential_profile?: string; // falls back to a profile matching the URL's host
+}
+
+export type CredentialSource =
+  | { type: 'cookies_file'; path: string } // Netscape cookies.txt
+  | { type: 'browser'; browser: string; profile?: string }
+  | { type: 'password'; username: string } // password is sent separately and kept in the secret store
+  | { type: 'netrc'; path?: string };
+
+export interface YtDlpProfile {
+  user_agent?: string; // unset keeps yt-dlp's own
+  retries: number;
+  extractor_retries: number;
+  sleep_interval?: number; // seconds
+  max_sleep_interval?: number;
+  verify_tls: boolean;
+  rate_limit?: string; // e.g. "500K", "4.2M"
+  geo_bypass: boolean;
+  geo_bypass_country?: string; // two-letter code
+  extra_args: string[];
+}
+
+export interface ProxyServer {
+  url: string; // http://, https://, socks5:// or socks5h:// with host and port
+  username?: string;
+  password?: string; // only sent when changing it, never returned
+  has_password: boolean;
+}
+
+export interface ProxyRule {
+  hosts: string[];
+  proxy?: ProxyServer; // omitted to connect to these hosts directly
+}
+
+export interface ProxySettings {
+  default_proxy?: ProxyServer;
+  rules: ProxyRule[];
+}
+
+export interface ProxyTestResult {
+  status: number;
+  latency_ms: number;
+}
+
+export interface CredentialProfile {
+  id: string; // empty when creating a profile
+  name: string;
+  hosts: string[];
+  source: CredentialSource;
+  has_secret: boolean;
 }
 
 export interface DuplicateCheckResult {